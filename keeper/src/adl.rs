@@ -0,0 +1,138 @@
+//! Auto-deleveraging candidate ranking
+//!
+//! Mirrors the router's ADL score so counterparties are submitted in the
+//! order the router enforces (highest score first).
+
+use crate::health::Portfolio;
+use solana_sdk::pubkey::Pubkey;
+use std::cmp::Reverse;
+
+/// Price/quantity scale (1e6)
+const SCALE: i128 = 1_000_000;
+
+/// A ranked ADL counterparty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdlCandidate {
+    pub portfolio: Pubkey,
+    pub qty: i64,
+    pub score: u128,
+}
+
+/// ADL score: unrealized profit × leverage
+///
+/// score = profit * notional / equity, matching the router. Positions not
+/// in profit, or portfolios without positive equity, score 0.
+pub fn adl_score(qty: i64, entry_px: i64, mark_px: i64, equity: i128) -> u128 {
+    if qty == 0 || mark_px <= 0 || equity <= 0 {
+        return 0;
+    }
+
+    let profit = (qty as i128 * (mark_px as i128 - entry_px as i128)) / SCALE;
+    if profit <= 0 {
+        return 0;
+    }
+
+    let notional = (qty.unsigned_abs() as u128).saturating_mul(mark_px as u128) / SCALE as u128;
+    (profit as u128).saturating_mul(notional) / equity as u128
+}
+
+/// Rank counterparties for deleveraging a bankrupt position
+///
+/// Keeps portfolios holding the opposite side of `bankrupt_qty` at
+/// (slab, instrument) in profit, sorted by descending score, and returns the
+/// shortest prefix whose positions cover the bankrupt quantity.
+pub fn rank_adl_candidates(
+    portfolios: &[(Pubkey, Portfolio)],
+    slab_idx: u16,
+    instrument_idx: u16,
+    bankrupt_qty: i64,
+    mark_px: i64,
+) -> Vec<AdlCandidate> {
+    let mut candidates: Vec<AdlCandidate> = portfolios
+        .iter()
+        .filter_map(|(key, portfolio)| {
+            let count = (portfolio.exposure_count as usize).min(portfolio.exposures.len());
+            let i = portfolio.exposures[..count]
+                .iter()
+                .position(|&(s, inst, _)| s == slab_idx && inst == instrument_idx)?;
+            let qty = portfolio.exposures[i].2;
+            if qty == 0 || (qty > 0) == (bankrupt_qty > 0) {
+                return None;
+            }
+
+            let entry_px = portfolio.entry_prices.get(i).copied().unwrap_or(0);
+            let score = adl_score(qty, entry_px, mark_px, portfolio.equity);
+            (score > 0).then_some(AdlCandidate {
+                portfolio: *key,
+                qty,
+                score,
+            })
+        })
+        .collect();
+
+    candidates.sort_by_key(|c| Reverse(c.score));
+
+    // Only as many counterparties as needed to close the bankrupt position
+    let mut remaining = bankrupt_qty.unsigned_abs();
+    let mut needed = 0;
+    for candidate in &candidates {
+        if remaining == 0 {
+            break;
+        }
+        remaining = remaining.saturating_sub(candidate.qty.unsigned_abs());
+        needed += 1;
+    }
+    candidates.truncate(needed);
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: i64 = 1_000_000;
+
+    fn portfolio(qty: i64, entry: i64, equity: i64) -> Portfolio {
+        Portfolio {
+            equity: equity as i128,
+            unrealized_pnl: 0,
+            im: 0,
+            mm: 0,
+            exposures: vec![(0, 0, qty)],
+            entry_prices: vec![entry],
+            exposure_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_adl_score_matches_router() {
+        // Short 10 from $120 at mark $100: $200 profit, $1000 notional, $500 equity
+        assert_eq!(adl_score(-10 * S, 120 * S, 100 * S, 500 * S as i128), 400 * S as u128);
+        assert_eq!(adl_score(10 * S, 120 * S, 100 * S, 500 * S as i128), 0);
+        assert_eq!(adl_score(-10 * S, 120 * S, 100 * S, 0), 0);
+    }
+
+    #[test]
+    fn test_rank_filters_sorts_and_truncates() {
+        let low = Pubkey::new_unique();
+        let high = Pubkey::new_unique();
+        let losing = Pubkey::new_unique();
+        let same_side = Pubkey::new_unique();
+        let extra = Pubkey::new_unique();
+
+        let portfolios = vec![
+            (low, portfolio(-4 * S, 110 * S, 500 * S)),
+            (high, portfolio(-8 * S, 120 * S, 250 * S)),
+            (losing, portfolio(-5 * S, 90 * S, 500 * S)),
+            (same_side, portfolio(5 * S, 80 * S, 500 * S)),
+            (extra, portfolio(-S, 101 * S, 1_000 * S)),
+        ];
+
+        // Bankrupt long of 10 needs the two best-ranked shorts
+        let ranked = rank_adl_candidates(&portfolios, 0, 0, 10 * S, 100 * S);
+        let keys: Vec<Pubkey> = ranked.iter().map(|c| c.portfolio).collect();
+        assert_eq!(keys, vec![high, low]);
+        assert!(ranked[0].score >= ranked[1].score);
+    }
+}
@@ -3,13 +3,34 @@
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 
+/// Router `Portfolio` account layout (`#[repr(C)]`, see
+/// programs/router/src/state/portfolio.rs)
+mod layout {
+    /// Exposure slots (MAX_SLABS * MAX_INSTRUMENTS)
+    pub const MAX_EXPOSURES: usize = 256 * 32;
+    /// Size of one `(u16, u16, i64)` exposure entry
+    pub const EXPOSURE_SIZE: usize = 16;
+
+    pub const EQUITY: usize = 64;
+    pub const IM: usize = 80;
+    pub const MM: usize = 96;
+    pub const EXPOSURE_COUNT: usize = 136;
+    pub const UNREALIZED_PNL: usize = 544;
+    pub const EXPOSURES: usize = 560;
+    pub const EXPOSURE_ENTRY_PX: usize = EXPOSURES + MAX_EXPOSURES * EXPOSURE_SIZE;
+    /// End of the entry VWAP array (the rest of the account is not read)
+    pub const MIN_LEN: usize = EXPOSURE_ENTRY_PX + MAX_EXPOSURES * 8;
+}
+
 /// Portfolio state (simplified mirror of on-chain state)
 #[derive(Debug, Clone)]
 pub struct Portfolio {
     pub equity: i128,
+    pub unrealized_pnl: i128,          // mark-to-market PnL counted in equity
     pub im: u128,
     pub mm: u128,
     pub exposures: Vec<(u16, u16, i64)>, // (slab_idx, instrument_idx, qty)
+    pub entry_prices: Vec<i64>,          // entry VWAP per exposure (1e6 scale)
     pub exposure_count: u16,
 }
 
@@ -34,13 +55,14 @@ pub fn calculate_health(
 /// Equity = base_equity + sum(position_pnl)
 /// where position_pnl = qty * (current_price - entry_price) / 1e6
 ///
-/// For v0, we simplify by using mark-to-market:
-/// Equity = base_equity + sum(qty * current_price) / 1e6
+/// Base equity is the on-chain equity less the unrealized PnL the router
+/// marked in at its last refresh, so positions are revalued rather than
+/// counted twice. Exposures without an oracle price contribute nothing.
 pub fn calculate_equity(
     portfolio: &Portfolio,
     oracle_prices: &HashMap<u16, i64>,
 ) -> i128 {
-    let mut equity = portfolio.equity;
+    let mut equity = portfolio.equity - portfolio.unrealized_pnl;
 
     // Add unrealized PnL for each exposure
     for i in 0..portfolio.exposure_count as usize {
@@ -49,15 +71,18 @@ pub fn calculate_equity(
         }
 
         let (_slab_idx, instrument_idx, qty) = portfolio.exposures[i];
+        let entry_price = portfolio.entry_prices.get(i).copied().unwrap_or(0);
 
         // Get oracle price for instrument
-        let price = oracle_prices.get(&instrument_idx).copied().unwrap_or(0);
+        let price = match oracle_prices.get(&instrument_idx) {
+            Some(&price) => price,
+            None => continue,
+        };
 
-        // Calculate notional value (simplified: qty * price / 1e6)
-        // In production, this would account for entry price
-        let notional = (qty as i128 * price as i128) / 1_000_000;
+        // Unrealized PnL against entry VWAP
+        let unrealized = (qty as i128 * (price as i128 - entry_price as i128)) / 1_000_000;
 
-        equity += notional;
+        equity += unrealized;
     }
 
     equity
@@ -77,24 +102,61 @@ pub fn calculate_mm(
 }
 
 /// Parse portfolio from account data
+///
+/// Reads margin state, exposures and their entry VWAPs at the router's
+/// `Portfolio` field offsets.
 pub fn parse_portfolio(data: &[u8]) -> Result<Portfolio> {
-    // This is a simplified parser for v0
-    // In production, this would use proper deserialization
-
-    if data.len() < 1024 {
+    if data.len() < layout::MIN_LEN {
         anyhow::bail!("Portfolio account data too small");
     }
 
-    // For v0 stub, return dummy portfolio
+    let exposure_count = read_u16(data, layout::EXPOSURE_COUNT)?;
+    if exposure_count as usize > layout::MAX_EXPOSURES {
+        anyhow::bail!("Portfolio exposure count out of range: {}", exposure_count);
+    }
+
+    let mut exposures = Vec::with_capacity(exposure_count as usize);
+    let mut entry_prices = Vec::with_capacity(exposure_count as usize);
+    for i in 0..exposure_count as usize {
+        let offset = layout::EXPOSURES + i * layout::EXPOSURE_SIZE;
+        exposures.push((
+            read_u16(data, offset)?,
+            read_u16(data, offset + 2)?,
+            read_i64(data, offset + 8)?,
+        ));
+        entry_prices.push(read_i64(data, layout::EXPOSURE_ENTRY_PX + i * 8)?);
+    }
+
     Ok(Portfolio {
-        equity: 0,
-        im: 0,
-        mm: 0,
-        exposures: Vec::new(),
-        exposure_count: 0,
+        equity: read_i128(data, layout::EQUITY)?,
+        unrealized_pnl: read_i128(data, layout::UNREALIZED_PNL)?,
+        im: read_i128(data, layout::IM)? as u128,
+        mm: read_i128(data, layout::MM)? as u128,
+        exposures,
+        entry_prices,
+        exposure_count,
     })
 }
 
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    let bytes = data
+        .get(offset..offset + N)
+        .with_context(|| format!("Portfolio field at {} out of bounds", offset))?;
+    Ok(bytes.try_into()?)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_i128(data: &[u8], offset: usize) -> Result<i128> {
+    Ok(i128::from_le_bytes(read_bytes(data, offset)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,9 +165,11 @@ mod tests {
     fn test_calculate_health_below_mm() {
         let portfolio = Portfolio {
             equity: 95_000_000, // $95
+            unrealized_pnl: 0,
             im: 110_000_000,
             mm: 100_000_000,    // $100
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -120,9 +184,11 @@ mod tests {
     fn test_calculate_health_in_preliq_zone() {
         let portfolio = Portfolio {
             equity: 105_000_000, // $105
+            unrealized_pnl: 0,
             im: 110_000_000,
             mm: 100_000_000,     // $100
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -139,14 +205,19 @@ mod tests {
 
     #[test]
     fn test_calculate_equity_with_positions() {
-        let mut portfolio = Portfolio {
+        let portfolio = Portfolio {
             equity: 100_000_000, // $100 base
+            unrealized_pnl: 0,
             im: 110_000_000,
             mm: 100_000_000,
             exposures: vec![
                 (0, 0, 10_000_000),  // Long 10 units at instrument 0
                 (1, 1, -5_000_000),  // Short 5 units at instrument 1
             ],
+            entry_prices: vec![
+                45_000_000,  // Long entered at $45
+                104_000_000, // Short entered at $104
+            ],
             exposure_count: 2,
         };
 
@@ -157,19 +228,21 @@ mod tests {
         let equity = calculate_equity(&portfolio, &oracle_prices);
 
         // Base equity: $100
-        // Long position: 10 * $50 / 1e6 = $500
-        // Short position: -5 * $100 / 1e6 = -$500
-        // Total: $100 + $500 - $500 = $100
-        assert_eq!(equity, 100_000_000);
+        // Long position: 10 * ($50 - $45) = $50
+        // Short position: -5 * ($100 - $104) = $20
+        // Total: $100 + $50 + $20 = $170
+        assert_eq!(equity, 170_000_000);
     }
 
     #[test]
     fn test_calculate_equity_no_positions() {
         let portfolio = Portfolio {
             equity: 100_000_000,
+            unrealized_pnl: 0,
             im: 110_000_000,
             mm: 100_000_000,
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -183,9 +256,11 @@ mod tests {
     fn test_calculate_mm() {
         let portfolio = Portfolio {
             equity: 100_000_000,
+            unrealized_pnl: 0,
             im: 110_000_000,
             mm: 90_000_000,
             exposures: vec![],
+            entry_prices: vec![],
             exposure_count: 0,
         };
 
@@ -194,4 +269,29 @@ mod tests {
 
         assert_eq!(mm, 90_000_000);
     }
+
+    #[test]
+    fn test_parse_portfolio_reads_entry_prices() {
+        let mut data = vec![0u8; layout::MIN_LEN];
+        data[layout::EQUITY..layout::EQUITY + 16].copy_from_slice(&150_000_000i128.to_le_bytes());
+        data[layout::UNREALIZED_PNL..layout::UNREALIZED_PNL + 16].copy_from_slice(&50_000_000i128.to_le_bytes());
+        data[layout::MM..layout::MM + 16].copy_from_slice(&90_000_000u128.to_le_bytes());
+        data[layout::EXPOSURE_COUNT..layout::EXPOSURE_COUNT + 2].copy_from_slice(&1u16.to_le_bytes());
+        data[layout::EXPOSURES..layout::EXPOSURES + 2].copy_from_slice(&3u16.to_le_bytes());
+        data[layout::EXPOSURES + 2..layout::EXPOSURES + 4].copy_from_slice(&1u16.to_le_bytes());
+        data[layout::EXPOSURES + 8..layout::EXPOSURES + 16].copy_from_slice(&10_000_000i64.to_le_bytes());
+        data[layout::EXPOSURE_ENTRY_PX..layout::EXPOSURE_ENTRY_PX + 8].copy_from_slice(&45_000_000i64.to_le_bytes());
+
+        let portfolio = parse_portfolio(&data).unwrap();
+        assert_eq!(portfolio.exposures, vec![(3, 1, 10_000_000)]);
+        assert_eq!(portfolio.entry_prices, vec![45_000_000]);
+        assert_eq!(portfolio.mm, 90_000_000);
+
+        // Router marked at $50 ($50 unrealized); revalued at $48 it is $30
+        let mut oracle_prices = HashMap::new();
+        oracle_prices.insert(1, 48_000_000);
+        assert_eq!(calculate_equity(&portfolio, &oracle_prices), 130_000_000);
+
+        assert!(parse_portfolio(&data[..layout::MIN_LEN - 1]).is_err());
+    }
 }
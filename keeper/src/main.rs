@@ -3,6 +3,7 @@
 //! Off-chain service that monitors portfolio health and triggers liquidations
 //! for undercollateralized users.
 
+mod adl;
 mod config;
 mod health;
 mod priority_queue;
@@ -9,10 +9,37 @@ use solana_sdk::{
     transaction::Transaction,
 };
 
+/// SPL Token program ID
+const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Accounts the router settles insurance fees and payouts through
+#[derive(Debug, Clone, Copy)]
+pub struct InsuranceAccounts {
+    /// Insurance vault PDA (["insurance", mint])
+    pub insurance_vault: Pubkey,
+    /// Collateral vault token account
+    pub vault_token_account: Pubkey,
+    /// Insurance vault token account
+    pub insurance_token_account: Pubkey,
+}
+
+impl InsuranceAccounts {
+    /// Trailing account metas, in router order
+    fn metas(&self) -> [AccountMeta; 4] {
+        [
+            AccountMeta::new(self.insurance_vault, false),
+            AccountMeta::new(self.vault_token_account, false),
+            AccountMeta::new(self.insurance_token_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ]
+    }
+}
+
 /// Build liquidate_user instruction
 ///
 /// This constructs the liquidate_user instruction that the keeper
-/// will submit to liquidate undercollateralized portfolios.
+/// will submit to liquidate undercollateralized portfolios. The keeper's
+/// own portfolio receives its share of the liquidation penalty.
 pub fn build_liquidate_instruction(
     router_program: &Pubkey,
     portfolio: &Pubkey,
@@ -20,24 +47,61 @@ pub fn build_liquidate_instruction(
     vault: &Pubkey,
     router_authority: &Pubkey,
     keeper: &Pubkey,
+    keeper_portfolio: &Pubkey,
+    insurance: &InsuranceAccounts,
     is_preliq: bool,
 ) -> Instruction {
     // Instruction discriminator for LiquidateUser
-    let discriminator = 3u8;
+    let discriminator = 5u8;
 
-    // Instruction data: discriminator + is_preliq
+    // Instruction data: discriminator + num_oracles + num_slabs + is_preliq
+    // (the router reads time from the Clock sysvar)
     let mut data = vec![discriminator];
+    data.push(0); // num_oracles
+    data.push(0); // num_slabs
     data.push(if is_preliq { 1 } else { 0 });
 
     // Build account metas
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*portfolio, false),
-        AccountMeta::new_readonly(*registry, false),
+        AccountMeta::new(*registry, false),
         AccountMeta::new(*vault, false),
         AccountMeta::new_readonly(*router_authority, false),
         AccountMeta::new_readonly(*keeper, true),
+        AccountMeta::new(*keeper_portfolio, false),
         // In production, would include oracle accounts, slab accounts, etc.
     ];
+    accounts.extend(insurance.metas());
+
+    Instruction {
+        program_id: *router_program,
+        accounts,
+        data,
+    }
+}
+
+/// Build auto_deleverage instruction
+///
+/// Closes a bankrupt portfolio's position on `slab` against `counterparties`,
+/// which must be ordered by descending ADL score (see `adl::rank_adl_candidates`).
+pub fn build_adl_instruction(
+    router_program: &Pubkey,
+    bankrupt_portfolio: &Pubkey,
+    registry: &Pubkey,
+    slab: &Pubkey,
+    keeper: &Pubkey,
+    counterparties: &[Pubkey],
+) -> Instruction {
+    // Instruction discriminator for AutoDeleverage (no further data)
+    let data = vec![8u8];
+
+    let mut accounts = vec![
+        AccountMeta::new(*bankrupt_portfolio, false),
+        AccountMeta::new(*registry, false),
+        AccountMeta::new_readonly(*slab, false),
+        AccountMeta::new_readonly(*keeper, true),
+    ];
+    accounts.extend(counterparties.iter().map(|c| AccountMeta::new(*c, false)));
 
     Instruction {
         program_id: *router_program,
@@ -54,6 +118,8 @@ pub fn build_liquidation_transaction(
     vault: &Pubkey,
     router_authority: &Pubkey,
     keeper: &Keypair,
+    keeper_portfolio: &Pubkey,
+    insurance: &InsuranceAccounts,
     is_preliq: bool,
     recent_blockhash: solana_sdk::hash::Hash,
 ) -> Result<Transaction> {
@@ -64,6 +130,8 @@ pub fn build_liquidation_transaction(
         vault,
         router_authority,
         &keeper.pubkey(),
+        keeper_portfolio,
+        insurance,
         is_preliq,
     );
 
@@ -81,6 +149,14 @@ pub fn build_liquidation_transaction(
 mod tests {
     use super::*;
 
+    fn insurance_accounts() -> InsuranceAccounts {
+        InsuranceAccounts {
+            insurance_vault: Pubkey::new_unique(),
+            vault_token_account: Pubkey::new_unique(),
+            insurance_token_account: Pubkey::new_unique(),
+        }
+    }
+
     #[test]
     fn test_build_liquidate_instruction() {
         let router_program = Pubkey::new_unique();
@@ -89,6 +165,8 @@ mod tests {
         let vault = Pubkey::new_unique();
         let router_authority = Pubkey::new_unique();
         let keeper = Pubkey::new_unique();
+        let keeper_portfolio = Pubkey::new_unique();
+        let insurance = insurance_accounts();
 
         let ix = build_liquidate_instruction(
             &router_program,
@@ -97,13 +175,19 @@ mod tests {
             &vault,
             &router_authority,
             &keeper,
+            &keeper_portfolio,
+            &insurance,
             false,
         );
 
         assert_eq!(ix.program_id, router_program);
-        assert_eq!(ix.data[0], 3); // LiquidateUser discriminator
-        assert_eq!(ix.data[1], 0); // is_preliq = false
-        assert_eq!(ix.accounts.len(), 5);
+        assert_eq!(ix.data[0], 5); // LiquidateUser discriminator
+        assert_eq!(ix.data[3], 0); // is_preliq = false
+        assert_eq!(ix.data.len(), 4);
+        assert_eq!(ix.accounts.len(), 10);
+        assert_eq!(ix.accounts[5].pubkey, keeper_portfolio);
+        assert_eq!(ix.accounts[6].pubkey, insurance.insurance_vault);
+        assert_eq!(ix.accounts[9].pubkey, TOKEN_PROGRAM_ID);
     }
 
     #[test]
@@ -114,6 +198,8 @@ mod tests {
         let vault = Pubkey::new_unique();
         let router_authority = Pubkey::new_unique();
         let keeper = Pubkey::new_unique();
+        let keeper_portfolio = Pubkey::new_unique();
+        let insurance = insurance_accounts();
 
         let ix = build_liquidate_instruction(
             &router_program,
@@ -122,9 +208,29 @@ mod tests {
             &vault,
             &router_authority,
             &keeper,
+            &keeper_portfolio,
+            &insurance,
             true,
         );
 
-        assert_eq!(ix.data[1], 1); // is_preliq = true
+        assert_eq!(ix.data[3], 1); // is_preliq = true
+    }
+
+    #[test]
+    fn test_build_adl_instruction() {
+        let router_program = Pubkey::new_unique();
+        let bankrupt = Pubkey::new_unique();
+        let registry = Pubkey::new_unique();
+        let slab = Pubkey::new_unique();
+        let keeper = Pubkey::new_unique();
+        let counterparties = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let ix = build_adl_instruction(&router_program, &bankrupt, &registry, &slab, &keeper, &counterparties);
+
+        assert_eq!(ix.data, vec![8]); // AutoDeleverage discriminator
+        assert_eq!(ix.accounts.len(), 6);
+        assert!(ix.accounts[3].is_signer);
+        assert!(ix.accounts[5].is_writable);
+        assert_eq!(ix.accounts[5].pubkey, counterparties[1]);
     }
 }
@@ -1,7 +1,7 @@
 //! AMM instructions - initialize and commit_fill
 
 use crate::{AmmState, math::{quote_buy, quote_sell}};
-use percolator_common::{PercolatorError, Side, SlabHeader, FillReceipt, borrow_account_data_mut};
+use percolator_common::{PercolatorError, Side, SlabHeader, FillReceipt, borrow_account_data_mut, emit, Event};
 use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey, ProgramResult};
 
 /// Initialize a new AMM pool
@@ -172,7 +172,18 @@ pub fn process_commit_fill(
 
     // Write fill receipt
     let receipt = unsafe { borrow_account_data_mut::<FillReceipt>(receipt_account)? };
-    receipt.write(seqno_committed, qty, result.vwap_px, notional, fee);
+    // The pool has no maker side of its own; the LP earns the taker fee
+    receipt.write(seqno_committed, qty, result.vwap_px, notional, fee, 0);
+
+    emit(&Event::Fill {
+        venue: *amm_account.key(),
+        account: amm.header.lp_owner,
+        side: side as u8,
+        qty,
+        price: result.vwap_px,
+        fee,
+        seqno: seqno_committed,
+    });
 
     // Increment seqno (AMM state changed)
     amm.header.increment_seqno();
@@ -30,6 +30,14 @@ pub enum PercolatorError {
     InvalidAmount = 112,
     InsufficientBalance = 113,
     StalePrice = 114,
+    WithdrawalQueued = 115,
+    SlippageExceeded = 116,
+    AdlRankViolation = 117,
+    FundingNotDue = 118,
+    SlabPaused = 119,
+    InsuranceDebtOutstanding = 120,
+    FeeCapExceeded = 121,
+    EscrowCapsOutstanding = 122,
 
     // Slab errors (200-299)
     InvalidInstrument = 200,
@@ -46,6 +54,7 @@ pub enum PercolatorError {
     InvalidQuantity = 211,
     PoolFull = 212,
     SeqnoMismatch = 213,
+    HoldLimitReached = 214,
 
     // Matching errors (300-399)
     InvalidSide = 300,
@@ -54,6 +63,7 @@ pub enum PercolatorError {
     InvalidOrderState = 303,
     BookCorrupted = 304,
     ReservedQtyExceeded = 305,
+    PostOnlyWouldCross = 306,
 
     // Risk errors (400-499)
     InsufficientMargin = 400,
@@ -0,0 +1,430 @@
+//! Binary event log - versioned schema shared by router, slab and AMM
+//!
+//! Events are emitted with `sol_log_data` as a single slice:
+//! `[EVENT_VERSION][EventKind][fields...]`, all integers little-endian and
+//! pubkeys as raw 32 bytes. The runtime prints them as
+//! `Program data: <base64>` log lines, which `decode_log_line` turns back
+//! into typed events for indexers. New kinds may be appended; existing
+//! layouts only change with a version bump.
+
+use pinocchio::pubkey::Pubkey;
+
+/// Current event schema version
+pub const EVENT_VERSION: u8 = 1;
+
+/// Upper bound on an encoded event (bytes)
+pub const MAX_EVENT_LEN: usize = 128;
+
+/// Log line prefix written by the runtime for `sol_log_data`
+pub const LOG_DATA_PREFIX: &str = "Program data: ";
+
+/// Event discriminator
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Fill = 0,
+    Deposit = 1,
+    Withdraw = 2,
+    LiquidationStart = 3,
+    LiquidationEnd = 4,
+    InsurancePayout = 5,
+    Haircut = 6,
+    Funding = 7,
+}
+
+/// Typed event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A fill on a venue. `account` is the portfolio owner when emitted by
+    /// the router and the LP owner when emitted by the slab or AMM; `side`
+    /// is always the taker's (0 = buy, 1 = sell).
+    Fill {
+        venue: Pubkey,
+        account: Pubkey,
+        side: u8,
+        qty: i64,
+        price: i64,
+        fee: i64,
+        seqno: u32,
+    },
+    /// Collateral deposited into a portfolio
+    Deposit { account: Pubkey, amount: u128 },
+    /// Collateral withdrawn (`queued` is held back by exit buckets)
+    Withdraw { account: Pubkey, amount: u128, queued: u128 },
+    /// Liquidation began; fills until `LiquidationEnd` belong to it
+    LiquidationStart {
+        account: Pubkey,
+        keeper: Pubkey,
+        equity: i128,
+        mm: u128,
+        is_preliq: bool,
+    },
+    /// Liquidation finished
+    LiquidationEnd {
+        account: Pubkey,
+        notional: u128,
+        penalty: u128,
+        bad_debt: u128,
+    },
+    /// Insurance fund covered bad debt
+    InsurancePayout { account: Pubkey, amount: u128, uncovered: u128 },
+    /// Uncovered loss socialized across positive PnL
+    Haircut {
+        event_id: u64,
+        shortfall: u128,
+        socialized: u128,
+        pnl_index: i128,
+    },
+    /// Funding accrued for an instrument
+    Funding {
+        instrument_idx: u16,
+        rate_bps: i64,
+        cum_funding: i64,
+        ts: u64,
+    },
+}
+
+/// Little-endian writer over a fixed buffer
+struct Writer<'a> {
+    buf: &'a mut [u8; MAX_EVENT_LEN],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+/// Little-endian reader that fails on short input
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.data.len() < N {
+            return None;
+        }
+        let (head, rest) = self.data.split_at(N);
+        self.data = rest;
+        let mut out = [0u8; N];
+        out.copy_from_slice(head);
+        Some(out)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+    fn i64(&mut self) -> Option<i64> {
+        self.take().map(i64::from_le_bytes)
+    }
+    fn u128(&mut self) -> Option<u128> {
+        self.take().map(u128::from_le_bytes)
+    }
+    fn i128(&mut self) -> Option<i128> {
+        self.take().map(i128::from_le_bytes)
+    }
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.take::<32>()
+    }
+}
+
+impl Event {
+    /// Event discriminator
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Fill { .. } => EventKind::Fill,
+            Event::Deposit { .. } => EventKind::Deposit,
+            Event::Withdraw { .. } => EventKind::Withdraw,
+            Event::LiquidationStart { .. } => EventKind::LiquidationStart,
+            Event::LiquidationEnd { .. } => EventKind::LiquidationEnd,
+            Event::InsurancePayout { .. } => EventKind::InsurancePayout,
+            Event::Haircut { .. } => EventKind::Haircut,
+            Event::Funding { .. } => EventKind::Funding,
+        }
+    }
+
+    /// Encode into `buf`, returning the encoded length
+    pub fn encode(&self, buf: &mut [u8; MAX_EVENT_LEN]) -> usize {
+        let mut w = Writer { buf, len: 0 };
+        w.put(&[EVENT_VERSION, self.kind() as u8]);
+
+        match *self {
+            Event::Fill { venue, account, side, qty, price, fee, seqno } => {
+                w.put(&venue);
+                w.put(&account);
+                w.put(&[side]);
+                w.put(&qty.to_le_bytes());
+                w.put(&price.to_le_bytes());
+                w.put(&fee.to_le_bytes());
+                w.put(&seqno.to_le_bytes());
+            }
+            Event::Deposit { account, amount } => {
+                w.put(&account);
+                w.put(&amount.to_le_bytes());
+            }
+            Event::Withdraw { account, amount, queued } => {
+                w.put(&account);
+                w.put(&amount.to_le_bytes());
+                w.put(&queued.to_le_bytes());
+            }
+            Event::LiquidationStart { account, keeper, equity, mm, is_preliq } => {
+                w.put(&account);
+                w.put(&keeper);
+                w.put(&equity.to_le_bytes());
+                w.put(&mm.to_le_bytes());
+                w.put(&[is_preliq as u8]);
+            }
+            Event::LiquidationEnd { account, notional, penalty, bad_debt } => {
+                w.put(&account);
+                w.put(&notional.to_le_bytes());
+                w.put(&penalty.to_le_bytes());
+                w.put(&bad_debt.to_le_bytes());
+            }
+            Event::InsurancePayout { account, amount, uncovered } => {
+                w.put(&account);
+                w.put(&amount.to_le_bytes());
+                w.put(&uncovered.to_le_bytes());
+            }
+            Event::Haircut { event_id, shortfall, socialized, pnl_index } => {
+                w.put(&event_id.to_le_bytes());
+                w.put(&shortfall.to_le_bytes());
+                w.put(&socialized.to_le_bytes());
+                w.put(&pnl_index.to_le_bytes());
+            }
+            Event::Funding { instrument_idx, rate_bps, cum_funding, ts } => {
+                w.put(&instrument_idx.to_le_bytes());
+                w.put(&rate_bps.to_le_bytes());
+                w.put(&cum_funding.to_le_bytes());
+                w.put(&ts.to_le_bytes());
+            }
+        }
+
+        w.len
+    }
+
+    /// Decode an encoded event
+    ///
+    /// Returns `None` for an unknown version or kind, or truncated data.
+    pub fn decode(data: &[u8]) -> Option<Event> {
+        let mut r = Reader { data };
+        if r.u8()? != EVENT_VERSION {
+            return None;
+        }
+
+        let event = match r.u8()? {
+            0 => Event::Fill {
+                venue: r.pubkey()?,
+                account: r.pubkey()?,
+                side: r.u8()?,
+                qty: r.i64()?,
+                price: r.i64()?,
+                fee: r.i64()?,
+                seqno: r.u32()?,
+            },
+            1 => Event::Deposit { account: r.pubkey()?, amount: r.u128()? },
+            2 => Event::Withdraw {
+                account: r.pubkey()?,
+                amount: r.u128()?,
+                queued: r.u128()?,
+            },
+            3 => Event::LiquidationStart {
+                account: r.pubkey()?,
+                keeper: r.pubkey()?,
+                equity: r.i128()?,
+                mm: r.u128()?,
+                is_preliq: r.u8()? != 0,
+            },
+            4 => Event::LiquidationEnd {
+                account: r.pubkey()?,
+                notional: r.u128()?,
+                penalty: r.u128()?,
+                bad_debt: r.u128()?,
+            },
+            5 => Event::InsurancePayout {
+                account: r.pubkey()?,
+                amount: r.u128()?,
+                uncovered: r.u128()?,
+            },
+            6 => Event::Haircut {
+                event_id: r.u64()?,
+                shortfall: r.u128()?,
+                socialized: r.u128()?,
+                pnl_index: r.i128()?,
+            },
+            7 => Event::Funding {
+                instrument_idx: r.u16()?,
+                rate_bps: r.i64()?,
+                cum_funding: r.i64()?,
+                ts: r.u64()?,
+            },
+            _ => return None,
+        };
+
+        Some(event)
+    }
+}
+
+/// Emit an event to the program log via `sol_log_data`
+pub fn emit(event: &Event) {
+    let mut buf = [0u8; MAX_EVENT_LEN];
+    let len = event.encode(&mut buf);
+    pinocchio::log::sol_log_data(&[&buf[..len]]);
+}
+
+/// Decode a `Program data: <base64>` log line into an event (host side)
+///
+/// Lines that are not event data, or carry an unknown version, yield `None`.
+pub fn decode_log_line(line: &str) -> Option<Event> {
+    let payload = line.trim().strip_prefix(LOG_DATA_PREFIX)?;
+    // Events are logged as a single slice, so only the first field matters
+    let field = payload.split(' ').next()?;
+
+    let mut buf = [0u8; MAX_EVENT_LEN];
+    let len = decode_base64(field.as_bytes(), &mut buf)?;
+    Event::decode(&buf[..len])
+}
+
+/// Decode standard padded base64 into `out`, returning the decoded length
+fn decode_base64(input: &[u8], out: &mut [u8; MAX_EVENT_LEN]) -> Option<usize> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+            b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    if !input.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut len = 0;
+    for chunk in input.chunks(4) {
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if pad > 2 {
+            return None;
+        }
+
+        let mut acc: u32 = 0;
+        for &c in &chunk[..4 - pad] {
+            acc = (acc << 6) | value(c)?;
+        }
+        acc <<= 6 * pad as u32;
+
+        let bytes = acc.to_be_bytes();
+        for &b in &bytes[1..4 - pad] {
+            if len == MAX_EVENT_LEN {
+                return None;
+            }
+            out[len] = b;
+            len += 1;
+        }
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal encoder for building log lines in tests
+    fn encode_base64(data: &[u8], out: &mut [u8; 256]) -> usize {
+        const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut len = 0;
+        for chunk in data.chunks(3) {
+            let mut acc = [0u8; 3];
+            acc[..chunk.len()].copy_from_slice(chunk);
+            let n = ((acc[0] as u32) << 16) | ((acc[1] as u32) << 8) | acc[2] as u32;
+            for i in 0..4 {
+                out[len + i] = if i <= chunk.len() {
+                    TABLE[((n >> (18 - 6 * i)) & 63) as usize]
+                } else {
+                    b'='
+                };
+            }
+            len += 4;
+        }
+        len
+    }
+
+    fn roundtrip(event: Event) {
+        let mut buf = [0u8; MAX_EVENT_LEN];
+        let len = event.encode(&mut buf);
+        assert_eq!(buf[0], EVENT_VERSION);
+        assert_eq!(Event::decode(&buf[..len]), Some(event));
+        assert_eq!(Event::decode(&buf[..len - 1]), None);
+    }
+
+    #[test]
+    fn test_every_event_roundtrips() {
+        roundtrip(Event::Fill {
+            venue: [1; 32],
+            account: [2; 32],
+            side: 1,
+            qty: -5,
+            price: 100_000_000,
+            fee: 20,
+            seqno: 7,
+        });
+        roundtrip(Event::Deposit { account: [3; 32], amount: 1 << 100 });
+        roundtrip(Event::Withdraw { account: [3; 32], amount: 10, queued: 5 });
+        roundtrip(Event::LiquidationStart {
+            account: [4; 32],
+            keeper: [5; 32],
+            equity: -1,
+            mm: 2,
+            is_preliq: true,
+        });
+        roundtrip(Event::LiquidationEnd { account: [4; 32], notional: 1, penalty: 2, bad_debt: 3 });
+        roundtrip(Event::InsurancePayout { account: [4; 32], amount: 9, uncovered: 1 });
+        roundtrip(Event::Haircut { event_id: 3, shortfall: 4, socialized: 5, pnl_index: -6 });
+        roundtrip(Event::Funding { instrument_idx: 2, rate_bps: -50, cum_funding: 12, ts: 99 });
+    }
+
+    #[test]
+    fn test_unknown_version_and_kind_rejected() {
+        let mut buf = [0u8; MAX_EVENT_LEN];
+        let len = Event::Deposit { account: [0; 32], amount: 1 }.encode(&mut buf);
+
+        buf[0] = EVENT_VERSION + 1;
+        assert_eq!(Event::decode(&buf[..len]), None);
+        buf[0] = EVENT_VERSION;
+        buf[1] = 200;
+        assert_eq!(Event::decode(&buf[..len]), None);
+    }
+
+    #[test]
+    fn test_decode_log_line() {
+        let event = Event::Funding { instrument_idx: 1, rate_bps: 25, cum_funding: 500_000, ts: 1_700_000_000 };
+        let mut buf = [0u8; MAX_EVENT_LEN];
+        let len = event.encode(&mut buf);
+        let mut b64 = [0u8; 256];
+        let b64_len = encode_base64(&buf[..len], &mut b64);
+
+        let mut line = [0u8; 300];
+        let prefix = LOG_DATA_PREFIX.as_bytes();
+        line[..prefix.len()].copy_from_slice(prefix);
+        line[prefix.len()..prefix.len() + b64_len].copy_from_slice(&b64[..b64_len]);
+        let line = core::str::from_utf8(&line[..prefix.len() + b64_len]).unwrap();
+
+        assert_eq!(decode_log_line(line), Some(event));
+        assert_eq!(decode_log_line("Program log: CommitFill executed successfully"), None);
+        assert_eq!(decode_log_line("Program data: !!!!"), None);
+    }
+}
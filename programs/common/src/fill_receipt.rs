@@ -17,6 +17,8 @@ pub struct FillReceipt {
     pub notional: i64,
     /// Fee charged (1e6 scale)
     pub fee: i64,
+    /// Maker fee on the same fill (1e6 scale, negative = rebate)
+    pub maker_fee: i64,
     /// Realized PnL delta (optional in v0)
     pub pnl_delta: i64,
 }
@@ -33,6 +35,7 @@ impl FillReceipt {
             vwap_px: 0,
             notional: 0,
             fee: 0,
+            maker_fee: 0,
             pnl_delta: 0,
         }
     }
@@ -45,6 +48,7 @@ impl FillReceipt {
         vwap_px: i64,
         notional: i64,
         fee: i64,
+        maker_fee: i64,
     ) {
         self.used = 1;
         self.seqno_committed = seqno;
@@ -52,6 +56,7 @@ impl FillReceipt {
         self.vwap_px = vwap_px;
         self.notional = notional;
         self.fee = fee;
+        self.maker_fee = maker_fee;
         self.pnl_delta = 0; // Not calculated in v0
     }
 
@@ -82,6 +87,7 @@ mod tests {
             50_000_000_000,      // vwap $50,000
             50_000_000_000,      // notional $50,000
             10_000_000,          // fee $10
+            -2_500_000,          // maker rebate $2.50
         );
 
         assert!(receipt.is_used());
@@ -89,5 +95,6 @@ mod tests {
         assert_eq!(receipt.filled_qty, 1_000_000);
         assert_eq!(receipt.vwap_px, 50_000_000_000);
         assert_eq!(receipt.fee, 10_000_000);
+        assert_eq!(receipt.maker_fee, -2_500_000);
     }
 }
@@ -33,6 +33,8 @@ pub struct SlabHeader {
 
     /// Taker fee (basis points, 1e6 scale)
     pub taker_fee_bps: i64,
+    /// Maker fee (basis points, negative = rebate)
+    pub maker_fee_bps: i64,
 
     /// Byte offset to BookArea (from start of account)
     pub off_book: u32,
@@ -51,6 +53,10 @@ impl SlabHeader {
     pub const MAGIC: &'static [u8; 8] = b"PERP10\0\0";
     pub const VERSION: u32 = 1;
     pub const LEN: usize = core::mem::size_of::<Self>();
+    /// Fingerprint of the layout shared with the router (magic, version,
+    /// header and QuoteCache sizes). Slabs publish it and the router only
+    /// registers slabs built against the same layout.
+    pub const VERSION_HASH: [u8; 32] = Self::layout_hash();
 
     /// Initialize new slab header (v0 minimal)
     pub fn new(
@@ -81,6 +87,7 @@ impl SlabHeader {
             lot: 1_000_000,            // 1.0 lot
             mark_px,
             taker_fee_bps,
+            maker_fee_bps: 0,
             off_book,
             off_quote_cache,
             off_receipt_area,
@@ -89,6 +96,28 @@ impl SlabHeader {
         }
     }
 
+    /// Build the layout fingerprint (const so it is fixed at compile time)
+    const fn layout_hash() -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        let mut i = 0;
+        while i < 8 {
+            hash[i] = Self::MAGIC[i];
+            i += 1;
+        }
+
+        let version = Self::VERSION.to_le_bytes();
+        let header_len = (Self::LEN as u32).to_le_bytes();
+        let cache_len = (crate::quote_cache::QuoteCache::LEN as u32).to_le_bytes();
+        let mut j = 0;
+        while j < 4 {
+            hash[8 + j] = version[j];
+            hash[12 + j] = header_len[j];
+            hash[16 + j] = cache_len[j];
+            j += 1;
+        }
+        hash
+    }
+
     /// Validate magic and version
     pub fn validate(&self) -> bool {
         &self.magic == Self::MAGIC && self.version == Self::VERSION
@@ -122,6 +151,7 @@ mod tests {
         assert_eq!(header.seqno, 0);
         assert_eq!(header.version, 1);
         assert_eq!(header.magic, *SlabHeader::MAGIC);
+        assert_eq!(&SlabHeader::VERSION_HASH[..8], SlabHeader::MAGIC);
     }
 
     #[test]
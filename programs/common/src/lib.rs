@@ -8,6 +8,9 @@ pub mod instruction;
 pub mod header;
 pub mod quote_cache;
 pub mod fill_receipt;
+pub mod reserve_receipt;
+pub mod oracle;
+pub mod event;
 
 #[cfg(test)]
 mod tests;
@@ -20,3 +23,6 @@ pub use instruction::*;
 pub use header::*;
 pub use quote_cache::*;
 pub use fill_receipt::*;
+pub use reserve_receipt::*;
+pub use oracle::*;
+pub use event::*;
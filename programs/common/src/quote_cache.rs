@@ -64,6 +64,15 @@ impl QuoteCache {
         }
     }
 
+    /// Mid of the best bid and ask, if both sides are quoted
+    pub fn mid_px(&self) -> Option<i64> {
+        let (bid, ask) = (self.best_bids[0], self.best_asks[0]);
+        if bid.avail_qty <= 0 || ask.avail_qty <= 0 || bid.px <= 0 || ask.px <= 0 {
+            return None;
+        }
+        Some(bid.px + (ask.px - bid.px) / 2)
+    }
+
     /// Get total available quantity across all bid levels
     pub fn total_bid_qty(&self) -> i64 {
         self.best_bids.iter().map(|l| l.avail_qty).sum()
@@ -107,5 +116,10 @@ mod tests {
         assert_eq!(cache.best_asks[0].px, 50_001_000_000);
         assert_eq!(cache.total_bid_qty(), 3_000_000);
         assert_eq!(cache.total_ask_qty(), 1_500_000);
+        assert_eq!(cache.mid_px(), Some(50_000_500_000));
+
+        // One-sided book has no mid
+        cache.update(2, &bids, &[]);
+        assert_eq!(cache.mid_px(), None);
     }
 }
@@ -0,0 +1,106 @@
+//! Reserve receipt - written by slab reserve for router to read
+
+/// Reserved liquidity summary (1e6 scale)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoldFill {
+    /// Reserved quantity
+    pub qty: i64,
+    /// Volume-weighted average price of the reserved slices
+    pub vwap_px: i64,
+    /// Worst price among the reserved slices
+    pub worst_px: i64,
+    /// Maximum charge on commit: notional plus taker fee
+    pub max_charge: i64,
+}
+
+/// Reserve receipt - summary of a liquidity hold
+/// Router provides an account for the slab to write this
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ReserveReceipt {
+    /// Used flag (1 if written)
+    pub used: u32,
+    /// Header.seqno at time of reserve
+    pub seqno_reserved: u32,
+    /// Hold ID to commit or cancel
+    pub hold_id: u64,
+    /// Reserved quantity (1e6 scale, may be less than requested)
+    pub reserved_qty: i64,
+    /// Volume-weighted average price of the reserved slices (1e6 scale)
+    pub vwap_px: i64,
+    /// Worst price among the reserved slices (1e6 scale)
+    pub worst_px: i64,
+    /// Maximum charge on commit: notional plus taker fee (1e6 scale)
+    pub max_charge: i64,
+    /// Hold expiry (milliseconds)
+    pub expiry_ms: u64,
+}
+
+impl ReserveReceipt {
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Create empty receipt
+    pub fn new() -> Self {
+        Self {
+            used: 0,
+            seqno_reserved: 0,
+            hold_id: 0,
+            reserved_qty: 0,
+            vwap_px: 0,
+            worst_px: 0,
+            max_charge: 0,
+            expiry_ms: 0,
+        }
+    }
+
+    /// Mark as used with hold data
+    pub fn write(&mut self, seqno: u32, hold_id: u64, fill: HoldFill, expiry_ms: u64) {
+        self.used = 1;
+        self.seqno_reserved = seqno;
+        self.hold_id = hold_id;
+        self.reserved_qty = fill.qty;
+        self.vwap_px = fill.vwap_px;
+        self.worst_px = fill.worst_px;
+        self.max_charge = fill.max_charge;
+        self.expiry_ms = expiry_ms;
+    }
+
+    /// Check if receipt was written
+    pub fn is_used(&self) -> bool {
+        self.used == 1
+    }
+}
+
+impl Default for ReserveReceipt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_receipt_write() {
+        let mut receipt = ReserveReceipt::new();
+        assert!(!receipt.is_used());
+
+        let fill = HoldFill {
+            qty: 2_000_000,
+            vwap_px: 100_500_000,
+            worst_px: 101_000_000,
+            max_charge: 201_201_000,
+        };
+        receipt.write(7, 42, fill, 60_000);
+
+        assert!(receipt.is_used());
+        assert_eq!(receipt.seqno_reserved, 7);
+        assert_eq!(receipt.hold_id, 42);
+        assert_eq!(receipt.reserved_qty, 2_000_000);
+        assert_eq!(receipt.vwap_px, 100_500_000);
+        assert_eq!(receipt.worst_px, 101_000_000);
+        assert_eq!(receipt.max_charge, 201_201_000);
+        assert_eq!(receipt.expiry_ms, 60_000);
+    }
+}
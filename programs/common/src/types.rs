@@ -49,6 +49,7 @@ pub enum TimeInForce {
     GTC = 0, // Good till cancel
     IOC = 1, // Immediate or cancel
     FOK = 2, // Fill or kill
+    PostOnly = 3, // Rest only, reject if it would cross
 }
 
 /// Maker class
@@ -67,6 +68,7 @@ pub enum OrderState {
     #[default]
     LIVE = 0,    // Active in book
     PENDING = 1, // Waiting for promotion
+    CANCELLED = 2, // Cancelled, reserved quantity kept until its holds resolve
 }
 
 /// Account state for tracking within slab
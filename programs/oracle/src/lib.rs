@@ -37,3 +37,6 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
 }
 
 pub use state::{PriceOracle, PRICE_ORACLE_SIZE};
+
+/// Program ID slabs require their oracle accounts to be owned by
+pub use percolator_common::ORACLE_PROGRAM_ID as ID;
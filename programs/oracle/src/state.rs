@@ -1,88 +1,14 @@
 //! Oracle state structures
 //!
 //! Minimal price oracle for Surfpool testing. Stores price data for instruments.
+//! The account layout lives in `percolator_common` so the router can read it.
 
-use pinocchio::pubkey::Pubkey;
-
-/// Size of PriceOracle account: 128 bytes
-pub const PRICE_ORACLE_SIZE: usize = 128;
-
-/// Price oracle account state
-///
-/// Stores current price data for an instrument. Similar to Pyth but simplified.
-#[repr(C)]
-#[derive(Clone, Copy)]
-pub struct PriceOracle {
-    /// Magic bytes: "PRCL" + "ORCL" = 0x4C43525044444C52
-    pub magic: u64,
-
-    /// Version (currently 0)
-    pub version: u8,
-
-    /// Bump seed for PDA
-    pub bump: u8,
-
-    /// Padding for alignment
-    pub _padding: [u8; 6],
-
-    /// Authority that can update prices
-    pub authority: Pubkey,
-
-    /// Instrument this oracle is for
-    pub instrument: Pubkey,
-
-    /// Current price (scaled by 1_000_000)
-    pub price: i64,
-
-    /// Last update timestamp (Unix timestamp)
-    pub timestamp: i64,
-
-    /// Price confidence interval (scaled by 1_000_000)
-    pub confidence: i64,
-
-    /// Reserved for future use (24 bytes to reach 128 total)
-    pub _reserved: [u8; 24],
-}
-
-impl PriceOracle {
-    /// Magic bytes for validation
-    pub const MAGIC: &'static [u8; 8] = b"PRCLORCL";
-
-    /// Current version
-    pub const VERSION: u8 = 0;
-
-    /// Create a new price oracle
-    pub fn new(authority: Pubkey, instrument: Pubkey, price: i64, bump: u8) -> Self {
-        Self {
-            magic: u64::from_le_bytes(*Self::MAGIC),
-            version: Self::VERSION,
-            bump,
-            _padding: [0; 6],
-            authority,
-            instrument,
-            price,
-            timestamp: 0,
-            confidence: 0,
-            _reserved: [0; 24],
-        }
-    }
-
-    /// Validate the oracle account
-    pub fn validate(&self) -> bool {
-        self.magic == u64::from_le_bytes(*Self::MAGIC) && self.version == Self::VERSION
-    }
-
-    /// Update the price
-    pub fn update_price(&mut self, price: i64, timestamp: i64, confidence: i64) {
-        self.price = price;
-        self.timestamp = timestamp;
-        self.confidence = confidence;
-    }
-}
+pub use percolator_common::{PriceOracle, PRICE_ORACLE_SIZE};
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pinocchio::pubkey::Pubkey;
 
     #[test]
     fn test_price_oracle_size() {
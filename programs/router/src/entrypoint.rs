@@ -8,9 +8,9 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::instructions::{RouterInstruction, process_deposit, process_withdraw, process_initialize_registry, process_initialize_portfolio, process_execute_cross_slab, process_liquidate_user, process_burn_lp_shares, process_cancel_lp_orders};
-use crate::state::{Vault, Portfolio, SlabRegistry};
-use percolator_common::{PercolatorError, validate_owner, validate_writable, borrow_account_data_mut, InstructionReader};
+use crate::instructions::{RouterInstruction, process_deposit, process_withdraw, WithdrawSource, process_initialize_registry, process_initialize_portfolio, process_execute_cross_slab, process_liquidate_user, process_burn_lp_shares, process_cancel_lp_orders, process_auto_deleverage, process_update_funding, process_register_slab, process_set_slab_status, process_update_slab_params, process_update_risk_params, process_update_insurance_params, process_update_vesting_params, process_update_haircut_caps, process_transfer_governance, process_accept_governance, process_initialize_insurance_vault, process_top_up_insurance, process_withdraw_insurance_surplus, process_set_collateral, process_update_collateral_price, process_pledge_escrow, process_release_escrow, process_issue_cap, process_cap_debit, process_reserve_cross_slab, process_commit_cross_slab, process_cancel_cross_slab, process_update_marks, HoldLeg, SlabSplit, InsuranceAccounts, SlabAccount, SlabRegistration, SlabStatus, RiskParams};
+use crate::state::{Vault, Portfolio, SlabRegistry, InsuranceParams, PnlVestingParams, Escrow, Cap};
+use percolator_common::{PercolatorError, validate_owner, validate_signer, validate_writable, borrow_account_data, borrow_account_data_mut, InstructionReader, PriceOracle, SlabHeader};
 
 entrypoint!(process_instruction);
 
@@ -36,6 +36,30 @@ pub fn process_instruction(
         5 => RouterInstruction::LiquidateUser,
         6 => RouterInstruction::BurnLpShares,
         7 => RouterInstruction::CancelLpOrders,
+        8 => RouterInstruction::AutoDeleverage,
+        9 => RouterInstruction::UpdateFunding,
+        10 => RouterInstruction::RegisterSlab,
+        11 => RouterInstruction::SetSlabStatus,
+        12 => RouterInstruction::UpdateSlabParams,
+        13 => RouterInstruction::UpdateRiskParams,
+        14 => RouterInstruction::UpdateInsuranceParams,
+        15 => RouterInstruction::UpdateVestingParams,
+        16 => RouterInstruction::UpdateHaircutCaps,
+        17 => RouterInstruction::TransferGovernance,
+        18 => RouterInstruction::AcceptGovernance,
+        19 => RouterInstruction::TopUpInsurance,
+        20 => RouterInstruction::WithdrawInsuranceSurplus,
+        21 => RouterInstruction::SetCollateral,
+        22 => RouterInstruction::UpdateCollateralPrice,
+        23 => RouterInstruction::PledgeEscrow,
+        24 => RouterInstruction::ReleaseEscrow,
+        25 => RouterInstruction::IssueCap,
+        26 => RouterInstruction::CapDebit,
+        27 => RouterInstruction::ReserveCrossSlab,
+        28 => RouterInstruction::CommitCrossSlab,
+        29 => RouterInstruction::CancelCrossSlab,
+        30 => RouterInstruction::UpdateMarks,
+        31 => RouterInstruction::InitializeInsuranceVault,
         _ => {
             msg!("Error: Unknown instruction");
             return Err(PercolatorError::InvalidInstruction.into());
@@ -76,6 +100,102 @@ pub fn process_instruction(
             msg!("Instruction: CancelLpOrders");
             process_cancel_lp_orders_inner(program_id, accounts, &instruction_data[1..])
         }
+        RouterInstruction::AutoDeleverage => {
+            msg!("Instruction: AutoDeleverage");
+            process_auto_deleverage_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateFunding => {
+            msg!("Instruction: UpdateFunding");
+            process_update_funding_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::RegisterSlab => {
+            msg!("Instruction: RegisterSlab");
+            process_register_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::SetSlabStatus => {
+            msg!("Instruction: SetSlabStatus");
+            process_set_slab_status_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateSlabParams => {
+            msg!("Instruction: UpdateSlabParams");
+            process_update_slab_params_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateRiskParams => {
+            msg!("Instruction: UpdateRiskParams");
+            process_update_risk_params_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateInsuranceParams => {
+            msg!("Instruction: UpdateInsuranceParams");
+            process_update_insurance_params_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateVestingParams => {
+            msg!("Instruction: UpdateVestingParams");
+            process_update_vesting_params_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateHaircutCaps => {
+            msg!("Instruction: UpdateHaircutCaps");
+            process_update_haircut_caps_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::TransferGovernance => {
+            msg!("Instruction: TransferGovernance");
+            process_transfer_governance_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::AcceptGovernance => {
+            msg!("Instruction: AcceptGovernance");
+            process_accept_governance_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::TopUpInsurance => {
+            msg!("Instruction: TopUpInsurance");
+            process_top_up_insurance_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::WithdrawInsuranceSurplus => {
+            msg!("Instruction: WithdrawInsuranceSurplus");
+            process_withdraw_insurance_surplus_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::SetCollateral => {
+            msg!("Instruction: SetCollateral");
+            process_set_collateral_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateCollateralPrice => {
+            msg!("Instruction: UpdateCollateralPrice");
+            process_update_collateral_price_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::PledgeEscrow => {
+            msg!("Instruction: PledgeEscrow");
+            process_pledge_escrow_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ReleaseEscrow => {
+            msg!("Instruction: ReleaseEscrow");
+            process_release_escrow_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::IssueCap => {
+            msg!("Instruction: IssueCap");
+            process_issue_cap_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::CapDebit => {
+            msg!("Instruction: CapDebit");
+            process_cap_debit_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::ReserveCrossSlab => {
+            msg!("Instruction: ReserveCrossSlab");
+            process_reserve_cross_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::CommitCrossSlab => {
+            msg!("Instruction: CommitCrossSlab");
+            process_commit_cross_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::CancelCrossSlab => {
+            msg!("Instruction: CancelCrossSlab");
+            process_cancel_cross_slab_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::UpdateMarks => {
+            msg!("Instruction: UpdateMarks");
+            process_update_marks_inner(program_id, accounts, &instruction_data[1..])
+        }
+        RouterInstruction::InitializeInsuranceVault => {
+            msg!("Instruction: InitializeInsuranceVault");
+            process_initialize_insurance_vault_inner(program_id, accounts, &instruction_data[1..])
+        }
     }
 }
 
@@ -87,8 +207,9 @@ pub fn process_instruction(
 /// 0. `[writable]` Registry account (PDA)
 /// 1. `[signer]` Governance authority
 ///
-/// Expected data layout (32 bytes):
+/// Expected data layout (64 bytes):
 /// - governance: Pubkey (32 bytes)
+/// - quote_mint: Pubkey (32 bytes)
 fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 2 {
         msg!("Error: Initialize instruction requires at least 2 accounts");
@@ -106,6 +227,7 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
     let mut reader = InstructionReader::new(data);
     let governance_bytes = reader.read_bytes::<32>()?;
     let governance = Pubkey::from(governance_bytes);
+    let quote_mint = Pubkey::from(reader.read_bytes::<32>()?);
 
     // Verify governance signer matches instruction data
     if governance_account.key() != &governance {
@@ -114,7 +236,7 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
     }
 
     // Call the initialization logic
-    process_initialize_registry(program_id, registry_account, &governance)?;
+    process_initialize_registry(program_id, registry_account, &governance, &quote_mint)?;
 
     msg!("Router initialized successfully");
     Ok(())
@@ -127,27 +249,54 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
 /// 1. `[writable]` User token account
 /// 2. `[signer]` User authority
 /// 3. `[]` Token program
+/// 4. `[writable]` Vault token account (must match Vault.token_account)
+/// 5. `[writable]` Portfolio account
+/// 6. `[]` Registry account (collateral table)
 ///
 /// Expected data layout (16 bytes):
 /// - amount: u128 (16 bytes)
 fn process_deposit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Deposit instruction requires at least 1 account");
+    if accounts.len() < 7 {
+        msg!("Error: Deposit instruction requires at least 7 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
     let vault_account = &accounts[0];
+    let user_token_account = &accounts[1];
+    let user_account = &accounts[2];
+    let token_program = &accounts[3];
+    let vault_token_account = &accounts[4];
+    let portfolio_account = &accounts[5];
+    let registry_account = &accounts[6];
+
+    // Validate accounts
     validate_owner(vault_account, program_id)?;
     validate_writable(vault_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(user_token_account)?;
+    validate_writable(vault_token_account)?;
 
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data::<SlabRegistry>(registry_account)? };
 
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let amount = reader.read_u128()?;
 
     // Call the instruction handler
-    process_deposit(vault, amount)?;
+    process_deposit(
+        vault,
+        portfolio,
+        registry,
+        user_token_account,
+        vault_token_account,
+        user_account,
+        token_program,
+        amount,
+    )?;
 
     msg!("Deposit processed successfully");
     Ok(())
@@ -156,31 +305,64 @@ fn process_deposit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[
 /// Process withdraw instruction
 ///
 /// Expected accounts:
-/// 0. `[writable]` Vault account
+/// 0. `[writable]` Vault account (PDA, signs the token transfer)
 /// 1. `[writable]` User token account
 /// 2. `[signer]` User authority
 /// 3. `[]` Token program
+/// 4. `[writable]` Vault token account (must match Vault.token_account)
+/// 5. `[writable]` Portfolio account
+/// 6. `[writable]` Registry account (global exit bucket)
 ///
-/// Expected data layout (16 bytes):
-/// - amount: u128 (16 bytes)
+/// Expected data layout (17 bytes):
+/// - amount: u128 (16 bytes, 0 = claim queued withdrawal)
+/// - source: u8 (1 byte, 0 = principal, 1 = vested PnL)
 fn process_withdraw_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Withdraw instruction requires at least 1 account");
+    if accounts.len() < 7 {
+        msg!("Error: Withdraw instruction requires at least 7 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
     let vault_account = &accounts[0];
+    let user_token_account = &accounts[1];
+    let user_account = &accounts[2];
+    let token_program = &accounts[3];
+    let vault_token_account = &accounts[4];
+    let portfolio_account = &accounts[5];
+    let registry_account = &accounts[6];
+
+    // Validate accounts
     validate_owner(vault_account, program_id)?;
     validate_writable(vault_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_writable(user_token_account)?;
+    validate_writable(vault_token_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
 
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
 
     // Parse instruction data
     let mut reader = InstructionReader::new(data);
     let amount = reader.read_u128()?;
+    let source = WithdrawSource::from_u8(reader.read_u8()?)
+        .ok_or(PercolatorError::InvalidInstruction)?;
 
     // Call the instruction handler
-    process_withdraw(vault, amount)?;
+    process_withdraw(
+        vault,
+        portfolio,
+        registry,
+        vault_account,
+        user_token_account,
+        vault_token_account,
+        user_account,
+        token_program,
+        amount,
+        source,
+    )?;
 
     msg!("Withdraw processed successfully");
     Ok(())
@@ -233,18 +415,25 @@ fn process_initialize_portfolio_inner(program_id: &Pubkey, accounts: &[AccountIn
 /// 2. `[writable]` Vault account
 /// 3. `[writable]` Registry account
 /// 4. `[]` Router authority PDA
-/// 5..5+N. `[writable]` Slab accounts (N = num_splits)
+/// 5..5+N. `[writable]` Slab accounts (N = num_splits, registered and active)
 /// 5+N..5+2N. `[writable]` Receipt PDAs (N = num_splits)
+/// 5+2N..5+3N. `[writable]` LP owner portfolios (N = num_splits, fee credit)
+/// 5+3N. `[writable]` Insurance vault (PDA ["insurance", mint])
+/// 6+3N. `[writable]` Vault token account (must match Vault.token_account)
+/// 7+3N. `[writable]` Insurance vault token account
+/// 8+3N. `[]` SPL Token program
 ///
 /// Instruction data layout:
 /// - num_splits: u8 (1 byte)
-/// - For each split (17 bytes):
+/// - max_slippage_bps: u64 (8 bytes) - max distance from best quote
+/// - For each split (21 bytes):
 ///   - side: u8 (0 = buy, 1 = sell)
 ///   - qty: i64 (quantity in 1e6 scale)
 ///   - limit_px: i64 (limit price in 1e6 scale)
+///   - expected_seqno: u32 (QuoteCache.seqno_snapshot the client priced off)
 ///
-/// Total size: 1 + (17 * num_splits) bytes
-/// Maximum splits: 8 (to avoid stack overflow)
+/// Total size: 9 + (21 * num_splits) bytes
+/// Maximum splits: 8 (to avoid stack overflow), at most one per slab
 fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 5 {
         msg!("Error: ExecuteCrossSlab requires at least 5 accounts");
@@ -270,8 +459,8 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
     let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
 
-    // Parse instruction data: num_splits (u8) + splits (17 bytes each)
-    // Layout per split: side (u8) + qty (i64) + limit_px (i64)
+    // Parse instruction data: num_splits (u8) + max_slippage_bps (u64) + splits (21 bytes each)
+    // Layout per split: side (u8) + qty (i64) + limit_px (i64) + expected_seqno (u32)
     if data.is_empty() {
         msg!("Error: Instruction data is empty");
         return Err(PercolatorError::InvalidInstruction.into());
@@ -279,14 +468,15 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
 
     let mut reader = InstructionReader::new(data);
     let num_splits = reader.read_u8()? as usize;
+    let max_slippage_bps = reader.read_u64()?;
 
     if num_splits == 0 {
         msg!("Error: num_splits must be > 0");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    // Verify we have enough accounts: 5 base + num_splits slabs + num_splits receipts
-    let required_accounts = 5 + (num_splits * 2);
+    // Verify we have enough accounts: 5 base + slabs, receipts and LP portfolios per split + 4 insurance
+    let required_accounts = 5 + (num_splits * 3) + INSURANCE_ACCOUNTS;
     if accounts.len() < required_accounts {
         msg!("Error: Insufficient accounts for ExecuteCrossSlab");
         return Err(PercolatorError::InvalidInstruction.into());
@@ -295,6 +485,12 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
     // Split accounts into slabs and receipts
     let slab_accounts = &accounts[5..5 + num_splits];
     let receipt_accounts = &accounts[5 + num_splits..5 + num_splits * 2];
+    let lp_portfolio_accounts = &accounts[5 + num_splits * 2..5 + num_splits * 3];
+    let insurance = insurance_accounts(
+        program_id,
+        vault_account,
+        &accounts[5 + num_splits * 3..required_accounts],
+    )?;
 
     // Parse splits from instruction data (on stack, small)
     // Use a fixed-size buffer to avoid heap allocation
@@ -310,12 +506,14 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
         qty: 0,
         side: 0,
         limit_px: 0,
+        expected_seqno: 0,
     }; MAX_SPLITS];
 
     for i in 0..num_splits {
         let side = reader.read_u8()?;
         let qty = reader.read_i64()?;
         let limit_px = reader.read_i64()?;
+        let expected_seqno = reader.read_u32()?;
 
         // Validate side
         if side > 1 {
@@ -331,11 +529,15 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
             qty,
             side,
             limit_px,
+            expected_seqno,
         };
     }
 
     let splits = &splits_buffer[..num_splits];
 
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
     // Call the instruction handler
     process_execute_cross_slab(
         portfolio,
@@ -345,7 +547,11 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
         router_authority,
         slab_accounts,
         receipt_accounts,
+        lp_portfolio_accounts,
         splits,
+        max_slippage_bps,
+        &insurance,
+        now_ts,
     )?;
 
     msg!("ExecuteCrossSlab processed successfully");
@@ -356,23 +562,31 @@ fn process_execute_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo
 ///
 /// Expected accounts:
 /// 0. `[writable]` Portfolio account (to be liquidated)
-/// 1. `[]` Registry account
+/// 1. `[writable]` Registry account
 /// 2. `[writable]` Vault account
 /// 3. `[]` Router authority PDA
-/// 4..4+N. `[]` Oracle accounts (N = num_oracles)
-/// 4+N..4+N+M. `[writable]` Slab accounts (M = num_slabs)
-/// 4+N+M..4+N+2M. `[writable]` Receipt PDAs (M = num_slabs)
+/// 4. `[signer]` Keeper authority
+/// 5. `[writable]` Keeper portfolio (receives the keeper share of the penalty)
+/// 6..6+N. `[]` Oracle accounts (N = num_oracles), any order; each is matched to a slab by instrument
+/// 6+N..6+N+M. `[writable]` Slab accounts (M = num_slabs)
+/// 6+N+M..6+N+2M. `[writable]` Receipt PDAs (M = num_slabs)
+/// 6+N+2M..6+N+3M. `[writable]` LP owner portfolios (M = num_slabs, fee credit)
+/// 6+N+3M. `[writable]` Insurance vault (PDA ["insurance", mint])
+/// 7+N+3M. `[writable]` Vault token account (must match Vault.token_account)
+/// 8+N+3M. `[writable]` Insurance vault token account
+/// 9+N+3M. `[]` SPL Token program
 ///
 /// Instruction data layout:
 /// - num_oracles: u8 (1 byte)
 /// - num_slabs: u8 (1 byte)
 /// - is_preliq: u8 (1 byte, 0 = auto, 1 = force pre-liq)
-/// - current_ts: u64 (8 bytes, Unix timestamp)
 ///
-/// Total size: 11 bytes
+/// Total size: 3 bytes
+///
+/// Time is read from the Clock sysvar, never from the keeper.
 fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 4 {
-        msg!("Error: LiquidateUser requires at least 4 accounts");
+    if accounts.len() < 6 {
+        msg!("Error: LiquidateUser requires at least 6 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
@@ -380,6 +594,8 @@ fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
     let registry_account = &accounts[1];
     let vault_account = &accounts[2];
     let router_authority = &accounts[3];
+    let keeper_account = &accounts[4];
+    let keeper_portfolio_account = &accounts[5];
 
     // Validate accounts
     validate_owner(portfolio_account, program_id)?;
@@ -388,14 +604,24 @@ fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
     validate_writable(registry_account)?;
     validate_owner(vault_account, program_id)?;
     validate_writable(vault_account)?;
+    validate_signer(keeper_account)?;
+    validate_owner(keeper_portfolio_account, program_id)?;
+    validate_writable(keeper_portfolio_account)?;
+
+    // The liquidated portfolio cannot also collect the keeper reward
+    if keeper_portfolio_account.key() == portfolio_account.key() {
+        msg!("Error: Keeper portfolio must differ from liquidated portfolio");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
 
     // Borrow account data mutably
     let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let keeper_portfolio = unsafe { borrow_account_data_mut::<Portfolio>(keeper_portfolio_account)? };
     let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
     let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
 
     // Parse instruction data
-    if data.len() < 11 {
+    if data.len() < 3 {
         msg!("Error: Instruction data too short");
         return Err(PercolatorError::InvalidInstruction.into());
     }
@@ -404,31 +630,50 @@ fn process_liquidate_user_inner(program_id: &Pubkey, accounts: &[AccountInfo], d
     let num_oracles = reader.read_u8()? as usize;
     let num_slabs = reader.read_u8()? as usize;
     let is_preliq = reader.read_u8()? != 0;
-    let current_ts = reader.read_u64()?;
+
+    // Oracle staleness, cooldown and loss caps use the cluster clock
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
 
     // Verify we have enough accounts
-    let required_accounts = 4 + num_oracles + num_slabs * 2;
+    let required_accounts = 6 + num_oracles + num_slabs * 3 + INSURANCE_ACCOUNTS;
     if accounts.len() < required_accounts {
         msg!("Error: Insufficient accounts for LiquidateUser");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
     // Split accounts
-    let oracle_accounts = &accounts[4..4 + num_oracles];
-    let slab_accounts = &accounts[4 + num_oracles..4 + num_oracles + num_slabs];
-    let receipt_accounts = &accounts[4 + num_oracles + num_slabs..4 + num_oracles + num_slabs * 2];
+    let oracle_accounts = &accounts[6..6 + num_oracles];
+    let slab_accounts = &accounts[6 + num_oracles..6 + num_oracles + num_slabs];
+    let receipt_accounts = &accounts[6 + num_oracles + num_slabs..6 + num_oracles + num_slabs * 2];
+    let lp_portfolio_accounts = &accounts[6 + num_oracles + num_slabs * 2..6 + num_oracles + num_slabs * 3];
+    let insurance = insurance_accounts(
+        program_id,
+        vault_account,
+        &accounts[6 + num_oracles + num_slabs * 3..required_accounts],
+    )?;
+
+    // The keeper portfolio is borrowed separately; it cannot double as an LP portfolio
+    if lp_portfolio_accounts.iter().any(|a| a.key() == keeper_portfolio_account.key()) {
+        msg!("Error: Keeper portfolio cannot be passed as an LP portfolio");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
 
     // Call the instruction handler
     process_liquidate_user(
         portfolio,
+        keeper_portfolio,
+        keeper_account.key(),
         registry,
         vault,
         router_authority,
         oracle_accounts,
         slab_accounts,
         receipt_accounts,
+        lp_portfolio_accounts,
+        &insurance,
         is_preliq,
-        current_ts,
+        now_ts,
     )?;
 
     msg!("LiquidateUser processed successfully");
@@ -563,3 +808,1362 @@ fn process_cancel_lp_orders_inner(program_id: &Pubkey, accounts: &[AccountInfo],
     msg!("CancelLpOrders processed successfully");
     Ok(())
 }
+
+/// Process auto-deleverage instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Bankrupt portfolio account
+/// 1. `[writable]` Registry account
+/// 2. `[]` Slab account (exposure being deleveraged)
+/// 3. `[signer]` Keeper authority
+/// 4..4+N. `[writable]` Counterparty portfolios, highest ADL score first
+///
+/// Instruction data layout:
+/// - (none)
+///
+/// Counterparties are processed in order and must close the bankrupt
+/// position exactly: a set that leaves it open, or that carries
+/// counterparties past the one that closes it, is rejected.
+fn process_auto_deleverage_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 5 {
+        msg!("Error: AutoDeleverage requires at least 5 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let bankrupt_account = &accounts[0];
+    let registry_account = &accounts[1];
+    let slab_account = &accounts[2];
+    let keeper_account = &accounts[3];
+    let counterparty_accounts = &accounts[4..];
+
+    // Validate accounts
+    validate_owner(bankrupt_account, program_id)?;
+    validate_writable(bankrupt_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(keeper_account)?;
+
+    // Borrow account data mutably
+    let bankrupt = unsafe { borrow_account_data_mut::<Portfolio>(bankrupt_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    // Resolve the exposure key from the slab (registry index + header instrument)
+    let (slab_idx, _) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+        msg!("Error: Slab not registered or inactive");
+        PercolatorError::SlabNotRegistered
+    })?;
+    let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+    if &header.magic != SlabHeader::MAGIC {
+        msg!("Error: Invalid slab account data");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+    let instrument_idx = registry.find_instrument(&header.instrument).ok_or_else(|| {
+        msg!("Error: Slab instrument not known to registry");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    // Deleverage counterparties in rank order until the position is closed
+    let mut max_score = u128::MAX;
+    for counterparty_account in counterparty_accounts.iter() {
+        if bankrupt.get_exposure(slab_idx, instrument_idx) == 0 {
+            msg!("Error: Counterparties passed beyond the closing set");
+            return Err(PercolatorError::InvalidInstruction.into());
+        }
+
+        validate_owner(counterparty_account, program_id)?;
+        validate_writable(counterparty_account)?;
+        if counterparty_account.key() == bankrupt_account.key() {
+            msg!("Error: Counterparty must differ from bankrupt portfolio");
+            return Err(PercolatorError::InvalidAccount.into());
+        }
+
+        let counterparty = unsafe { borrow_account_data_mut::<Portfolio>(counterparty_account)? };
+        let fill = process_auto_deleverage(
+            bankrupt,
+            counterparty,
+            registry,
+            slab_idx,
+            instrument_idx,
+            max_score,
+            now_ts,
+        )?;
+        max_score = fill.score;
+    }
+    if bankrupt.get_exposure(slab_idx, instrument_idx) != 0 {
+        msg!("Error: Counterparties do not close the bankrupt position");
+        return Err(PercolatorError::InsufficientLiquidity.into());
+    }
+
+    msg!("AutoDeleverage processed successfully");
+    Ok(())
+}
+
+/// Process update funding instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[]` Slab account (funding mark is the mid of its QuoteCache)
+/// 2. `[]` Oracle account (index price, bound to the slab's instrument)
+///
+/// Instruction data layout:
+/// - (none)
+fn process_update_funding_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: UpdateFunding requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let slab_account = &accounts[1];
+    let oracle_account = &accounts[2];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    if registry.find_slab(slab_account.key()).is_none() {
+        msg!("Error: Slab not registered or inactive");
+        return Err(PercolatorError::SlabNotRegistered.into());
+    }
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    // Index price from the oracle bound to this slab's instrument
+    use crate::instructions::{read_bound_oracle, read_quote_cache};
+    let (instrument_idx, index_px, index_ts) = read_bound_oracle(
+        oracle_account,
+        registry,
+        core::slice::from_ref(slab_account),
+        now_ts,
+    )?;
+    registry.set_instrument_mark(instrument_idx, index_px, index_ts);
+
+    // The header mark is an oracle copy; the book mid is the traded price
+    let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+    let mark_px = read_quote_cache(slab_account, header)?.mid_px().ok_or_else(|| {
+        msg!("Error: Slab book has no two-sided quote for the funding mark");
+        PercolatorError::InsufficientLiquidity
+    })?;
+    process_update_funding(registry, instrument_idx, mark_px, index_px, now_ts)?;
+
+    msg!("UpdateFunding processed successfully");
+    Ok(())
+}
+
+/// Process register slab instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+/// 2. `[]` Slab account (header must match the router layout)
+///
+/// Instruction data layout:
+/// - version_hash: [u8; 32]
+/// - oracle_id: Pubkey (32 bytes)
+/// - imr: u64 (bps, 0 = registry default)
+/// - mmr: u64 (bps, 0 = registry default)
+/// - maker_fee_cap: u64 (bps)
+/// - taker_fee_cap: u64 (bps)
+/// - latency_sla_ms: u64
+/// - max_exposure: u128
+///
+/// Total size: 120 bytes
+fn process_register_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: RegisterSlab requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+    let slab_account = &accounts[2];
+
+    let mut reader = InstructionReader::new(data);
+    let params = SlabRegistration {
+        version_hash: reader.read_bytes::<32>()?,
+        oracle_id: Pubkey::from(reader.read_bytes::<32>()?),
+        imr: reader.read_u64()?,
+        mmr: reader.read_u64()?,
+        maker_fee_cap: reader.read_u64()?,
+        taker_fee_cap: reader.read_u64()?,
+        latency_sla_ms: reader.read_u64()?,
+        max_exposure: reader.read_u128()?,
+    };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+    let (router_authority, _) = crate::pda::derive_authority_pda(&registry.router_id);
+    process_register_slab(
+        registry,
+        governance_account.key(),
+        &router_authority,
+        SlabAccount { key: slab_account.key(), owner: slab_account.owner(), header },
+        &params,
+        now_ts,
+    )?;
+
+    msg!("RegisterSlab processed successfully");
+    Ok(())
+}
+
+/// Process set slab status instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+/// 2. `[]` Slab account
+///
+/// Instruction data layout:
+/// - status: u8 (0 = active, 1 = paused, 2 = deactivated)
+fn process_set_slab_status_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: SetSlabStatus requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+    let slab_account = &accounts[2];
+
+    let mut reader = InstructionReader::new(data);
+    let status = SlabStatus::from_u8(reader.read_u8()?)
+        .ok_or(PercolatorError::InvalidInstruction)?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_set_slab_status(registry, governance_account.key(), slab_account.key(), status)?;
+
+    msg!("SetSlabStatus processed successfully");
+    Ok(())
+}
+
+/// Process update slab params instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+/// 2. `[]` Slab account
+///
+/// Instruction data layout:
+/// - imr: u64 (bps, 0 = registry default)
+/// - mmr: u64 (bps, 0 = registry default)
+fn process_update_slab_params_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: UpdateSlabParams requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+    let slab_account = &accounts[2];
+
+    let mut reader = InstructionReader::new(data);
+    let imr = reader.read_u64()?;
+    let mmr = reader.read_u64()?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_slab_params(registry, governance_account.key(), slab_account.key(), imr, mmr)?;
+
+    msg!("UpdateSlabParams processed successfully");
+    Ok(())
+}
+
+/// Process update risk params instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - imr: u64, mmr: u64 (bps)
+/// - liq_band_bps: u64
+/// - preliq_buffer: i128 (16 bytes, 1e6 scale)
+/// - preliq_band_bps: u64
+/// - router_cap_per_slab: u64
+/// - oracle_tolerance_bps: u64
+/// - liquidation_fee_bps: u64
+/// - liquidation_keeper_share_bps: u64
+/// - oracle_max_age_secs: u64
+/// - oracle_max_conf_bps: u64
+/// - funding_interval_secs: u64
+/// - funding_cap_bps: u64
+///
+/// Total size: 112 bytes
+fn process_update_risk_params_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateRiskParams requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let params = RiskParams {
+        imr: reader.read_u64()?,
+        mmr: reader.read_u64()?,
+        liq_band_bps: reader.read_u64()?,
+        preliq_buffer: reader.read_u128()? as i128,
+        preliq_band_bps: reader.read_u64()?,
+        router_cap_per_slab: reader.read_u64()?,
+        oracle_tolerance_bps: reader.read_u64()?,
+        liquidation_fee_bps: reader.read_u64()?,
+        liquidation_keeper_share_bps: reader.read_u64()?,
+        oracle_max_age_secs: reader.read_u64()?,
+        oracle_max_conf_bps: reader.read_u64()?,
+        funding_interval_secs: reader.read_u64()?,
+        funding_cap_bps: reader.read_u64()?,
+    };
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_risk_params(registry, governance_account.key(), &params)?;
+
+    msg!("UpdateRiskParams processed successfully");
+    Ok(())
+}
+
+/// Process update insurance params instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - fee_bps_to_insurance: u16
+/// - max_payout_bps_of_oi: u16
+/// - max_daily_payout_bps_of_vault: u16
+/// - cooloff_secs: u32
+fn process_update_insurance_params_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateInsuranceParams requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let params = InsuranceParams {
+        fee_bps_to_insurance: reader.read_u16()?,
+        max_payout_bps_of_oi: reader.read_u16()?,
+        max_daily_payout_bps_of_vault: reader.read_u16()?,
+        cooloff_secs: reader.read_u32()?,
+    };
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_insurance_params(registry, governance_account.key(), params)?;
+
+    msg!("UpdateInsuranceParams processed successfully");
+    Ok(())
+}
+
+/// Process update vesting params instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - tau_slots: u64
+/// - cliff_slots: u64
+fn process_update_vesting_params_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateVestingParams requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let params = PnlVestingParams {
+        tau_slots: reader.read_u64()?,
+        cliff_slots: reader.read_u64()?,
+    };
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_vesting_params(registry, governance_account.key(), params)?;
+
+    msg!("UpdateVestingParams processed successfully");
+    Ok(())
+}
+
+/// Process update haircut caps instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - max_haircut_per_event_bps: u16
+/// - max_haircut_per_day_bps: u16
+fn process_update_haircut_caps_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateHaircutCaps requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let max_per_event_bps = reader.read_u16()?;
+    let max_per_day_bps = reader.read_u16()?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_haircut_caps(registry, governance_account.key(), max_per_event_bps, max_per_day_bps)?;
+
+    msg!("UpdateHaircutCaps processed successfully");
+    Ok(())
+}
+
+/// Process transfer governance instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - new_governance: Pubkey (32 bytes, default pubkey cancels)
+fn process_transfer_governance_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: TransferGovernance requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let new_governance = Pubkey::from(reader.read_bytes::<32>()?);
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_transfer_governance(registry, governance_account.key(), &new_governance)?;
+
+    msg!("TransferGovernance processed successfully");
+    Ok(())
+}
+
+/// Process accept governance instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Pending governance authority
+///
+/// Instruction data layout:
+/// - (none)
+fn process_accept_governance_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: AcceptGovernance requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_accept_governance(registry, governance_account.key())?;
+
+    msg!("AcceptGovernance processed successfully");
+    Ok(())
+}
+
+/// Process top up insurance instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority (owner of the source token account)
+/// 2. `[writable]` Insurance vault (PDA ["insurance", mint])
+/// 3. `[writable]` Source token account
+/// 4. `[writable]` Insurance vault token account
+/// 5. `[]` SPL Token program
+///
+/// Instruction data layout:
+/// - amount: u64 (8 bytes)
+fn process_top_up_insurance_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 6 {
+        msg!("Error: TopUpInsurance requires at least 6 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+    let insurance_account = &accounts[2];
+    let source_token_account = &accounts[3];
+    let insurance_token_account = &accounts[4];
+    let token_program = &accounts[5];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+    validate_owner(insurance_account, program_id)?;
+    validate_writable(insurance_account)?;
+    validate_writable(source_token_account)?;
+    validate_writable(insurance_token_account)?;
+    validate_insurance_vault(program_id, insurance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u64()? as u128;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    let insurance_vault = unsafe { borrow_account_data_mut::<Vault>(insurance_account)? };
+    process_top_up_insurance(
+        registry,
+        insurance_vault,
+        governance_account,
+        source_token_account,
+        insurance_token_account,
+        token_program,
+        amount,
+    )?;
+
+    msg!("TopUpInsurance processed successfully");
+    Ok(())
+}
+
+/// Process initialize insurance vault instruction
+///
+/// Expected accounts:
+/// 0. `[]` Registry account
+/// 1. `[signer]` Governance authority
+/// 2. `[writable]` Insurance vault (PDA ["insurance", mint], allocated to Vault::LEN)
+/// 3. `[]` Insurance vault token account (mint `mint`, owned by the insurance vault PDA)
+///
+/// Instruction data layout:
+/// - mint: Pubkey (32 bytes)
+fn process_initialize_insurance_vault_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 {
+        msg!("Error: InitializeInsuranceVault requires at least 4 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+    let insurance_account = &accounts[2];
+    let insurance_token_account = &accounts[3];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_owner(insurance_account, program_id)?;
+    validate_writable(insurance_account)?;
+    if insurance_account.data_len() != Vault::LEN {
+        msg!("Error: Insurance vault account has incorrect size");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+
+    let mut reader = InstructionReader::new(data);
+    let mint = Pubkey::from(reader.read_bytes::<32>()?);
+
+    use crate::pda::derive_insurance_vault_pda;
+    let (expected, bump) = derive_insurance_vault_pda(&mint, program_id);
+    if insurance_account.key() != &expected {
+        msg!("Error: Account is not the insurance vault PDA");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+
+    let registry = unsafe { borrow_account_data::<SlabRegistry>(registry_account)? };
+    let insurance_vault = unsafe { borrow_account_data_mut::<Vault>(insurance_account)? };
+    process_initialize_insurance_vault(
+        registry,
+        insurance_vault,
+        insurance_account.key(),
+        governance_account,
+        insurance_token_account,
+        mint,
+        bump,
+    )?;
+
+    msg!("InitializeInsuranceVault processed successfully");
+    Ok(())
+}
+
+/// Process withdraw insurance surplus instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+/// 2. `[writable]` Insurance vault (PDA ["insurance", mint], signs the token transfer)
+/// 3. `[writable]` Insurance vault token account
+/// 4. `[writable]` Destination token account
+/// 5. `[]` SPL Token program
+///
+/// Instruction data layout:
+/// - amount: u64 (8 bytes)
+fn process_withdraw_insurance_surplus_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 6 {
+        msg!("Error: WithdrawInsuranceSurplus requires at least 6 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+    let insurance_account = &accounts[2];
+    let insurance_token_account = &accounts[3];
+    let destination_token_account = &accounts[4];
+    let token_program = &accounts[5];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+    validate_owner(insurance_account, program_id)?;
+    validate_writable(insurance_account)?;
+    validate_writable(insurance_token_account)?;
+    validate_writable(destination_token_account)?;
+    validate_insurance_vault(program_id, insurance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u64()? as u128;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    let insurance_vault = unsafe { borrow_account_data_mut::<Vault>(insurance_account)? };
+    process_withdraw_insurance_surplus(
+        registry,
+        insurance_vault,
+        governance_account,
+        insurance_account,
+        insurance_token_account,
+        destination_token_account,
+        token_program,
+        amount,
+    )?;
+
+    msg!("WithdrawInsuranceSurplus processed successfully");
+    Ok(())
+}
+
+/// Process set collateral instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[signer]` Governance authority
+///
+/// Instruction data layout:
+/// - mint: Pubkey (32 bytes)
+/// - oracle: Pubkey (32 bytes, price oracle account for the mint)
+/// - weight_bps: u64 (share of oracle value counted as equity)
+/// - decimals: u8 (mint decimals)
+///
+/// Total size: 73 bytes
+fn process_set_collateral_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: SetCollateral requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let governance_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(governance_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let mint = Pubkey::from(reader.read_bytes::<32>()?);
+    let oracle = Pubkey::from(reader.read_bytes::<32>()?);
+    let weight_bps = reader.read_u64()?;
+    let decimals = reader.read_u8()?;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_set_collateral(registry, governance_account.key(), &mint, &oracle, weight_bps, decimals)?;
+
+    msg!("SetCollateral processed successfully");
+    Ok(())
+}
+
+/// Process update collateral price instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1. `[]` Oracle account registered for the collateral mint
+///
+/// Instruction data layout: none
+fn process_update_collateral_price_inner(program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateCollateralPrice requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+    let oracle_account = &accounts[1];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    let oracle = unsafe { borrow_account_data::<PriceOracle>(oracle_account)? };
+    process_update_collateral_price(registry, oracle_account.key(), oracle, now_ts)?;
+
+    msg!("UpdateCollateralPrice processed successfully");
+    Ok(())
+}
+
+/// Process pledge escrow instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Escrow account (PDA ["escrow", user, slab, mint], initialized on first pledge)
+/// 1. `[writable]` Vault account (escrow mint)
+/// 2. `[writable]` User portfolio account
+/// 3. `[writable]` Registry account
+/// 4. `[signer]` User authority
+/// 5. `[]` Slab account (registered and active)
+///
+/// Instruction data layout:
+/// - amount: u128 (16 bytes)
+fn process_pledge_escrow_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 6 {
+        msg!("Error: PledgeEscrow requires at least 6 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let escrow_account = &accounts[0];
+    let vault_account = &accounts[1];
+    let portfolio_account = &accounts[2];
+    let registry_account = &accounts[3];
+    let user_account = &accounts[4];
+    let slab_account = &accounts[5];
+
+    // Validate accounts
+    validate_owner(escrow_account, program_id)?;
+    validate_writable(escrow_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+    validate_signer(user_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u128()?;
+
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+
+    // The escrow address pins its (user, slab, mint) scope
+    use crate::pda::derive_escrow_pda;
+    let (expected, bump) = derive_escrow_pda(user_account.key(), slab_account.key(), &vault.mint, program_id);
+    if escrow_account.key() != &expected {
+        msg!("Error: Escrow account is not the correct PDA");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+    let escrow = unsafe { borrow_account_data_mut::<Escrow>(escrow_account)? };
+    if !escrow.is_initialized() {
+        escrow.initialize_in_place(*program_id, *user_account.key(), *slab_account.key(), vault.mint, bump);
+    }
+
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+    process_pledge_escrow(escrow, vault, portfolio, registry, user_account.key(), amount, now_ts)?;
+
+    msg!("PledgeEscrow processed successfully");
+    Ok(())
+}
+
+/// Process release escrow instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Escrow account (PDA)
+/// 1. `[writable]` Vault account (escrow mint)
+/// 2. `[writable]` User portfolio account
+/// 3. `[signer]` User authority
+///
+/// Instruction data layout:
+/// - amount: u128 (16 bytes)
+fn process_release_escrow_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 {
+        msg!("Error: ReleaseEscrow requires at least 4 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let escrow_account = &accounts[0];
+    let vault_account = &accounts[1];
+    let portfolio_account = &accounts[2];
+    let user_account = &accounts[3];
+
+    // Validate accounts
+    validate_owner(escrow_account, program_id)?;
+    validate_writable(escrow_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_signer(user_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u128()?;
+
+    let escrow = unsafe { borrow_account_data_mut::<Escrow>(escrow_account)? };
+    validate_escrow_pda(program_id, escrow_account, escrow)?;
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+
+    process_release_escrow(escrow, vault, portfolio, user_account.key(), amount, current_time_ms()?)?;
+
+    msg!("ReleaseEscrow processed successfully");
+    Ok(())
+}
+
+/// Process issue cap instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Cap account (PDA ["cap", user, slab, mint, escrow.nonce], unissued)
+/// 1. `[writable]` Escrow account (PDA)
+/// 2. `[signer]` User authority
+///
+/// Instruction data layout:
+/// - amount_max: u128 (16 bytes)
+/// - ttl_ms: u64 (8 bytes, clamped to MAX_CAP_TTL_MS)
+fn process_issue_cap_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: IssueCap requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let cap_account = &accounts[0];
+    let escrow_account = &accounts[1];
+    let user_account = &accounts[2];
+
+    // Validate accounts
+    validate_owner(cap_account, program_id)?;
+    validate_writable(cap_account)?;
+    validate_owner(escrow_account, program_id)?;
+    validate_writable(escrow_account)?;
+    validate_signer(user_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount_max = reader.read_u128()?;
+    let ttl_ms = reader.read_u64()?;
+
+    let escrow = unsafe { borrow_account_data_mut::<Escrow>(escrow_account)? };
+    validate_escrow_pda(program_id, escrow_account, escrow)?;
+
+    // The cap address must use the escrow's next nonce (anti-replay)
+    use crate::pda::derive_cap_pda;
+    let (expected, bump) = derive_cap_pda(&escrow.user, &escrow.slab, &escrow.mint, escrow.nonce, program_id);
+    if cap_account.key() != &expected {
+        msg!("Error: Cap account is not the PDA for the escrow's next nonce");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+    let cap = unsafe { borrow_account_data_mut::<Cap>(cap_account)? };
+
+    process_issue_cap(
+        cap,
+        escrow,
+        escrow_account.key(),
+        user_account.key(),
+        amount_max,
+        ttl_ms,
+        current_time_ms()?,
+        bump,
+    )?;
+
+    msg!("IssueCap processed successfully");
+    Ok(())
+}
+
+/// Process cap debit instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Cap account (PDA)
+/// 1. `[writable]` Escrow account (PDA, must match Cap.escrow)
+/// 2. `[writable]` Vault account (escrow mint)
+/// 3. `[writable]` LP owner portfolio account (credited)
+/// 4. `[]` Registry account
+/// 5. `[]` Slab account (registered and active)
+/// 6. `[signer]` Slab LP owner
+///
+/// Instruction data layout:
+/// - amount: u128 (16 bytes)
+fn process_cap_debit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 7 {
+        msg!("Error: CapDebit requires at least 7 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let cap_account = &accounts[0];
+    let escrow_account = &accounts[1];
+    let vault_account = &accounts[2];
+    let lp_portfolio_account = &accounts[3];
+    let registry_account = &accounts[4];
+    let slab_account = &accounts[5];
+    let lp_owner_account = &accounts[6];
+
+    // Validate accounts
+    validate_owner(cap_account, program_id)?;
+    validate_writable(cap_account)?;
+    validate_owner(escrow_account, program_id)?;
+    validate_writable(escrow_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_owner(lp_portfolio_account, program_id)?;
+    validate_writable(lp_portfolio_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_signer(lp_owner_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let amount = reader.read_u128()?;
+
+    let cap = unsafe { borrow_account_data_mut::<Cap>(cap_account)? };
+    use crate::pda::derive_cap_pda;
+    let (expected, _) = derive_cap_pda(&cap.user, &cap.slab, &cap.mint, cap.nonce, program_id);
+    if cap_account.key() != &expected || !cap.is_issued() {
+        msg!("Error: Account is not an issued cap");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+    let escrow = unsafe { borrow_account_data_mut::<Escrow>(escrow_account)? };
+    validate_escrow_pda(program_id, escrow_account, escrow)?;
+
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let lp_portfolio = unsafe { borrow_account_data_mut::<Portfolio>(lp_portfolio_account)? };
+    let registry = unsafe { borrow_account_data::<SlabRegistry>(registry_account)? };
+    let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+
+    process_cap_debit(
+        cap,
+        escrow,
+        escrow_account.key(),
+        vault,
+        lp_portfolio,
+        registry,
+        slab_account.key(),
+        header,
+        lp_owner_account.key(),
+        amount,
+        current_time_ms()?,
+    )?;
+
+    msg!("CapDebit processed successfully");
+    Ok(())
+}
+
+/// Maximum number of slabs in one two-phase order
+const MAX_HOLD_LEGS: usize = 8;
+
+/// Process reserve cross-slab instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Portfolio account (touched and margined before reserving)
+/// 1. `[signer]` User authority
+/// 2. `[writable]` Registry account
+/// 3. `[]` Router authority PDA
+/// 4..4+N. `[writable]` Slab accounts (N = num_splits)
+/// 4+N..4+2N. `[writable]` Reserve receipt accounts (N = num_splits)
+///
+/// Instruction data layout:
+/// - num_splits: u8 (1 byte)
+/// - max_slippage_bps: u64 (8 bytes)
+/// - ttl_ms: u64 (8 bytes)
+/// - route_id: u64 (8 bytes)
+/// - splits (21 bytes each): side (u8) + qty (i64) + limit_px (i64) + expected_seqno (u32)
+fn process_reserve_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 4 {
+        msg!("Error: ReserveCrossSlab requires at least 4 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let portfolio_account = &accounts[0];
+    let user_account = &accounts[1];
+    let registry_account = &accounts[2];
+    let router_authority = &accounts[3];
+
+    // Validate accounts
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_signer(user_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let num_splits = reader.read_u8()? as usize;
+    let max_slippage_bps = reader.read_u64()?;
+    let ttl_ms = reader.read_u64()?;
+    let route_id = reader.read_u64()?;
+
+    if num_splits == 0 || num_splits > MAX_HOLD_LEGS {
+        msg!("Error: num_splits out of range");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+    if accounts.len() < 4 + num_splits * 2 {
+        msg!("Error: Insufficient accounts for ReserveCrossSlab");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+    let slab_accounts = &accounts[4..4 + num_splits];
+    let receipt_accounts = &accounts[4 + num_splits..4 + num_splits * 2];
+
+    let mut splits_buffer = [SlabSplit {
+        slab_id: Pubkey::default(),
+        qty: 0,
+        side: 0,
+        limit_px: 0,
+        expected_seqno: 0,
+    }; MAX_HOLD_LEGS];
+    for i in 0..num_splits {
+        let side = reader.read_u8()?;
+        let qty = reader.read_i64()?;
+        let limit_px = reader.read_i64()?;
+        let expected_seqno = reader.read_u32()?;
+        if side > 1 {
+            msg!("Error: Invalid side");
+            return Err(PercolatorError::InvalidSide.into());
+        }
+        splits_buffer[i] = SlabSplit {
+            slab_id: *slab_accounts[i].key(),
+            qty,
+            side,
+            limit_px,
+            expected_seqno,
+        };
+    }
+
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    process_reserve_cross_slab(
+        portfolio,
+        user_account.key(),
+        registry,
+        router_authority,
+        slab_accounts,
+        receipt_accounts,
+        &splits_buffer[..num_splits],
+        max_slippage_bps,
+        ttl_ms,
+        route_id,
+        now_ts,
+    )?;
+
+    msg!("ReserveCrossSlab processed successfully");
+    Ok(())
+}
+
+/// Process commit cross-slab instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Portfolio account
+/// 1. `[signer]` User authority
+/// 2. `[writable]` Vault account
+/// 3. `[writable]` Registry account
+/// 4. `[]` Router authority PDA
+/// 5..5+N. `[writable]` Slab accounts (N = num_legs)
+/// 5+N..5+2N. `[writable]` Fill receipt accounts (N = num_legs)
+/// 5+2N..5+3N. `[writable]` LP owner portfolios (N = num_legs, fee credit)
+/// 5+3N. `[writable]` Insurance vault (PDA ["insurance", mint])
+/// 6+3N. `[writable]` Vault token account (must match Vault.token_account)
+/// 7+3N. `[writable]` Insurance vault token account
+/// 8+3N. `[]` SPL Token program
+///
+/// Instruction data layout:
+/// - num_legs: u8 (1 byte)
+/// - legs (17 bytes each): hold_id (u64) + side (u8) + qty (i64)
+fn process_commit_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 5 {
+        msg!("Error: CommitCrossSlab requires at least 5 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let portfolio_account = &accounts[0];
+    let user_account = &accounts[1];
+    let vault_account = &accounts[2];
+    let registry_account = &accounts[3];
+    let router_authority = &accounts[4];
+
+    // Validate accounts
+    validate_owner(portfolio_account, program_id)?;
+    validate_writable(portfolio_account)?;
+    validate_signer(user_account)?;
+    validate_owner(vault_account, program_id)?;
+    validate_writable(vault_account)?;
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let num_legs = reader.read_u8()? as usize;
+    if num_legs == 0 || num_legs > MAX_HOLD_LEGS {
+        msg!("Error: num_legs out of range");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let required_accounts = 5 + num_legs * 3 + INSURANCE_ACCOUNTS;
+    if accounts.len() < required_accounts {
+        msg!("Error: Insufficient accounts for CommitCrossSlab");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+    let slab_accounts = &accounts[5..5 + num_legs];
+    let receipt_accounts = &accounts[5 + num_legs..5 + num_legs * 2];
+    let lp_portfolio_accounts = &accounts[5 + num_legs * 2..5 + num_legs * 3];
+    let insurance = insurance_accounts(
+        program_id,
+        vault_account,
+        &accounts[5 + num_legs * 3..required_accounts],
+    )?;
+
+    let mut legs_buffer = [HoldLeg {
+        slab_id: Pubkey::default(),
+        hold_id: 0,
+        side: 0,
+        qty: 0,
+    }; MAX_HOLD_LEGS];
+    for i in 0..num_legs {
+        let hold_id = reader.read_u64()?;
+        let side = reader.read_u8()?;
+        let qty = reader.read_i64()?;
+        if side > 1 {
+            msg!("Error: Invalid side");
+            return Err(PercolatorError::InvalidSide.into());
+        }
+        legs_buffer[i] = HoldLeg {
+            slab_id: *slab_accounts[i].key(),
+            hold_id,
+            side,
+            qty,
+        };
+    }
+
+    let portfolio = unsafe { borrow_account_data_mut::<Portfolio>(portfolio_account)? };
+    let vault = unsafe { borrow_account_data_mut::<Vault>(vault_account)? };
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    process_commit_cross_slab(
+        portfolio,
+        user_account.key(),
+        vault,
+        registry,
+        router_authority,
+        slab_accounts,
+        receipt_accounts,
+        lp_portfolio_accounts,
+        &legs_buffer[..num_legs],
+        &insurance,
+        now_ts,
+    )?;
+
+    msg!("CommitCrossSlab processed successfully");
+    Ok(())
+}
+
+/// Process cancel cross-slab instruction
+///
+/// Expected accounts:
+/// 0. `[]` Portfolio account
+/// 1. `[signer]` User authority
+/// 2. `[]` Router authority PDA
+/// 3..3+N. `[writable]` Slab accounts (N = num_holds)
+///
+/// Instruction data layout:
+/// - num_holds: u8 (1 byte)
+/// - hold_ids: u64 each, same order as the slab accounts
+fn process_cancel_cross_slab_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: CancelCrossSlab requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let portfolio_account = &accounts[0];
+    let user_account = &accounts[1];
+    let router_authority = &accounts[2];
+
+    // Validate accounts
+    validate_owner(portfolio_account, program_id)?;
+    validate_signer(user_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let num_holds = reader.read_u8()? as usize;
+    if num_holds == 0 || num_holds > MAX_HOLD_LEGS {
+        msg!("Error: num_holds out of range");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+    if accounts.len() < 3 + num_holds {
+        msg!("Error: Insufficient accounts for CancelCrossSlab");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let mut hold_ids = [0u64; MAX_HOLD_LEGS];
+    for hold_id in hold_ids.iter_mut().take(num_holds) {
+        *hold_id = reader.read_u64()?;
+    }
+
+    let portfolio = unsafe { borrow_account_data::<Portfolio>(portfolio_account)? };
+
+    process_cancel_cross_slab(
+        portfolio,
+        user_account.key(),
+        router_authority,
+        &accounts[3..3 + num_holds],
+        &hold_ids[..num_holds],
+    )?;
+
+    msg!("CancelCrossSlab processed successfully");
+    Ok(())
+}
+
+/// Process update marks instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Registry account
+/// 1..1+N. `[]` Slab accounts (N = num_slabs, registered and active)
+/// 1+N... `[]` Oracle accounts, each bound to one of the slabs' instruments
+///
+/// Instruction data layout:
+/// - num_slabs: u8 (1 byte)
+fn process_update_marks_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: UpdateMarks requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let registry_account = &accounts[0];
+
+    // Validate accounts
+    validate_owner(registry_account, program_id)?;
+    validate_writable(registry_account)?;
+
+    let mut reader = InstructionReader::new(data);
+    let num_slabs = reader.read_u8()? as usize;
+    if num_slabs == 0 || accounts.len() < 2 + num_slabs {
+        msg!("Error: Insufficient accounts for UpdateMarks");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+    let slab_accounts = &accounts[1..1 + num_slabs];
+    let oracle_accounts = &accounts[1 + num_slabs..];
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    let now_ts = Clock::get()?.unix_timestamp as u64;
+
+    let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
+    process_update_marks(registry, oracle_accounts, slab_accounts, now_ts)?;
+
+    msg!("UpdateMarks processed successfully");
+    Ok(())
+}
+
+/// Verify an initialized escrow sits at the PDA for its (user, slab, mint)
+fn validate_escrow_pda(program_id: &Pubkey, escrow_account: &AccountInfo, escrow: &Escrow) -> Result<(), PercolatorError> {
+    use crate::pda::derive_escrow_pda;
+
+    let (expected, _) = derive_escrow_pda(&escrow.user, &escrow.slab, &escrow.mint, program_id);
+    if escrow_account.key() != &expected || !escrow.is_initialized() {
+        msg!("Error: Account is not an initialized escrow");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    Ok(())
+}
+
+/// Current time in milliseconds for cap expiry
+fn current_time_ms() -> Result<u64, pinocchio::program_error::ProgramError> {
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    Ok((Clock::get()?.unix_timestamp as u64).saturating_mul(1_000))
+}
+
+/// Number of trailing accounts used to settle insurance flows
+const INSURANCE_ACCOUNTS: usize = 4;
+
+/// Validate and bundle the insurance accounts trailing a trade or liquidation
+///
+/// Layout: insurance vault (writable), vault token account (writable),
+/// insurance vault token account (writable), SPL Token program.
+fn insurance_accounts<'a>(
+    program_id: &Pubkey,
+    vault_account: &'a AccountInfo,
+    accounts: &'a [AccountInfo],
+) -> Result<InsuranceAccounts<'a>, PercolatorError> {
+    let [insurance_account, vault_token_account, insurance_token_account, token_program] = accounts else {
+        return Err(PercolatorError::InvalidInstruction);
+    };
+
+    validate_owner(insurance_account, program_id)?;
+    validate_writable(insurance_account)?;
+    validate_writable(vault_token_account)?;
+    validate_writable(insurance_token_account)?;
+    validate_insurance_vault(program_id, insurance_account)?;
+
+    Ok(InsuranceAccounts {
+        vault_account,
+        vault_token_account,
+        insurance_account,
+        insurance_token_account,
+        token_program,
+    })
+}
+
+/// Verify an account is the insurance vault PDA for its mint
+///
+/// A collateral vault for the same mint has the same layout, so the
+/// address is the only thing that tells them apart.
+fn validate_insurance_vault(program_id: &Pubkey, insurance_account: &AccountInfo) -> Result<(), PercolatorError> {
+    use crate::pda::derive_insurance_vault_pda;
+
+    let insurance_vault = unsafe { borrow_account_data::<Vault>(insurance_account)? };
+    let (expected, _) = derive_insurance_vault_pda(&insurance_vault.mint, program_id);
+    if insurance_account.key() != &expected {
+        msg!("Error: Account is not the insurance vault PDA");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! Auto-deleveraging (ADL) - close a bankrupt position against winners
+//!
+//! Last resort once insurance and the socialized haircut are exhausted.
+//! The bankrupt portfolio's remaining position is force-closed against
+//! opposite-side portfolios at its bankruptcy price, so its equity lands at
+//! zero and the counterparties give up part of their unrealized profit.
+//!
+//! Counterparties are ranked off-chain by `adl_score` (profit × leverage).
+//! The router checks that they arrive in non-increasing score order and
+//! that the set is complete: it must close the whole bankrupt position,
+//! with no counterparty passed beyond the ones needed to do so.
+
+use crate::state::{Portfolio, SlabRegistry};
+use percolator_common::*;
+use pinocchio::msg;
+
+/// Price/quantity scale (1e6)
+const SCALE: u128 = 1_000_000;
+
+/// Result of deleveraging one counterparty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdlFill {
+    /// Quantity closed on both sides (1e6 scale, unsigned)
+    pub qty: i64,
+    /// Bankruptcy price the position was closed at (1e6 scale)
+    pub price: i64,
+    /// Counterparty ADL score (pass as `max_score` for the next one)
+    pub score: u128,
+}
+
+/// ADL ranking score: unrealized profit × leverage (using verified math)
+///
+/// score = profit * notional / equity, where profit is
+/// `qty * (mark - entry) / 1e6` and notional is `|qty| * mark / 1e6`.
+/// Positions not in profit, or portfolios without positive equity, score 0
+/// and cannot be deleveraged.
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in ranking.
+pub fn adl_score(qty: i64, entry_px: i64, mark_px: i64, equity: i128) -> u128 {
+    use model_safety::math::{div_u128, mul_u128};
+
+    if qty == 0 || mark_px <= 0 || equity <= 0 {
+        return 0;
+    }
+
+    let profit = (qty as i128 * (mark_px as i128 - entry_px as i128)) / SCALE as i128;
+    if profit <= 0 {
+        return 0;
+    }
+
+    let notional = div_u128(mul_u128(qty.unsigned_abs() as u128, mark_px as u128), SCALE);
+    div_u128(mul_u128(profit as u128, notional), equity as u128)
+}
+
+/// Bankruptcy price of a position: the close price that brings equity to zero
+///
+/// Closing `qty` at `p` moves equity by `qty * (p - ref_px) / 1e6`, where
+/// `ref_px` is the price `equity` is measured at (entry for realized equity,
+/// mark for mark-to-market equity), so `p = ref_px - equity * 1e6 / qty`. Rounds in the bankrupt account's favor
+/// so the whole deficit is covered; a short's price floors at 1.
+pub fn bankruptcy_price(qty: i64, ref_px: i64, equity: i128) -> i64 {
+    if qty == 0 || equity >= 0 {
+        return ref_px;
+    }
+
+    let per_unit = (equity.unsigned_abs().saturating_mul(SCALE)).div_ceil(qty.unsigned_abs() as u128);
+    let per_unit = per_unit.min(i64::MAX as u128) as i64;
+    if qty > 0 {
+        ref_px.saturating_add(per_unit)
+    } else {
+        ref_px.saturating_sub(per_unit).max(1)
+    }
+}
+
+/// Process auto_deleverage against one counterparty
+///
+/// Closes `min(|bankrupt qty|, |counterparty qty|)` of the exposure at
+/// (slab, instrument) on both portfolios at the bankrupt position's
+/// bankruptcy price. The bankrupt portfolio's recovered equity is taken off
+/// the insurance fund's uncovered bad debt.
+///
+/// # Arguments
+/// * `bankrupt` - Portfolio with negative equity and an open position
+/// * `counterparty` - Opposite-side portfolio in profit
+/// * `registry` - Slab registry (mark prices, insurance and haircut state)
+/// * `slab_idx` - Registry slab index of the exposure
+/// * `instrument_idx` - Registry instrument index of the exposure
+/// * `max_score` - Previous counterparty's score (`u128::MAX` for the first)
+/// * `now_ts` - Clock sysvar timestamp (mark freshness)
+///
+/// # Returns
+/// * The quantity closed, the price and the counterparty's score
+pub fn process_auto_deleverage(
+    bankrupt: &mut Portfolio,
+    counterparty: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    slab_idx: u16,
+    instrument_idx: u16,
+    max_score: u128,
+    now_ts: u64,
+) -> Result<AdlFill, PercolatorError> {
+    // ADL only recovers debt insurance and the haircut left uncovered
+    if registry.insurance_state.uncovered_bad_debt == 0 {
+        msg!("Error: No uncovered bad debt to deleverage");
+        return Err(PercolatorError::PortfolioHealthy);
+    }
+
+    // Apply vesting, haircut catchup and funding, then mark to market, so
+    // equity, score and bankruptcy price include all of them
+    use crate::instructions::touch_portfolio;
+    use crate::margin::{fresh_mark, refresh_margin};
+    touch_portfolio(bankrupt, registry);
+    touch_portfolio(counterparty, registry);
+    refresh_margin(bankrupt, registry, now_ts)?;
+    refresh_margin(counterparty, registry, now_ts)?;
+
+    if bankrupt.equity >= 0 {
+        msg!("Error: Portfolio is not bankrupt");
+        return Err(PercolatorError::PortfolioHealthy);
+    }
+
+    let bankrupt_qty = bankrupt.get_exposure(slab_idx, instrument_idx);
+    if bankrupt_qty == 0 {
+        msg!("Error: Bankrupt portfolio has no position to deleverage");
+        return Err(PercolatorError::PositionNotFound);
+    }
+
+    let counter_qty = counterparty.get_exposure(slab_idx, instrument_idx);
+    if counter_qty == 0 || (counter_qty > 0) == (bankrupt_qty > 0) {
+        msg!("Error: Counterparty is not on the opposite side");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    let mark_px = fresh_mark(registry, instrument_idx, now_ts)?;
+
+    // Counterparties must be passed best-ranked first
+    let score = adl_score(
+        counter_qty,
+        counterparty.get_entry_price(slab_idx, instrument_idx),
+        mark_px,
+        counterparty.equity,
+    );
+    if score == 0 {
+        msg!("Error: Counterparty is not in profit");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+    if score > max_score {
+        msg!("Error: Counterparties not in ADL rank order");
+        return Err(PercolatorError::AdlRankViolation);
+    }
+
+    // Equity is marked to market, so the close price that zeroes it is
+    // measured from mark rather than from the entry price
+    let price = bankruptcy_price(bankrupt_qty, mark_px, bankrupt.equity);
+    let qty = bankrupt_qty.unsigned_abs().min(counter_qty.unsigned_abs()) as i64;
+
+    // Close both sides at the bankruptcy price
+    let bankrupt_fill = if bankrupt_qty > 0 { -qty } else { qty };
+    let equity_before = bankrupt.equity;
+    let pnl_before = bankrupt.pnl;
+    let realized = bankrupt.apply_fill(slab_idx, instrument_idx, bankrupt_fill, price);
+    bankrupt.book_fill(realized, 0);
+    registry.global_haircut.track_pnl_change(pnl_before, bankrupt.pnl);
+
+    let pnl_before = counterparty.pnl;
+    let realized = counterparty.apply_fill(slab_idx, instrument_idx, -bankrupt_fill, price);
+    counterparty.book_fill(realized, 0);
+    registry.global_haircut.track_pnl_change(pnl_before, counterparty.pnl);
+
+    refresh_margin(bankrupt, registry, now_ts)?;
+    refresh_margin(counterparty, registry, now_ts)?;
+
+    // Equity recovered by the bankrupt account is bad debt no longer owed
+    let recovered = bankrupt.equity.min(0).saturating_sub(equity_before);
+    if recovered > 0 {
+        registry.insurance_state.recover_bad_debt(recovered as u128);
+        bankrupt.booked_bad_debt = bankrupt.booked_bad_debt.saturating_sub(recovered as u64);
+    }
+
+    msg!("AutoDeleverage executed against counterparty");
+    Ok(AdlFill { qty, price, score })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+
+    const S: i64 = 1_000_000;
+
+    fn registry() -> SlabRegistry {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry
+            .register_slab(Pubkey::from([1; 32]), [0; 32], Pubkey::default(), 0, 0, 0, 0, 0, 0, 0)
+            .unwrap();
+        registry.find_or_add_instrument(&Pubkey::from([10; 32])).unwrap();
+        registry.set_instrument_mark(0, 100 * S, 0);
+        registry
+    }
+
+    /// Portfolio holding `qty` at `entry` with the given realized equity
+    fn portfolio(user: u8, qty: i64, entry: i64, equity: i64) -> Portfolio {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::from([user; 32]), 0);
+        portfolio.apply_fill(0, 0, qty, entry);
+        portfolio.update_equity(equity as i128);
+        portfolio
+    }
+
+    #[test]
+    fn test_score_and_bankruptcy_price() {
+        // Short 10 from $120 at mark $100: $200 profit, $1000 notional, $500 equity
+        assert_eq!(adl_score(-10 * S, 120 * S, 100 * S, 500 * S as i128), 400 * S as u128);
+        // Same profit at half the equity ranks twice as high
+        assert_eq!(adl_score(-10 * S, 120 * S, 100 * S, 250 * S as i128), 800 * S as u128);
+        // Losing position is not eligible
+        assert_eq!(adl_score(10 * S, 120 * S, 100 * S, 500 * S as i128), 0);
+
+        // Long 10 from $100 with -$50 equity goes bankrupt at $105
+        assert_eq!(bankruptcy_price(10 * S, 100 * S, -50 * S as i128), 105 * S);
+        assert_eq!(bankruptcy_price(-10 * S, 100 * S, -50 * S as i128), 95 * S);
+        assert_eq!(bankruptcy_price(-10 * S, S, -50 * S as i128), 1);
+    }
+
+    #[test]
+    fn test_adl_closes_at_bankruptcy_price() {
+        let mut registry = registry();
+        registry.insurance_state.uncovered_bad_debt = 80 * S as u128;
+        let mut bankrupt = portfolio(1, 10 * S, 100 * S, -50 * S);
+        let mut winner = portfolio(2, -10 * S, 120 * S, 500 * S);
+
+        let fill = process_auto_deleverage(&mut bankrupt, &mut winner, &mut registry, 0, 0, u128::MAX, 0).unwrap();
+
+        assert_eq!(fill.qty, 10 * S);
+        assert_eq!(fill.price, 105 * S);
+        assert_eq!(bankrupt.get_exposure(0, 0), 0);
+        assert_eq!(bankrupt.equity, 0);
+        assert_eq!(winner.get_exposure(0, 0), 0);
+        // Winner realizes $150 instead of the $200 available at mark
+        assert_eq!(winner.equity, 650 * S as i128);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, 30 * S as u128);
+    }
+
+    #[test]
+    fn test_adl_rejects_same_side_and_rank_order() {
+        let mut registry = registry();
+        registry.insurance_state.uncovered_bad_debt = 80 * S as u128;
+        let mut bankrupt = portfolio(1, 10 * S, 100 * S, -50 * S);
+        let mut same_side = portfolio(2, 5 * S, 90 * S, 500 * S);
+        let mut winner = portfolio(3, -4 * S, 120 * S, 500 * S);
+
+        assert_eq!(
+            process_auto_deleverage(&mut bankrupt, &mut same_side, &mut registry, 0, 0, u128::MAX, 0),
+            Err(PercolatorError::InvalidPortfolio)
+        );
+        assert_eq!(
+            process_auto_deleverage(&mut bankrupt, &mut winner, &mut registry, 0, 0, 1, 0),
+            Err(PercolatorError::AdlRankViolation)
+        );
+
+        // Partial close keeps the bankruptcy price for the remainder
+        let fill = process_auto_deleverage(&mut bankrupt, &mut winner, &mut registry, 0, 0, u128::MAX, 0).unwrap();
+        assert_eq!(fill.qty, 4 * S);
+        assert_eq!(fill.price, 105 * S);
+        assert_eq!(bankrupt.get_exposure(0, 0), 6 * S);
+        assert_eq!(bankrupt.equity, -30 * S as i128);
+    }
+
+    #[test]
+    fn test_adl_requires_uncovered_bad_debt() {
+        let mut registry = registry();
+        let mut bankrupt = portfolio(1, 10 * S, 100 * S, -50 * S);
+        let mut winner = portfolio(2, -10 * S, 120 * S, 500 * S);
+
+        // Insurance or the haircut already covered the deficit
+        assert_eq!(
+            process_auto_deleverage(&mut bankrupt, &mut winner, &mut registry, 0, 0, u128::MAX, 0),
+            Err(PercolatorError::PortfolioHealthy)
+        );
+        assert_eq!(winner.get_exposure(0, 0), -10 * S);
+    }
+}
@@ -0,0 +1,382 @@
+//! Escrow pledges and capability (Cap) debits
+//!
+//! A user pledges quote principal to an escrow for one (user, slab, mint)
+//! triple; the funds stay in the router vault, marked pledged. The user
+//! then issues Caps against the escrow: each consumes the escrow's next
+//! nonce, expires within `MAX_CAP_TTL_MS` and lets that slab's LP owner
+//! debit at most `amount_max`. Debits move value from the escrow to the
+//! LP owner's portfolio, so a slab can settle on its own without ever
+//! touching a token account. Total debits are bounded by
+//! `min(cap.remaining, escrow.balance)`, and the user can only release
+//! the escrow once every Cap issued against it has expired.
+
+use crate::state::{Cap, Escrow, Portfolio, SlabRegistry, Vault};
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Process pledge_escrow instruction
+///
+/// Moves `amount` of principal into the escrow after the same margin
+/// check a withdrawal gets, and pledges it on the vault so it cannot be
+/// withdrawn from under the escrow. Escrows hold quote collateral only.
+///
+/// # Arguments
+/// * `escrow` - Initialized escrow for (user, slab, vault mint)
+/// * `vault` - Collateral vault for the escrow's mint
+/// * `portfolio` - User's portfolio account
+/// * `registry` - Slab registry (slab whitelist, margin and touch state)
+/// * `user` - User signer
+/// * `amount` - Amount to pledge (base units)
+/// * `now_ts` - Clock sysvar timestamp (mark freshness)
+pub fn process_pledge_escrow(
+    escrow: &mut Escrow,
+    vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    user: &Pubkey,
+    amount: u128,
+    now_ts: u64,
+) -> Result<(), PercolatorError> {
+    validate_escrow_owner(escrow, portfolio, vault, user)?;
+    if amount == 0 {
+        return Err(PercolatorError::InvalidAmount);
+    }
+    if registry.find_collateral(&vault.mint).is_some() {
+        msg!("Error: Escrows hold quote collateral only");
+        return Err(PercolatorError::InvalidMint);
+    }
+    if registry.find_slab(&escrow.slab).is_none() {
+        msg!("Error: Slab not registered or inactive");
+        return Err(PercolatorError::SlabNotRegistered);
+    }
+
+    // Pledged principal leaves equity: same check as a withdrawal
+    use crate::instructions::touch_portfolio;
+    use crate::margin::refresh_margin;
+    touch_portfolio(portfolio, registry);
+    refresh_margin(portfolio, registry, now_ts)?;
+    if !portfolio.withdrawal_keeps_margin(amount) {
+        msg!("Error: Pledge would breach initial margin");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    portfolio
+        .debit_principal(amount)
+        .map_err(|_| PercolatorError::InsufficientBalance)?;
+    vault.pledge(amount).map_err(|_| PercolatorError::InsufficientFunds)?;
+    escrow.credit(amount);
+
+    msg!("PledgeEscrow executed successfully");
+    Ok(())
+}
+
+/// Process release_escrow instruction
+///
+/// Returns pledged funds to the user's principal. Rejected while any Cap
+/// issued against the escrow is still live.
+///
+/// # Arguments
+/// * `escrow` - User's escrow
+/// * `vault` - Collateral vault for the escrow's mint
+/// * `portfolio` - User's portfolio account
+/// * `user` - User signer
+/// * `amount` - Amount to release (base units)
+/// * `now_ms` - Current time (milliseconds)
+pub fn process_release_escrow(
+    escrow: &mut Escrow,
+    vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    user: &Pubkey,
+    amount: u128,
+    now_ms: u64,
+) -> Result<(), PercolatorError> {
+    validate_escrow_owner(escrow, portfolio, vault, user)?;
+    if amount == 0 {
+        return Err(PercolatorError::InvalidAmount);
+    }
+    if now_ms < escrow.caps_expiry_ms {
+        msg!("Error: Escrow has live caps");
+        return Err(PercolatorError::EscrowCapsOutstanding);
+    }
+
+    escrow
+        .debit(amount)
+        .map_err(|_| PercolatorError::EscrowInsufficientBalance)?;
+    vault.unpledge(amount);
+    portfolio.credit_principal(amount);
+
+    msg!("ReleaseEscrow executed successfully");
+    Ok(())
+}
+
+/// Process issue_cap instruction
+///
+/// Issues a Cap for the escrow's (user, slab, mint) scope using the
+/// escrow's next nonce. The TTL is clamped to `MAX_CAP_TTL_MS`.
+///
+/// # Arguments
+/// * `cap` - Unissued Cap account (PDA for the escrow's current nonce)
+/// * `escrow` - User's escrow
+/// * `escrow_key` - Escrow account pubkey
+/// * `user` - User signer
+/// * `amount_max` - Maximum total debit
+/// * `ttl_ms` - Requested lifetime (milliseconds)
+/// * `now_ms` - Current time (milliseconds)
+/// * `bump` - Cap PDA bump
+///
+/// # Returns
+/// * The nonce the cap consumed
+pub fn process_issue_cap(
+    cap: &mut Cap,
+    escrow: &mut Escrow,
+    escrow_key: &Pubkey,
+    user: &Pubkey,
+    amount_max: u128,
+    ttl_ms: u64,
+    now_ms: u64,
+    bump: u8,
+) -> Result<u64, PercolatorError> {
+    if &escrow.user != user {
+        msg!("Error: Escrow does not belong to user");
+        return Err(PercolatorError::Unauthorized);
+    }
+    if cap.is_issued() {
+        msg!("Error: Cap already issued");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if amount_max == 0 || ttl_ms == 0 {
+        return Err(PercolatorError::InvalidAmount);
+    }
+
+    let nonce = escrow.next_nonce();
+    cap.issue_in_place(
+        escrow.router_id,
+        *escrow_key,
+        escrow.user,
+        escrow.slab,
+        escrow.mint,
+        amount_max,
+        now_ms,
+        ttl_ms,
+        nonce,
+        bump,
+    );
+    escrow.caps_expiry_ms = escrow.caps_expiry_ms.max(cap.expiry_ms);
+
+    msg!("IssueCap executed successfully");
+    Ok(nonce)
+}
+
+/// Process cap_debit instruction
+///
+/// Debits `amount` from the escrow under a live, correctly scoped Cap and
+/// credits it to the slab LP owner's principal. Signed by the slab's LP
+/// owner; the slab must be registered and active.
+///
+/// # Arguments
+/// * `cap` - Cap authorizing the debit
+/// * `escrow` - Escrow being debited
+/// * `escrow_key` - Escrow account pubkey
+/// * `vault` - Collateral vault for the escrow's mint
+/// * `lp_portfolio` - Portfolio of the slab's LP owner
+/// * `registry` - Slab registry (slab whitelist)
+/// * `slab_key` - Slab account pubkey
+/// * `header` - Slab header (LP owner)
+/// * `signer` - Signer (must be the slab's LP owner)
+/// * `amount` - Amount to debit (base units)
+/// * `now_ms` - Current time (milliseconds)
+pub fn process_cap_debit(
+    cap: &mut Cap,
+    escrow: &mut Escrow,
+    escrow_key: &Pubkey,
+    vault: &mut Vault,
+    lp_portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    slab_key: &Pubkey,
+    header: &SlabHeader,
+    signer: &Pubkey,
+    amount: u128,
+    now_ms: u64,
+) -> Result<(), PercolatorError> {
+    if registry.find_slab(slab_key).is_none() {
+        msg!("Error: Slab not registered or inactive");
+        return Err(PercolatorError::SlabNotRegistered);
+    }
+    if &header.lp_owner != signer {
+        msg!("Error: Signer is not the slab's LP owner");
+        return Err(PercolatorError::Unauthorized);
+    }
+    if lp_portfolio.user != header.lp_owner {
+        msg!("Error: Portfolio does not belong to the slab's LP owner");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    // Scope: cap, escrow and vault must all describe (user, slab, mint)
+    if &cap.escrow != escrow_key
+        || !cap.in_scope(&escrow.user, slab_key, &vault.mint)
+        || &escrow.slab != slab_key
+        || escrow.mint != vault.mint
+    {
+        msg!("Error: Cap scope does not match escrow, slab or mint");
+        return Err(PercolatorError::CapInvalidScope);
+    }
+    if cap.is_expired(now_ms) {
+        msg!("Error: Cap expired");
+        return Err(PercolatorError::CapExpired);
+    }
+    if amount == 0 {
+        return Err(PercolatorError::InvalidAmount);
+    }
+    if escrow.balance < amount {
+        msg!("Error: Insufficient escrow balance");
+        return Err(PercolatorError::EscrowInsufficientBalance);
+    }
+
+    cap.consume(amount).map_err(|_| {
+        msg!("Error: Debit exceeds cap remaining");
+        PercolatorError::CapInsufficientRemaining
+    })?;
+    escrow
+        .debit(amount)
+        .map_err(|_| PercolatorError::EscrowInsufficientBalance)?;
+    vault.unpledge(amount);
+    lp_portfolio.credit_principal(amount);
+
+    msg!("CapDebit executed successfully");
+    Ok(())
+}
+
+/// Verify the escrow, portfolio and vault all belong to the signing user
+fn validate_escrow_owner(
+    escrow: &Escrow,
+    portfolio: &Portfolio,
+    vault: &Vault,
+    user: &Pubkey,
+) -> Result<(), PercolatorError> {
+    if &escrow.user != user || &portfolio.user != user {
+        msg!("Error: Escrow or portfolio does not belong to user");
+        return Err(PercolatorError::Unauthorized);
+    }
+    if escrow.mint != vault.mint {
+        msg!("Error: Vault mint does not match escrow");
+        return Err(PercolatorError::InvalidMint);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: Pubkey = [1; 32];
+    const SLAB: Pubkey = [2; 32];
+    const MINT: Pubkey = [3; 32];
+    const LP: Pubkey = [4; 32];
+    const ESCROW: Pubkey = [5; 32];
+
+    fn registry() -> SlabRegistry {
+        let mut registry = SlabRegistry::new([9; 32], [0; 32], 0);
+        registry.register_slab(SLAB, [0; 32], [0; 32], 0, 0, 0, 0, 0, 0, 0).unwrap();
+        registry
+    }
+
+    fn vault() -> Vault {
+        Vault {
+            router_id: [9; 32],
+            mint: MINT,
+            token_account: [6; 32],
+            balance: 10_000,
+            total_pledged: 0,
+            bump: 0,
+            _padding: [0; 7],
+        }
+    }
+
+    fn escrow() -> Escrow {
+        let mut escrow: Escrow = unsafe { core::mem::zeroed() };
+        escrow.initialize_in_place([9; 32], USER, SLAB, MINT, 0);
+        escrow
+    }
+
+    fn header() -> SlabHeader {
+        SlabHeader::new([7; 32], LP, [9; 32], [8; 32], 1_000_000, 0, 1_000_000, 0)
+    }
+
+    #[test]
+    fn test_pledge_and_release() {
+        let mut registry = registry();
+        let mut vault = vault();
+        let mut escrow = escrow();
+        let mut portfolio = Portfolio::new([9; 32], USER, 0);
+        portfolio.credit_principal(10_000);
+
+        assert_eq!(
+            process_pledge_escrow(&mut escrow, &mut vault, &mut portfolio, &mut registry, &LP, 100, 0),
+            Err(PercolatorError::Unauthorized)
+        );
+        process_pledge_escrow(&mut escrow, &mut vault, &mut portfolio, &mut registry, &USER, 4_000, 0).unwrap();
+        assert_eq!(escrow.balance, 4_000);
+        assert_eq!(vault.total_pledged, 4_000);
+        assert_eq!(portfolio.principal, 6_000);
+
+        // Live caps lock the escrow
+        escrow.caps_expiry_ms = 5_000;
+        assert_eq!(
+            process_release_escrow(&mut escrow, &mut vault, &mut portfolio, &USER, 1_000, 4_999),
+            Err(PercolatorError::EscrowCapsOutstanding)
+        );
+        assert_eq!(
+            process_release_escrow(&mut escrow, &mut vault, &mut portfolio, &USER, 4_001, 5_000),
+            Err(PercolatorError::EscrowInsufficientBalance)
+        );
+        process_release_escrow(&mut escrow, &mut vault, &mut portfolio, &USER, 4_000, 5_000).unwrap();
+        assert_eq!(escrow.balance, 0);
+        assert_eq!(vault.total_pledged, 0);
+        assert_eq!(portfolio.principal, 10_000);
+    }
+
+    #[test]
+    fn test_issue_and_debit_cap() {
+        let registry = registry();
+        let mut vault = vault();
+        vault.total_pledged = 1_000;
+        let mut escrow = escrow();
+        escrow.credit(1_000);
+        let mut cap: Cap = unsafe { core::mem::zeroed() };
+        let mut lp = Portfolio::new([9; 32], LP, 0);
+
+        assert_eq!(process_issue_cap(&mut cap, &mut escrow, &ESCROW, &USER, 600, 600_000, 1_000, 0), Ok(0));
+        assert_eq!(escrow.nonce, 1);
+        assert_eq!(escrow.caps_expiry_ms, 1_000 + MAX_CAP_TTL_MS);
+        // Same cap account cannot be issued twice
+        assert_eq!(
+            process_issue_cap(&mut cap, &mut escrow, &ESCROW, &USER, 600, 1_000, 1_000, 0),
+            Err(PercolatorError::InvalidAccount)
+        );
+
+        // Only the slab's LP owner may debit, and only within scope
+        assert_eq!(
+            process_cap_debit(&mut cap, &mut escrow, &ESCROW, &mut vault, &mut lp, &registry, &SLAB, &header(), &USER, 100, 2_000),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(
+            process_cap_debit(&mut cap, &mut escrow, &[0; 32], &mut vault, &mut lp, &registry, &SLAB, &header(), &LP, 100, 2_000),
+            Err(PercolatorError::CapInvalidScope)
+        );
+
+        process_cap_debit(&mut cap, &mut escrow, &ESCROW, &mut vault, &mut lp, &registry, &SLAB, &header(), &LP, 400, 2_000).unwrap();
+        assert_eq!(cap.remaining, 200);
+        assert_eq!(escrow.balance, 600);
+        assert_eq!(vault.total_pledged, 600);
+        assert_eq!(lp.principal, 400);
+
+        assert_eq!(
+            process_cap_debit(&mut cap, &mut escrow, &ESCROW, &mut vault, &mut lp, &registry, &SLAB, &header(), &LP, 201, 2_000),
+            Err(PercolatorError::CapInsufficientRemaining)
+        );
+        assert_eq!(
+            process_cap_debit(&mut cap, &mut escrow, &ESCROW, &mut vault, &mut lp, &registry, &SLAB, &header(), &LP, 100, 1_000 + MAX_CAP_TTL_MS),
+            Err(PercolatorError::CapExpired)
+        );
+    }
+}
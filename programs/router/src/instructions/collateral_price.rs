@@ -0,0 +1,98 @@
+//! Collateral price crank
+//!
+//! A permissionless crank copies each collateral mint's oracle price into
+//! the registry. Margin refreshes value non-quote balances at that cached
+//! price, the same way exposures are valued at cached instrument marks.
+
+use crate::liquidation::oracle::check_oracle_price;
+use crate::state::SlabRegistry;
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Process update_collateral_price instruction
+///
+/// The oracle must be the account governance registered for the mint its
+/// `instrument` names, and its reading must pass the registry's freshness
+/// and confidence guards.
+///
+/// # Arguments
+/// * `registry` - Slab registry holding the collateral table
+/// * `oracle_key` - Oracle account pubkey
+/// * `oracle` - Oracle account state
+/// * `now_ts` - Current unix timestamp
+///
+/// # Returns
+/// * The collateral slot that was priced
+pub fn process_update_collateral_price(
+    registry: &mut SlabRegistry,
+    oracle_key: &Pubkey,
+    oracle: &PriceOracle,
+    now_ts: u64,
+) -> Result<usize, PercolatorError> {
+    if !oracle.validate() {
+        msg!("Error: Invalid oracle account data");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let idx = registry.find_collateral(&oracle.instrument).ok_or_else(|| {
+        msg!("Error: Oracle mint is not registered collateral");
+        PercolatorError::InvalidAccount
+    })?;
+    if &registry.collaterals[idx].oracle != oracle_key {
+        msg!("Error: Oracle is not the registered feed for this collateral");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let price = check_oracle_price(
+        oracle,
+        now_ts,
+        registry.oracle_max_age_secs,
+        registry.oracle_max_conf_bps,
+    )?;
+
+    let entry = &mut registry.collaterals[idx];
+    entry.price = price;
+    entry.price_ts = oracle.timestamp as u64;
+
+    msg!("UpdateCollateralPrice executed successfully");
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: Pubkey = [20; 32];
+    const ORACLE: Pubkey = [21; 32];
+
+    #[test]
+    fn test_collateral_price_crank() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry.set_collateral(MINT, ORACLE, 8_000, 9).unwrap();
+
+        let mut oracle = PriceOracle::new([1; 32], MINT, 150_000_000, 0);
+        oracle.update_price(150_000_000, 1_000, 100_000);
+
+        // Wrong feed for the mint
+        assert_eq!(
+            process_update_collateral_price(&mut registry, &[22; 32], &oracle, 1_000),
+            Err(PercolatorError::InvalidAccount)
+        );
+        // Stale reading
+        assert_eq!(
+            process_update_collateral_price(&mut registry, &ORACLE, &oracle, 2_000),
+            Err(PercolatorError::StalePrice)
+        );
+
+        assert_eq!(process_update_collateral_price(&mut registry, &ORACLE, &oracle, 1_010), Ok(0));
+        assert_eq!(registry.collaterals[0].price, 150_000_000);
+        assert_eq!(registry.collaterals[0].price_ts, 1_000);
+
+        // Unregistered mint
+        let other = PriceOracle::new([1; 32], [23; 32], 1_000_000, 0);
+        assert_eq!(
+            process_update_collateral_price(&mut registry, &ORACLE, &other, 1_010),
+            Err(PercolatorError::InvalidAccount)
+        );
+    }
+}
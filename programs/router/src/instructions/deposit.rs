@@ -1,23 +1,88 @@
 //! Deposit instruction - deposit collateral to vault
 
-use crate::state::Vault;
+use crate::state::{Portfolio, SlabRegistry, Vault};
+use crate::token::{transfer, validate_token_program};
 use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
 
 /// Process deposit instruction
 ///
-/// Deposits collateral from user's token account to the router vault.
-/// Updates vault balance and total_pledged tracking.
+/// Transfers collateral from the user's token account into the vault token
+/// account via SPL Token CPI, then credits vault balance and the user's
+/// portfolio so custody and accounting stay in sync. Deposits of the
+/// registry's quote mint credit principal/equity 1:1; a mint registered as
+/// weighted collateral is credited to that mint's balance and enters equity
+/// at its weighted oracle value on the next margin refresh. Any other mint
+/// is rejected.
+///
+/// # Arguments
+/// * `vault` - Collateral vault for the deposited mint
+/// * `portfolio` - User's portfolio account
+/// * `registry` - Slab registry (collateral table)
+/// * `user_token_account` - Source token account (owned by user)
+/// * `vault_token_account` - Destination token account (must match `vault.token_account`)
+/// * `user_authority` - User signer (owner of the portfolio and source account)
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount to deposit (base units)
 pub fn process_deposit(
     vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    user_token_account: &AccountInfo,
+    vault_token_account: &AccountInfo,
+    user_authority: &AccountInfo,
+    token_program: &AccountInfo,
     amount: u128,
 ) -> Result<(), PercolatorError> {
     // Validate amount
     if amount == 0 {
         return Err(PercolatorError::InvalidQuantity);
     }
+    if amount > u64::MAX as u128 {
+        msg!("Error: Deposit amount exceeds token transfer limit");
+        return Err(PercolatorError::InvalidAmount);
+    }
+
+    // Verify user authority owns the portfolio
+    validate_signer(user_authority)?;
+    if &portfolio.user != user_authority.key() {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    // Verify destination is the vault's token account
+    if vault_token_account.key() != &vault.token_account {
+        msg!("Error: Vault token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    validate_token_program(token_program)?;
 
-    // Deposit to vault
+    let collateral_idx = registry.find_collateral(&vault.mint);
+    if collateral_idx.is_none() && vault.mint != registry.quote_mint {
+        msg!("Error: Mint is neither the quote mint nor registered collateral");
+        return Err(PercolatorError::InvalidMint);
+    }
+
+    // Move tokens: user -> vault (user signs)
+    transfer(
+        user_token_account,
+        vault_token_account,
+        user_authority,
+        token_program,
+        amount as u64,
+    )?;
+
+    // Credit custody and portfolio accounting
     vault.deposit(amount);
+    match collateral_idx {
+        Some(idx) => portfolio.credit_collateral(idx, amount),
+        None => portfolio.credit_principal(amount),
+    }
+
+    emit(&Event::Deposit {
+        account: portfolio.user,
+        amount,
+    });
 
     Ok(())
 }
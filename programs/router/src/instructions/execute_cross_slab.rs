@@ -1,5 +1,6 @@
 //! Execute cross-slab order - v0 main instruction
 
+use crate::instructions::{settle_insurance_flow, InsuranceAccounts};
 use crate::state::{Portfolio, Vault, SlabRegistry};
 use percolator_common::*;
 use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
@@ -15,8 +16,18 @@ pub struct SlabSplit {
     pub side: u8,
     /// Limit price (1e6 scale)
     pub limit_px: i64,
+    /// Slab seqno of the QuoteCache the client priced off (TOCTOU check)
+    pub expected_seqno: u32,
 }
 
+/// Slippage bound that disables the pre-CPI quote depth check
+///
+/// Used by liquidations, which are bounded by their own price bands.
+pub const NO_SLIPPAGE_LIMIT: u64 = u64::MAX;
+
+/// Basis point denominator
+const BPS: i128 = 10_000;
+
 /// Process execute cross-slab order (v0 main instruction)
 ///
 /// This is the core v0 instruction that proves portfolio netting.
@@ -32,11 +43,16 @@ pub struct SlabSplit {
 /// * `router_authority` - Router authority PDA (for CPI signing)
 /// * `slab_accounts` - Array of slab accounts to execute on
 /// * `receipt_accounts` - Array of receipt PDAs (one per slab)
+/// * `lp_portfolio_accounts` - Portfolios of each slab's LP owner (one per slab, fee credit)
 /// * `splits` - How to split the order across slabs
+/// * `max_slippage_bps` - Max distance of any fill from the best quote
+/// * `insurance` - Vault token accounts the insurance accrual moves between
+/// * `now_ts` - Clock sysvar timestamp (mark freshness)
 ///
 /// # Returns
+/// * Total filled notional across all slabs (1e6 scale)
 /// * Updates portfolio with net exposures
-/// * Accrues insurance fees from taker fills
+/// * Settles taker fees to LP portfolios and the insurance vault
 /// * Checks margin on net exposure (capital efficiency!)
 /// * All-or-nothing atomicity
 pub fn process_execute_cross_slab(
@@ -47,15 +63,72 @@ pub fn process_execute_cross_slab(
     router_authority: &AccountInfo,
     slab_accounts: &[AccountInfo],
     receipt_accounts: &[AccountInfo],
+    lp_portfolio_accounts: &[AccountInfo],
     splits: &[SlabSplit],
-) -> Result<(), PercolatorError> {
+    max_slippage_bps: u64,
+    insurance: &InsuranceAccounts,
+    now_ts: u64,
+) -> Result<u128, PercolatorError> {
     // Verify portfolio belongs to user
     if &portfolio.user != user {
         msg!("Error: Portfolio does not belong to user");
         return Err(PercolatorError::InvalidPortfolio);
     }
 
-    // Apply PnL vesting and haircut catchup on user touch
+    // Apply PnL vesting, haircut catchup and funding on user touch
+    touch_portfolio(portfolio, registry);
+
+    // Verify we have one slab account per split
+    if slab_accounts.len() != splits.len() {
+        msg!("Error: Mismatched slab/split counts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // Paused slabs take no new trades (liquidations still route through them)
+    for slab_account in slab_accounts.iter() {
+        if let Some((_, entry)) = registry.find_slab(slab_account.key()) {
+            if entry.paused {
+                msg!("Error: Slab is paused");
+                return Err(PercolatorError::SlabPaused);
+            }
+        }
+    }
+
+    let insurance_before = registry.insurance_state.vault_balance;
+    let total_notional = execute_splits(
+        portfolio,
+        false,
+        registry,
+        router_authority,
+        slab_accounts,
+        receipt_accounts,
+        lp_portfolio_accounts,
+        splits,
+        max_slippage_bps,
+    )?;
+
+    // Phase 4: Calculate IM/MM on net exposure per instrument (THE CAPITAL EFFICIENCY PROOF!)
+    // Shared margin engine: same IM/MM as withdraw and liquidate
+    use crate::margin::refresh_margin;
+    refresh_margin(portfolio, registry, now_ts)?;
+
+    msg!("Calculated margin on net exposure");
+
+    // Phase 5: Check if portfolio has sufficient margin (venue-aware, incl. LP buckets)
+    if !portfolio.has_sufficient_margin_venue_aware() {
+        msg!("Error: Insufficient margin");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    // Move the insurance share of the fees into the insurance vault
+    settle_insurance_flow(insurance_before, registry, vault, insurance)?;
+
+    msg!("ExecuteCrossSlab completed successfully");
+    Ok(total_notional)
+}
+
+/// Apply PnL vesting, haircut catchup and funding before a portfolio trades
+pub(crate) fn touch_portfolio(portfolio: &mut Portfolio, registry: &mut SlabRegistry) {
     use crate::state::on_user_touch;
     use pinocchio::sysvars::{clock::Clock, Sysvar};
     let current_slot = Clock::get()
@@ -73,193 +146,592 @@ pub fn process_execute_cross_slab(
         current_slot,
     );
 
-    // Verify we have matching number of slabs and receipts
-    if slab_accounts.len() != receipt_accounts.len() || slab_accounts.len() != splits.len() {
-        msg!("Error: Mismatched slab/receipt/split counts");
+    use crate::instructions::settle_portfolio_funding;
+    settle_portfolio_funding(portfolio, registry);
+}
+
+/// Execute splits on their slabs and apply the fills, without a margin check
+///
+/// Shared by user orders (which then require IM) and liquidations (which
+/// are reduce-only and must go through while the portfolio is below IM).
+/// Each split runs on the slab account whose key equals `split.slab_id`,
+/// paired with the receipt and LP portfolio accounts at the same position,
+/// so the account list need not follow split order.
+///
+/// Fees are checked against the slab's registered caps and settled per
+/// fill: the taker pays the receipt fee, the insurance fund takes its cut
+/// plus the maker fee, and the slab's LP portfolio is credited the rest.
+///
+/// Fills are keyed by `portfolio.user` in each slab's same-batch
+/// roundtrip guard; `liquidation` fills are flagged as exempt, which the
+/// slab trusts because the router authority signs the CPI.
+///
+/// # Returns
+/// * Total filled notional across all slabs (1e6 scale)
+pub(crate) fn execute_splits(
+    portfolio: &mut Portfolio,
+    liquidation: bool,
+    registry: &mut SlabRegistry,
+    router_authority: &AccountInfo,
+    slab_accounts: &[AccountInfo],
+    receipt_accounts: &[AccountInfo],
+    lp_portfolio_accounts: &[AccountInfo],
+    splits: &[SlabSplit],
+    max_slippage_bps: u64,
+) -> Result<u128, PercolatorError> {
+    // Verify we have matching number of slabs, receipts and LP portfolios
+    if slab_accounts.len() != receipt_accounts.len()
+        || slab_accounts.len() != lp_portfolio_accounts.len()
+    {
+        msg!("Error: Mismatched slab/receipt/LP portfolio counts");
         return Err(PercolatorError::InvalidInstruction);
     }
 
-    // Verify router_authority is the correct PDA
-    use crate::pda::derive_authority_pda;
-    let (expected_authority, authority_bump) = derive_authority_pda(&portfolio.router_id);
-    if router_authority.key() != &expected_authority {
-        msg!("Error: Invalid router authority PDA");
-        return Err(PercolatorError::InvalidAccount);
-    }
+    let authority_bump = validate_router_authority(&portfolio.router_id, router_authority)?;
+    validate_distinct_slabs(splits)?;
 
-    // Phase 1: Read QuoteCache from each slab (v0 - skip validation for now)
-    // In production, we'd validate seqno consistency here (TOCTOU safety)
+    // Phase 1: Validate every slab's book against the quote the client priced off
+    // (TOCTOU safety) before any CPI, so a stale quote fails the whole order cleanly
+    for split in splits.iter() {
+        let i = find_slab_account(slab_accounts, &split.slab_id)?;
+        validate_quote(&slab_accounts[i], split, max_slippage_bps)?;
+    }
 
     // Phase 2: CPI to each slab's commit_fill
     msg!("Executing fills on slabs");
 
-    for (i, split) in splits.iter().enumerate() {
+    let mut total_notional: u128 = 0;
+
+    for split in splits.iter() {
+        let i = find_slab_account(slab_accounts, &split.slab_id)?;
         let slab_account = &slab_accounts[i];
         let receipt_account = &receipt_accounts[i];
 
-        // Get slab program ID from account owner
-        let slab_program_id = slab_account.owner();
+        // Resolve exposure key via the registry; the slab rejects the fill
+        // if its seqno moved past the one validated in phase 1
+        let (slab_idx, instrument_idx) = resolve_slab(registry, slab_account)?;
+        let expected_seqno = split.expected_seqno;
 
-        // Read current seqno from slab for TOCTOU protection
-        let slab_data = slab_account
-            .try_borrow_data()
-            .map_err(|_| PercolatorError::InvalidAccount)?;
-        if slab_data.len() < 4 {
-            msg!("Error: Invalid slab account data");
-            return Err(PercolatorError::InvalidAccount);
-        }
-        // Seqno is at offset 0 in SlabHeader (first field)
-        let expected_seqno = u32::from_le_bytes([
-            slab_data[0],
-            slab_data[1],
-            slab_data[2],
-            slab_data[3],
-        ]);
-
-        // Build commit_fill instruction data (22 bytes total)
+        // Build commit_fill instruction data (55 bytes total)
         // Layout: discriminator (1) + expected_seqno (4) + side (1) + qty (8) + limit_px (8)
-        let mut instruction_data = [0u8; 22];
+        // + taker (32) + liquidation (1)
+        let mut instruction_data = [0u8; 55];
         instruction_data[0] = 1; // CommitFill discriminator
         instruction_data[1..5].copy_from_slice(&expected_seqno.to_le_bytes());
         instruction_data[5] = split.side;
         instruction_data[6..14].copy_from_slice(&split.qty.to_le_bytes());
         instruction_data[14..22].copy_from_slice(&split.limit_px.to_le_bytes());
+        instruction_data[22..54].copy_from_slice(&portfolio.user);
+        instruction_data[54] = liquidation as u8;
 
         // Build account metas for CPI
         // 0. slab_account (writable)
         // 1. receipt_account (writable)
         // 2. router_authority (signer PDA)
-        use pinocchio::{
-            instruction::{AccountMeta, Instruction},
-            program::invoke_signed,
-        };
-
+        use pinocchio::instruction::AccountMeta;
         let account_metas = [
             AccountMeta::writable(slab_account.key()),
             AccountMeta::writable(receipt_account.key()),
             AccountMeta::writable_signer(router_authority.key()),
         ];
+        invoke_slab(
+            slab_account,
+            &account_metas,
+            &instruction_data,
+            &[slab_account, receipt_account, router_authority],
+            authority_bump,
+        )?;
+
+        // Phase 3: Read the receipt the slab wrote and apply the actual fill
+        let receipt = read_fill_receipt(slab_account, receipt_account, expected_seqno)?;
+        let notional = apply_fill_receipt(
+            portfolio,
+            registry,
+            slab_account,
+            &lp_portfolio_accounts[i],
+            slab_idx,
+            instrument_idx,
+            split.side,
+            split.qty,
+            &receipt,
+        )?;
+
+        // Accumulate notional for insurance accrual
+        total_notional = total_notional.saturating_add(notional);
+    }
 
-        let instruction = Instruction {
-            program_id: slab_program_id,
-            accounts: &account_metas,
-            data: &instruction_data,
-        };
+    // Snapshot funding for exposures opened by these fills
+    use crate::instructions::settle_portfolio_funding;
+    settle_portfolio_funding(portfolio, registry);
 
-        // Sign the CPI with router authority PDA
-        use crate::pda::AUTHORITY_SEED;
-        use pinocchio::instruction::{Seed, Signer};
+    Ok(total_notional)
+}
 
-        let bump_array = [authority_bump];
-        let seeds = &[
-            Seed::from(AUTHORITY_SEED),
-            Seed::from(&bump_array[..]),
-        ];
-        let signer = Signer::from(seeds);
+/// Apply a slab's fill receipt to the taker portfolio
+///
+/// Validates the receipt against the requested quantity and the slab's
+/// fee caps, updates the exposure (realizing PnL against entry VWAP),
+/// books the taker fee and settles it between the LP and the insurance
+/// fund, then emits the fill event.
+///
+/// # Returns
+/// * Filled notional (1e6 scale)
+pub(crate) fn apply_fill_receipt(
+    portfolio: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    slab_account: &AccountInfo,
+    lp_portfolio_account: &AccountInfo,
+    slab_idx: u16,
+    instrument_idx: u16,
+    side: u8,
+    requested_qty: i64,
+    receipt: &FillReceipt,
+) -> Result<u128, PercolatorError> {
+    let filled_qty = validate_fill(receipt, requested_qty)?;
+    let entry = &registry.slabs[slab_idx as usize];
+    validate_fees(receipt, entry.taker_fee_cap, entry.maker_fee_cap)?;
+
+    // Update portfolio exposure keyed by registry slab index and instrument
+    let realized_pnl = apply_fill_to_exposure(
+        portfolio,
+        slab_idx,
+        instrument_idx,
+        side,
+        filled_qty,
+        receipt.vwap_px,
+    );
 
-        invoke_signed(
-            &instruction,
-            &[slab_account, receipt_account, router_authority],
-            &[signer],
-        )
-        .map_err(|_| PercolatorError::CpiFailed)?;
+    // Book realized PnL (against entry VWAP) and the receipt fee
+    let pnl_before = portfolio.pnl;
+    portfolio.book_fill(realized_pnl, receipt.fee as i128);
+    registry.global_haircut.track_pnl_change(pnl_before, portfolio.pnl);
+
+    // Settle the taker's fee between the LP and the insurance fund
+    let (lp_credit, insurance_take) = split_fill_fees(
+        receipt.fee,
+        receipt.maker_fee,
+        registry.insurance_params.fee_bps_to_insurance,
+    )?;
+    registry.insurance_state.accrue_trading_fee(insurance_take);
+    credit_lp_fees(portfolio, registry, slab_account, lp_portfolio_account, lp_credit)?;
+
+    emit(&Event::Fill {
+        venue: *slab_account.key(),
+        account: portfolio.user,
+        side,
+        qty: filled_qty,
+        price: receipt.vwap_px,
+        fee: receipt.fee,
+        seqno: receipt.seqno_committed,
+    });
+
+    Ok(receipt.notional.unsigned_abs() as u128)
+}
+
+/// Verify the router authority PDA
+///
+/// # Returns
+/// * The authority bump for signing slab CPIs
+pub(crate) fn validate_router_authority(
+    router_id: &Pubkey,
+    router_authority: &AccountInfo,
+) -> Result<u8, PercolatorError> {
+    use crate::pda::derive_authority_pda;
+    let (expected_authority, authority_bump) = derive_authority_pda(router_id);
+    if router_authority.key() != &expected_authority {
+        msg!("Error: Invalid router authority PDA");
+        return Err(PercolatorError::InvalidAccount);
     }
+    Ok(authority_bump)
+}
+
+/// CPI into a slab's program, signed by the router authority PDA
+///
+/// The slab program ID is taken from the slab account's owner.
+pub(crate) fn invoke_slab<const N: usize>(
+    slab_account: &AccountInfo,
+    account_metas: &[pinocchio::instruction::AccountMeta],
+    instruction_data: &[u8],
+    accounts: &[&AccountInfo; N],
+    authority_bump: u8,
+) -> Result<(), PercolatorError> {
+    use crate::pda::AUTHORITY_SEED;
+    use pinocchio::{
+        instruction::{Instruction, Seed, Signer},
+        program::invoke_signed,
+    };
+
+    let instruction = Instruction {
+        program_id: slab_account.owner(),
+        accounts: account_metas,
+        data: instruction_data,
+    };
+
+    let bump_array = [authority_bump];
+    let seeds = &[
+        Seed::from(AUTHORITY_SEED),
+        Seed::from(&bump_array[..]),
+    ];
+    let signer = Signer::from(seeds);
+
+    invoke_signed(&instruction, accounts, &[signer]).map_err(|_| PercolatorError::CpiFailed)
+}
 
-    // Phase 3: Aggregate fills and update portfolio
-    // For each split, update the portfolio exposure
+/// Reject orders that route more than one split to the same slab
+///
+/// Each split is checked against the seqno the client quoted, and the
+/// first fill on a slab moves its seqno, so a second split there could
+/// never pass. Quantities for one slab belong in a single split.
+pub(crate) fn validate_distinct_slabs(splits: &[SlabSplit]) -> Result<(), PercolatorError> {
     for (i, split) in splits.iter().enumerate() {
-        // In v0, assume fill is successful
-        let filled_qty = split.qty;
+        if splits[..i].iter().any(|prev| prev.slab_id == split.slab_id) {
+            msg!("Error: Duplicate slab in splits, combine them into one split");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+    }
+    Ok(())
+}
 
-        // Update portfolio exposure for this slab/instrument
-        // For v0, we'll use slab index and instrument 0 (simplified)
-        let slab_idx = i as u16;
-        let instrument_idx = 0u16;
+/// Find the position of a slab in the account list
+pub(crate) fn find_slab_account(
+    slab_accounts: &[AccountInfo],
+    slab_id: &Pubkey,
+) -> Result<usize, PercolatorError> {
+    slab_accounts
+        .iter()
+        .position(|account| account.key() == slab_id)
+        .ok_or_else(|| {
+            msg!("Error: Split slab account not provided");
+            PercolatorError::InvalidAccount
+        })
+}
 
-        // Get current exposure
-        let current_exposure = portfolio.get_exposure(slab_idx, instrument_idx);
+/// Resolve a slab account to its exposure key
+///
+/// The slab must be registered and active in the registry; its registry
+/// index is the exposure slab index. The instrument index comes from the
+/// registry's instrument table keyed by `SlabHeader.instrument`, so the
+/// same slab and instrument always land under the same key regardless of
+/// account order. The header mark is never trusted for margin; marks come
+/// only from registry-bound oracles.
+///
+/// # Returns
+/// * `(slab_idx, instrument_idx)`
+pub(crate) fn resolve_slab(
+    registry: &mut SlabRegistry,
+    slab_account: &AccountInfo,
+) -> Result<(u16, u16), PercolatorError> {
+    let (slab_idx, _) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+        msg!("Error: Slab not registered or inactive");
+        PercolatorError::SlabNotRegistered
+    })?;
+
+    let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+    if &header.magic != SlabHeader::MAGIC {
+        msg!("Error: Invalid slab account data");
+        return Err(PercolatorError::InvalidAccount);
+    }
 
-        // Update based on side: Buy = add qty, Sell = subtract qty
-        let new_exposure = if split.side == 0 {
-            // Buy
-            current_exposure + filled_qty
-        } else {
-            // Sell
-            current_exposure - filled_qty
-        };
+    let instrument_idx = registry
+        .find_or_add_instrument(&header.instrument)
+        .map_err(|_| PercolatorError::InvalidInstrument)?;
+
+    Ok((slab_idx, instrument_idx))
+}
 
-        portfolio.update_exposure(slab_idx, instrument_idx, new_exposure);
+/// Validate a slab's book against the quote a split was priced off
+///
+/// Both the slab header seqno and its QuoteCache snapshot must still equal
+/// the seqno the client observed; any book change since then fails with
+/// `SeqnoMismatch`. The cached levels are then checked for slippage.
+pub(crate) fn validate_quote(
+    slab_account: &AccountInfo,
+    split: &SlabSplit,
+    max_slippage_bps: u64,
+) -> Result<(), PercolatorError> {
+    let header = unsafe { *borrow_account_data::<SlabHeader>(slab_account)? };
+    if &header.magic != SlabHeader::MAGIC {
+        msg!("Error: Invalid slab account data");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if header.seqno != split.expected_seqno {
+        msg!("Error: Slab book changed since quote");
+        return Err(PercolatorError::SeqnoMismatch);
     }
 
-    // Phase 3.5: Accrue insurance fees from taker fills
-    // Calculate total notional across all splits and accrue insurance
-    let mut total_notional: u128 = 0;
-    for split in splits.iter() {
-        // Notional = qty * price (both in 1e6 scale, so divide by 1e6)
-        // For v0 simplified: use limit_px as execution price
-        let notional = ((split.qty.abs() as u128) * (split.limit_px.abs() as u128)) / 1_000_000;
-        total_notional = total_notional.saturating_add(notional);
+    let cache = read_quote_cache(slab_account, &header)?;
+    if cache.seqno_snapshot != split.expected_seqno {
+        msg!("Error: Quote cache does not match quoted seqno");
+        return Err(PercolatorError::SeqnoMismatch);
     }
 
-    if total_notional > 0 {
-        let accrual = registry.insurance_state.accrue_from_fill(
-            total_notional,
-            &registry.insurance_params,
-        );
-        if accrual > 0 {
-            msg!("Insurance accrued from fills");
+    if max_slippage_bps != NO_SLIPPAGE_LIMIT {
+        check_slippage(&cache, split, max_slippage_bps)?;
+    }
+    Ok(())
+}
+
+/// Read the QuoteCache from a slab account at the header's offset
+pub(crate) fn read_quote_cache(
+    slab_account: &AccountInfo,
+    header: &SlabHeader,
+) -> Result<QuoteCache, PercolatorError> {
+    let data = slab_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+
+    let offset = header.off_quote_cache as usize;
+    if offset < SlabHeader::LEN || data.len() < offset + QuoteCache::LEN {
+        msg!("Error: Slab quote cache out of bounds");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let cache = unsafe {
+        core::ptr::read_unaligned(data[offset..].as_ptr() as *const QuoteCache)
+    };
+    Ok(cache)
+}
+
+/// Check that a split cannot fill further than `max_slippage_bps` from the best quote
+///
+/// Walks the cached levels on the opposite side up to the split's limit
+/// price. Every level the split would reach must be within the bound, and
+/// if the cached depth runs out the limit price itself must be within it,
+/// since deeper (uncached) liquidity could otherwise fill past the bound.
+fn check_slippage(
+    cache: &QuoteCache,
+    split: &SlabSplit,
+    max_slippage_bps: u64,
+) -> Result<(), PercolatorError> {
+    let is_buy = split.side == 0;
+    let levels = if is_buy { &cache.best_asks } else { &cache.best_bids };
+
+    let best_px = levels[0].px;
+    if best_px <= 0 || levels[0].avail_qty <= 0 {
+        msg!("Error: No liquidity at quoted seqno");
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+
+    let allowed = best_px as i128 * max_slippage_bps.min(BPS as u64) as i128 / BPS;
+    let bound_px = if is_buy {
+        best_px as i128 + allowed
+    } else {
+        best_px as i128 - allowed
+    };
+    let within_bound = |px: i64| {
+        if is_buy {
+            (px as i128) <= bound_px
+        } else {
+            (px as i128) >= bound_px
         }
+    };
+    let within_limit = |px: i64| if is_buy { px <= split.limit_px } else { px >= split.limit_px };
+
+    let mut remaining = split.qty.abs();
+    for level in levels.iter() {
+        if remaining == 0 || level.px <= 0 || level.avail_qty <= 0 || !within_limit(level.px) {
+            break;
+        }
+        if !within_bound(level.px) {
+            msg!("Error: Fill would exceed max slippage");
+            return Err(PercolatorError::SlippageExceeded);
+        }
+        remaining -= remaining.min(level.avail_qty);
     }
 
-    // Phase 4: Calculate IM on net exposure (THE CAPITAL EFFICIENCY PROOF!)
-    // For v0, use simplified margin calculation:
-    // - Calculate net exposure across all slabs for same instrument
-    // - IM = abs(net_exposure) * notional_value * imr_factor
-    let net_exposure = calculate_net_exposure(portfolio);
-    let im_required = calculate_initial_margin(net_exposure, splits);
+    if remaining > 0 && !within_bound(split.limit_px) {
+        msg!("Error: Limit price exceeds max slippage beyond quoted depth");
+        return Err(PercolatorError::SlippageExceeded);
+    }
+    Ok(())
+}
 
-    msg!("Calculated margin on net exposure");
+/// Read the fill receipt written by a slab's commit_fill
+///
+/// The receipt must be owned by the slab's program, marked used and
+/// committed against the seqno the router sent, otherwise it is stale
+/// (from an earlier fill) or was never written.
+pub(crate) fn read_fill_receipt(
+    slab_account: &AccountInfo,
+    receipt_account: &AccountInfo,
+    expected_seqno: u32,
+) -> Result<FillReceipt, PercolatorError> {
+    if receipt_account.owner() != slab_account.owner() {
+        msg!("Error: Receipt not owned by slab program");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    let receipt = unsafe { *borrow_account_data::<FillReceipt>(receipt_account)? };
 
-    portfolio.update_margin(im_required, im_required / 2); // MM = IM / 2 for v0
+    if !receipt.is_used() {
+        msg!("Error: Fill receipt was not written");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if receipt.seqno_committed != expected_seqno {
+        msg!("Error: Fill receipt seqno mismatch");
+        return Err(PercolatorError::SeqnoMismatch);
+    }
 
-    // Phase 5: Check if portfolio has sufficient margin
-    // For v0, we assume equity is managed separately via vault
-    // In production, this would check vault.equity >= portfolio.im
-    if !portfolio.has_sufficient_margin() {
-        msg!("Error: Insufficient margin");
-        return Err(PercolatorError::PortfolioInsufficientMargin);
+    Ok(receipt)
+}
+
+/// Validate a receipt against the quantity requested from the slab
+///
+/// Returns the absolute filled quantity, which may be less than the
+/// requested quantity on a partial fill but never more.
+fn validate_fill(receipt: &FillReceipt, requested_qty: i64) -> Result<i64, PercolatorError> {
+    let filled_qty = receipt.filled_qty.abs();
+
+    if filled_qty > requested_qty.abs() {
+        msg!("Error: Fill exceeds requested quantity");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if filled_qty > 0 && receipt.vwap_px <= 0 {
+        msg!("Error: Fill receipt has invalid price");
+        return Err(PercolatorError::InvalidPrice);
+    }
+    if receipt.fee < 0 {
+        msg!("Error: Fill receipt has negative fee");
+        return Err(PercolatorError::InvalidAccount);
     }
 
-    let _ = vault; // Will be used in production for equity checks
-    let _ = receipt_accounts; // Will be used for real CPI
+    Ok(filled_qty)
+}
 
-    msg!("ExecuteCrossSlab completed successfully");
+/// Check a receipt's fees against the slab's registered caps
+///
+/// The taker fee may not exceed `taker_fee_cap` bps of the fill notional,
+/// nor a positive maker fee `maker_fee_cap` bps. Rebates are bounded in
+/// `split_fill_fees`.
+fn validate_fees(
+    receipt: &FillReceipt,
+    taker_fee_cap: u64,
+    maker_fee_cap: u64,
+) -> Result<(), PercolatorError> {
+    use model_safety::math::{div_u128, mul_u128};
+
+    let notional = receipt.notional.unsigned_abs() as u128;
+    let max_taker_fee = div_u128(mul_u128(notional, taker_fee_cap as u128), BPS as u128);
+    let max_maker_fee = div_u128(mul_u128(notional, maker_fee_cap as u128), BPS as u128);
+
+    if receipt.fee.unsigned_abs() as u128 > max_taker_fee {
+        msg!("Error: Taker fee exceeds registered cap");
+        return Err(PercolatorError::FeeCapExceeded);
+    }
+    if receipt.maker_fee > 0 && receipt.maker_fee as u128 > max_maker_fee {
+        msg!("Error: Maker fee exceeds registered cap");
+        return Err(PercolatorError::FeeCapExceeded);
+    }
     Ok(())
 }
 
-/// Calculate net exposure across all slabs for the same instrument (v0 simplified)
-fn calculate_net_exposure(portfolio: &Portfolio) -> i64 {
-    // For v0, sum all exposures (assuming same instrument across slabs)
-    let mut net = 0i64;
-    for i in 0..portfolio.exposure_count as usize {
-        net += portfolio.exposures[i].2;
+/// Split a fill's fees between the LP and the insurance fund (using verified math)
+///
+/// The insurance fund takes `fee_bps_to_insurance` of the taker fee plus
+/// the maker fee. A maker rebate (negative maker fee) is funded out of
+/// that cut and may not exceed it. The LP is credited the rest of the
+/// taker fee (negative when its maker fee is larger), so the taker's fee
+/// is accounted for in full.
+///
+/// # Returns
+/// * `(lp_credit, insurance_take)`
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in fee settlement.
+pub fn split_fill_fees(
+    fee: i64,
+    maker_fee: i64,
+    fee_bps_to_insurance: u16,
+) -> Result<(i128, u128), PercolatorError> {
+    use model_safety::math::{add_i128, div_i128, mul_i128, sub_i128};
+
+    let cut = div_i128(mul_i128(fee as i128, fee_bps_to_insurance as i128), BPS);
+    let insurance_take = add_i128(cut, maker_fee as i128);
+    if insurance_take < 0 {
+        msg!("Error: Maker rebate exceeds insurance cut");
+        return Err(PercolatorError::FeeCapExceeded);
+    }
+
+    Ok((sub_i128(fee as i128, insurance_take), insurance_take as u128))
+}
+
+/// Credit an LP's fee share to the portfolio of the slab's LP owner
+///
+/// When the taker is the LP itself, the credit is booked on the taker's
+/// portfolio and the LP account is not borrowed a second time. Otherwise
+/// the LP account must be a router-owned account of exactly
+/// `Portfolio::LEN` bytes, initialized for this router and the slab's LP
+/// owner, so no other router account layout can be credited as one.
+fn credit_lp_fees(
+    taker: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    slab_account: &AccountInfo,
+    lp_account: &AccountInfo,
+    credit: i128,
+) -> Result<(), PercolatorError> {
+    if credit == 0 {
+        return Ok(());
+    }
+
+    let lp_owner = unsafe { borrow_account_data::<SlabHeader>(slab_account)? }.lp_owner;
+    let lp = if lp_owner == taker.user {
+        taker
+    } else {
+        if lp_account.owner() != &taker.router_id
+            || !lp_account.is_writable()
+            || lp_account.data_len() != Portfolio::LEN
+        {
+            msg!("Error: Invalid LP portfolio account");
+            return Err(PercolatorError::InvalidAccount);
+        }
+        let lp = unsafe { borrow_account_data_mut::<Portfolio>(lp_account)? };
+        if lp.router_id != taker.router_id {
+            msg!("Error: LP portfolio not initialized for this router");
+            return Err(PercolatorError::InvalidPortfolio);
+        }
+        if lp.user != lp_owner {
+            msg!("Error: LP portfolio does not belong to slab LP owner");
+            return Err(PercolatorError::InvalidPortfolio);
+        }
+        lp
+    };
+
+    let pnl_before = lp.pnl;
+    if credit > 0 {
+        lp.book_fill(credit, 0);
+    } else {
+        lp.book_fill(0, -credit);
     }
-    net
+    registry.global_haircut.track_pnl_change(pnl_before, lp.pnl);
+    Ok(())
 }
 
-/// Calculate initial margin requirement (v0 simplified)
-fn calculate_initial_margin(net_exposure: i64, splits: &[SlabSplit]) -> u128 {
-    // For v0, simplified: IM = abs(net_exposure) * avg_price * 0.1 (10% IMR)
-    if splits.is_empty() {
+/// Apply a filled quantity to the portfolio exposure
+///
+/// Buy adds to the position, sell subtracts from it. The entry VWAP is
+/// updated at `fill_px`; returns the PnL realized by reducing or flipping.
+fn apply_fill_to_exposure(
+    portfolio: &mut Portfolio,
+    slab_idx: u16,
+    instrument_idx: u16,
+    side: u8,
+    filled_qty: i64,
+    fill_px: i64,
+) -> i128 {
+    if filled_qty == 0 {
         return 0;
     }
 
-    let abs_exposure = net_exposure.abs() as u128;
-    let avg_price = splits[0].limit_px as u128; // Use first split price
+    let signed_qty = if side == 0 {
+        // Buy
+        filled_qty
+    } else {
+        // Sell
+        -filled_qty
+    };
 
-    // IM = abs(net_exposure) * price * 0.1 / 1e6 (scale factor)
-    // For v0 proof: if net_exposure = 0, IM = 0!
-    (abs_exposure * avg_price * 10) / (100 * 1_000_000)
+    portfolio.apply_fill(slab_idx, instrument_idx, signed_qty, fill_px)
 }
 
 // Exclude test module from BPF builds to avoid stack overflow from test-only functions
@@ -163,15 +163,29 @@ mod margin_calculation_tests {
 
 #[cfg(test)]
 mod net_exposure_calculation_tests {
-    use super::super::calculate_net_exposure;
-    use crate::state::Portfolio;
+    use crate::margin::calculate_exposure_margin;
+    use crate::state::{Portfolio, SlabRegistry};
     use pinocchio::pubkey::Pubkey;
 
     const SCALE: i64 = 1_000_000;
 
+    /// Registry with three slabs trading one instrument at $60k (5% IMR defaults)
+    fn single_instrument_registry() -> SlabRegistry {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        for i in 0..3u8 {
+            registry
+                .register_slab(Pubkey::from([i + 1; 32]), [0; 32], Pubkey::default(), 0, 0, 0, 0, 0, 0, 0)
+                .unwrap();
+        }
+        registry.find_or_add_instrument(&Pubkey::from([9; 32])).unwrap();
+        registry.set_instrument_mark(0, 60_000 * SCALE, 0);
+        registry
+    }
+
     /// Test: Net exposure calculation
     #[test]
     fn test_calculate_net_exposure() {
+        let registry = single_instrument_registry();
         let router_id = Pubkey::default();
         let user = Pubkey::default();
         let mut portfolio = Portfolio::new(router_id, user, 0);
@@ -180,13 +194,15 @@ mod net_exposure_calculation_tests {
         portfolio.update_exposure(1, 0, -5 * SCALE);
         portfolio.update_exposure(2, 0, 3 * SCALE);
 
-        let net = calculate_net_exposure(&portfolio);
-        assert_eq!(net, 8 * SCALE);
+        // Net 8 @ $60k * 5% = $24k
+        let (im, _) = calculate_exposure_margin(&portfolio, &registry, 0).unwrap();
+        assert_eq!(im, (8 * 60_000 * 5 / 100) as u128 * SCALE as u128);
     }
 
     /// Test: Zero net exposure → zero IM
     #[test]
     fn test_zero_net_zero_im() {
+        let registry = single_instrument_registry();
         let router_id = Pubkey::default();
         let user = Pubkey::default();
         let mut portfolio = Portfolio::new(router_id, user, 0);
@@ -194,11 +210,238 @@ mod net_exposure_calculation_tests {
         portfolio.update_exposure(0, 0, 10 * SCALE);
         portfolio.update_exposure(1, 0, -10 * SCALE);
 
-        let net = calculate_net_exposure(&portfolio);
-        assert_eq!(net, 0, "Net exposure should be zero");
-
         // When net = 0, IM calculation should yield 0
-        let im = (net.abs() as u128 * 60_000 * 10) / 100;
+        let (im, mm) = calculate_exposure_margin(&portfolio, &registry, 0).unwrap();
         assert_eq!(im, 0, "Zero net MUST produce zero IM");
+        assert_eq!(mm, 0);
+    }
+}
+
+#[cfg(test)]
+mod fill_receipt_tests {
+    use super::super::{apply_fill_to_exposure, validate_fill, SlabSplit};
+    use crate::state::Portfolio;
+    use percolator_common::{FillReceipt, PercolatorError};
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE: i64 = 1_000_000;
+
+    fn buy_split(qty: i64, limit_px: i64) -> SlabSplit {
+        SlabSplit {
+            slab_id: Pubkey::default(),
+            qty,
+            side: 0,
+            limit_px,
+            expected_seqno: 0,
+        }
+    }
+
+    /// Test: Partial fill with price improvement is booked at actual size
+    #[test]
+    fn test_partial_fill_uses_receipt_qty() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        let split = buy_split(10 * SCALE, 50_000 * SCALE);
+
+        let mut receipt = FillReceipt::new();
+        receipt.write(7, 4 * SCALE, 49_900 * SCALE, 199_600 * SCALE, 20 * SCALE, 0);
+
+        let filled = validate_fill(&receipt, split.qty).unwrap();
+        assert_eq!(filled, 4 * SCALE);
+
+        let realized = apply_fill_to_exposure(&mut portfolio, 0, 0, split.side, filled, receipt.vwap_px);
+        portfolio.book_fill(realized, receipt.fee as i128);
+
+        assert_eq!(portfolio.get_exposure(0, 0), 4 * SCALE);
+        assert_eq!(portfolio.get_entry_price(0, 0), 49_900 * SCALE);
+        assert_eq!(portfolio.pnl, -20 * SCALE as i128);
+    }
+
+    /// Test: Receipt reporting more than requested is rejected
+    #[test]
+    fn test_overfill_rejected() {
+        let split = buy_split(SCALE, 50_000 * SCALE);
+
+        let mut receipt = FillReceipt::new();
+        receipt.write(1, 2 * SCALE, 50_000 * SCALE, 100_000 * SCALE, 0, 0);
+
+        assert_eq!(validate_fill(&receipt, split.qty), Err(PercolatorError::InvalidQuantity));
+    }
+
+    /// Test: Zero fill leaves exposure untouched
+    #[test]
+    fn test_zero_fill_no_exposure() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        let split = buy_split(SCALE, 50_000 * SCALE);
+
+        let mut receipt = FillReceipt::new();
+        receipt.write(1, 0, 0, 0, 0, 0);
+
+        let filled = validate_fill(&receipt, split.qty).unwrap();
+        let realized = apply_fill_to_exposure(&mut portfolio, 0, 0, split.side, filled, receipt.vwap_px);
+        assert_eq!(realized, 0);
+        assert_eq!(portfolio.exposure_count, 0);
+    }
+
+    /// Test: Selling back a long realizes PnL against the entry VWAP
+    #[test]
+    fn test_closing_fill_realizes_pnl() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // Buy 2 @ $50k
+        let realized = apply_fill_to_exposure(&mut portfolio, 0, 0, 0, 2 * SCALE, 50_000 * SCALE);
+        portfolio.book_fill(realized, 0);
+        assert_eq!(portfolio.pnl, 0);
+
+        // Sell 2 @ $51k: +$2k realized, minus $10 fee
+        let realized = apply_fill_to_exposure(&mut portfolio, 0, 0, 1, 2 * SCALE, 51_000 * SCALE);
+        portfolio.book_fill(realized, 10 * SCALE as i128);
+
+        assert_eq!(portfolio.exposure_count, 0);
+        assert_eq!(portfolio.pnl, 1_990 * SCALE as i128);
+        assert_eq!(portfolio.equity, 1_990 * SCALE as i128);
+    }
+}
+
+#[cfg(test)]
+mod slippage_tests {
+    use super::super::{check_slippage, SlabSplit};
+    use percolator_common::{PercolatorError, QuoteCache, QuoteLevel};
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE: i64 = 1_000_000;
+
+    fn split(side: u8, qty: i64, limit_px: i64) -> SlabSplit {
+        SlabSplit {
+            slab_id: Pubkey::default(),
+            qty,
+            side,
+            limit_px,
+            expected_seqno: 7,
+        }
+    }
+
+    /// Asks at $100 / $101 / $102 (1 each), bids at $99 / $98
+    fn cache() -> QuoteCache {
+        let mut cache = QuoteCache::new();
+        let asks = [
+            QuoteLevel { px: 100 * SCALE, avail_qty: SCALE },
+            QuoteLevel { px: 101 * SCALE, avail_qty: SCALE },
+            QuoteLevel { px: 102 * SCALE, avail_qty: SCALE },
+        ];
+        let bids = [
+            QuoteLevel { px: 99 * SCALE, avail_qty: SCALE },
+            QuoteLevel { px: 98 * SCALE, avail_qty: SCALE },
+        ];
+        cache.update(7, &bids, &asks);
+        cache
+    }
+
+    /// Test: Fill within the bound passes, walking into a level past it fails
+    #[test]
+    fn test_buy_depth_within_bound() {
+        let cache = cache();
+
+        // 2 units reach $101: 1% from $100
+        assert!(check_slippage(&cache, &split(0, 2 * SCALE, 105 * SCALE), 100).is_ok());
+        // 3 units reach $102: 2% from $100
+        assert_eq!(
+            check_slippage(&cache, &split(0, 3 * SCALE, 105 * SCALE), 100),
+            Err(PercolatorError::SlippageExceeded)
+        );
+        // A tighter limit stops before the $102 level
+        assert!(check_slippage(&cache, &split(0, 3 * SCALE, 101 * SCALE), 100).is_ok());
+    }
+
+    /// Test: Beyond cached depth, the limit price itself must be within the bound
+    #[test]
+    fn test_sell_beyond_cached_depth() {
+        let cache = cache();
+
+        // 3 units exhaust the cached bids; limit $97 is ~2% below $99
+        assert_eq!(
+            check_slippage(&cache, &split(1, 3 * SCALE, 97 * SCALE), 100),
+            Err(PercolatorError::SlippageExceeded)
+        );
+        assert!(check_slippage(&cache, &split(1, 3 * SCALE, 97 * SCALE), 300).is_ok());
+    }
+
+    /// Test: Empty side of the book is rejected before CPI
+    #[test]
+    fn test_no_liquidity() {
+        let cache = QuoteCache::new();
+        assert_eq!(
+            check_slippage(&cache, &split(0, SCALE, 100 * SCALE), 100),
+            Err(PercolatorError::InsufficientLiquidity)
+        );
+    }
+}
+
+#[cfg(test)]
+mod fee_tests {
+    use super::super::{split_fill_fees, validate_fees};
+    use percolator_common::{FillReceipt, PercolatorError};
+
+    const SCALE: i64 = 1_000_000;
+
+    fn receipt(fee: i64, maker_fee: i64) -> FillReceipt {
+        let mut receipt = FillReceipt::new();
+        receipt.write(1, SCALE, 100 * SCALE, 100 * SCALE, fee, maker_fee);
+        receipt
+    }
+
+    /// Test: Fees above the registered caps are rejected
+    #[test]
+    fn test_fee_caps_enforced() {
+        // $100 notional: 10 bps taker cap = $0.10, 5 bps maker cap = $0.05
+        assert!(validate_fees(&receipt(100_000, 50_000), 10, 5).is_ok());
+        assert_eq!(validate_fees(&receipt(100_001, 0), 10, 5), Err(PercolatorError::FeeCapExceeded));
+        assert_eq!(validate_fees(&receipt(0, 50_001), 10, 5), Err(PercolatorError::FeeCapExceeded));
+        // Rebates are not capped by the maker cap
+        assert!(validate_fees(&receipt(100_000, -1_000_000), 10, 5).is_ok());
+    }
+
+    /// Test: Fee split conserves the taker fee and bounds rebates by the insurance cut
+    #[test]
+    fn test_split_fill_fees() {
+        // 20% of a 1000 fee to insurance, no maker fee
+        assert_eq!(split_fill_fees(1_000, 0, 2_000), Ok((800, 200)));
+        // A 150 rebate comes out of the 200 cut
+        assert_eq!(split_fill_fees(1_000, -150, 2_000), Ok((950, 50)));
+        // A positive maker fee is charged to the LP on top
+        assert_eq!(split_fill_fees(1_000, 100, 2_000), Ok((700, 300)));
+
+        for (fee, maker_fee) in [(1_000, 0), (1_000, -150), (1_000, 100), (0, 40)] {
+            let (lp_credit, take) = split_fill_fees(fee, maker_fee, 2_000).unwrap();
+            assert_eq!(lp_credit + take as i128, fee as i128);
+        }
+
+        assert_eq!(split_fill_fees(1_000, -201, 2_000), Err(PercolatorError::FeeCapExceeded));
+    }
+}
+
+#[cfg(test)]
+mod split_validation_tests {
+    use super::super::{validate_distinct_slabs, SlabSplit};
+    use percolator_common::PercolatorError;
+    use pinocchio::pubkey::Pubkey;
+
+    fn split(slab: u8) -> SlabSplit {
+        SlabSplit {
+            slab_id: Pubkey::from([slab; 32]),
+            qty: 1_000_000,
+            side: 0,
+            limit_px: 100_000_000,
+            expected_seqno: 7,
+        }
+    }
+
+    /// Test: Two splits on one slab are rejected up front
+    #[test]
+    fn test_duplicate_slab_rejected() {
+        assert_eq!(validate_distinct_slabs(&[split(1), split(2)]), Ok(()));
+        assert_eq!(
+            validate_distinct_slabs(&[split(1), split(2), split(1)]),
+            Err(PercolatorError::InvalidInstruction)
+        );
     }
 }
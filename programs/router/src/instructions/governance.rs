@@ -0,0 +1,654 @@
+//! Governance instructions - slab registry and router parameters
+//!
+//! Every instruction here is signed by `registry.governance`. Slabs are
+//! whitelisted only after their header proves they share the router's
+//! layout; governance itself is rotated with a two-step transfer so a
+//! mistyped key cannot brick the registry.
+
+use crate::state::{InsuranceParams, PnlVestingParams, SlabRegistry};
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Basis point denominator
+const BPS: u64 = 10_000;
+
+/// Slab registration parameters
+#[derive(Debug, Clone, Copy)]
+pub struct SlabRegistration {
+    /// Layout version hash the slab was built against
+    pub version_hash: [u8; 32],
+    /// Oracle program ID for the slab's instrument
+    pub oracle_id: Pubkey,
+    /// Initial margin ratio (bps, 0 = registry default)
+    pub imr: u64,
+    /// Maintenance margin ratio (bps, 0 = registry default)
+    pub mmr: u64,
+    /// Maximum maker fee (bps)
+    pub maker_fee_cap: u64,
+    /// Maximum taker fee (bps)
+    pub taker_fee_cap: u64,
+    /// Latency SLA (milliseconds)
+    pub latency_sla_ms: u64,
+    /// Maximum exposure per user (per instrument)
+    pub max_exposure: u128,
+}
+
+/// Slab account presented for registration
+#[derive(Debug, Clone, Copy)]
+pub struct SlabAccount<'a> {
+    /// Slab state account
+    pub key: &'a Pubkey,
+    /// Program owning the slab account
+    pub owner: &'a Pubkey,
+    /// Slab header read from the account
+    pub header: &'a SlabHeader,
+}
+
+/// Slab trading status set by governance
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabStatus {
+    /// Trading enabled
+    Active = 0,
+    /// No new trades; liquidation and funding continue
+    Paused = 1,
+    /// Removed from the registry (cannot be undone)
+    Deactivated = 2,
+}
+
+impl SlabStatus {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(SlabStatus::Active),
+            1 => Some(SlabStatus::Paused),
+            2 => Some(SlabStatus::Deactivated),
+            _ => None,
+        }
+    }
+}
+
+/// Global risk parameters
+#[derive(Debug, Clone, Copy)]
+pub struct RiskParams {
+    /// Default initial margin ratio (bps)
+    pub imr: u64,
+    /// Default maintenance margin ratio (bps)
+    pub mmr: u64,
+    /// Hard liquidation price band (bps)
+    pub liq_band_bps: u64,
+    /// Pre-liquidation buffer above MM (1e6 scale)
+    pub preliq_buffer: i128,
+    /// Pre-liquidation price band (bps)
+    pub preliq_band_bps: u64,
+    /// Maximum size per slab in one transaction
+    pub router_cap_per_slab: u64,
+    /// Oracle price tolerance (bps)
+    pub oracle_tolerance_bps: u64,
+    /// Liquidation penalty (bps of liquidated notional)
+    pub liquidation_fee_bps: u64,
+    /// Keeper share of the liquidation penalty (bps)
+    pub liquidation_keeper_share_bps: u64,
+    /// Maximum oracle price age (seconds)
+    pub oracle_max_age_secs: u64,
+    /// Maximum oracle confidence interval (bps)
+    pub oracle_max_conf_bps: u64,
+    /// Minimum time between funding accruals (seconds)
+    pub funding_interval_secs: u64,
+    /// Cap on funding premium per interval (bps)
+    pub funding_cap_bps: u64,
+}
+
+/// Verify the signer is the registry's governance authority
+pub(crate) fn validate_governance(registry: &SlabRegistry, signer: &Pubkey) -> Result<(), PercolatorError> {
+    if &registry.governance != signer {
+        msg!("Error: Signer is not the governance authority");
+        return Err(PercolatorError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Validate per-slab margin ratios (0 means registry default)
+fn validate_slab_rates(imr: u64, mmr: u64) -> Result<(), PercolatorError> {
+    if imr > BPS || mmr > BPS || (imr > 0 && mmr > imr) {
+        msg!("Error: Invalid slab margin ratios");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+    Ok(())
+}
+
+/// Process register_slab instruction
+///
+/// The slab header must carry the expected magic and version, name the
+/// router authority PDA as its router_id (the key the router signs slab
+/// CPIs with), and the slab account must be owned by the header's program.
+/// `version_hash` must equal the shared layout hash.
+///
+/// # Arguments
+/// * `registry` - Slab registry
+/// * `governance` - Signer (must match registry.governance)
+/// * `router_authority` - Router authority PDA derived from registry.router_id
+/// * `slab` - Slab account, its owner and header
+/// * `params` - Registration parameters
+/// * `now_ts` - Current unix timestamp
+///
+/// # Returns
+/// * The slab's registry index
+pub fn process_register_slab(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    router_authority: &Pubkey,
+    slab: SlabAccount,
+    params: &SlabRegistration,
+    now_ts: u64,
+) -> Result<u16, PercolatorError> {
+    validate_governance(registry, governance)?;
+    let header = slab.header;
+
+    if &header.magic != SlabHeader::MAGIC {
+        msg!("Error: Invalid slab header magic");
+        return Err(PercolatorError::InvalidSlab);
+    }
+    if header.version != SlabHeader::VERSION || params.version_hash != SlabHeader::VERSION_HASH {
+        msg!("Error: Slab version does not match router layout");
+        return Err(PercolatorError::SlabVersionMismatch);
+    }
+    if &header.router_id != router_authority {
+        msg!("Error: Slab was not initialized for the router authority");
+        return Err(PercolatorError::InvalidSlab);
+    }
+    if slab.owner != &header.program_id {
+        msg!("Error: Slab account not owned by its header program");
+        return Err(PercolatorError::InvalidAccountOwner);
+    }
+    if registry.is_registered(slab.key) {
+        msg!("Error: Slab already registered");
+        return Err(PercolatorError::InvalidSlab);
+    }
+    validate_slab_rates(params.imr, params.mmr)?;
+
+    registry.find_or_add_instrument(&header.instrument).map_err(|_| {
+        msg!("Error: Instrument table is full");
+        PercolatorError::PoolFull
+    })?;
+    let idx = registry
+        .register_slab(
+            *slab.key,
+            params.version_hash,
+            params.oracle_id,
+            params.imr,
+            params.mmr,
+            params.maker_fee_cap,
+            params.taker_fee_cap,
+            params.latency_sla_ms,
+            params.max_exposure,
+            now_ts,
+        )
+        .map_err(|_| {
+            msg!("Error: Slab registry is full");
+            PercolatorError::PoolFull
+        })?;
+
+    msg!("RegisterSlab executed successfully");
+    Ok(idx)
+}
+
+/// Process set_slab_status instruction
+///
+/// # Arguments
+/// * `registry` - Slab registry
+/// * `governance` - Signer (must match registry.governance)
+/// * `slab_id` - Registered, active slab
+/// * `status` - New status (deactivation is permanent)
+pub fn process_set_slab_status(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    slab_id: &Pubkey,
+    status: SlabStatus,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    let result = match status {
+        SlabStatus::Active => registry.set_slab_paused(slab_id, false),
+        SlabStatus::Paused => registry.set_slab_paused(slab_id, true),
+        SlabStatus::Deactivated => registry.deactivate_slab(slab_id),
+    };
+    result.map_err(|_| {
+        msg!("Error: Slab not registered or inactive");
+        PercolatorError::SlabNotRegistered
+    })?;
+
+    msg!("SetSlabStatus executed successfully");
+    Ok(())
+}
+
+/// Process update_slab_params instruction
+///
+/// # Arguments
+/// * `registry` - Slab registry
+/// * `governance` - Signer (must match registry.governance)
+/// * `slab_id` - Registered, active slab
+/// * `imr` - Initial margin ratio (bps, 0 = registry default)
+/// * `mmr` - Maintenance margin ratio (bps, 0 = registry default)
+pub fn process_update_slab_params(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    slab_id: &Pubkey,
+    imr: u64,
+    mmr: u64,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+    validate_slab_rates(imr, mmr)?;
+
+    registry.update_risk_params(slab_id, imr, mmr).map_err(|_| {
+        msg!("Error: Slab not registered or inactive");
+        PercolatorError::SlabNotRegistered
+    })?;
+
+    msg!("UpdateSlabParams executed successfully");
+    Ok(())
+}
+
+/// Process update_risk_params instruction
+///
+/// Rejects margin ratios outside (0, 100%], MMR above IMR, a liquidation
+/// fee that would eat the whole maintenance margin, a keeper share above
+/// 100%, a negative pre-liquidation buffer and a zero funding interval.
+pub fn process_update_risk_params(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    params: &RiskParams,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    if params.imr == 0
+        || params.imr > BPS
+        || params.mmr == 0
+        || params.mmr > params.imr
+        || params.liquidation_fee_bps >= params.mmr
+        || params.liquidation_keeper_share_bps > BPS
+        || params.preliq_buffer < 0
+        || params.funding_interval_secs == 0
+    {
+        msg!("Error: Invalid risk parameters");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+
+    registry.update_liquidation_params(
+        params.imr,
+        params.mmr,
+        params.liq_band_bps,
+        params.preliq_buffer,
+        params.preliq_band_bps,
+        params.router_cap_per_slab,
+        params.oracle_tolerance_bps,
+    );
+    registry
+        .update_liquidation_fee(params.liquidation_fee_bps, params.liquidation_keeper_share_bps)
+        .map_err(|_| PercolatorError::InvalidRiskParams)?;
+    registry.update_oracle_guards(params.oracle_max_age_secs, params.oracle_max_conf_bps);
+    registry
+        .update_funding_params(params.funding_interval_secs, params.funding_cap_bps)
+        .map_err(|_| PercolatorError::InvalidRiskParams)?;
+
+    msg!("UpdateRiskParams executed successfully");
+    Ok(())
+}
+
+/// Process update_insurance_params instruction
+pub fn process_update_insurance_params(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    params: InsuranceParams,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    if params.fee_bps_to_insurance as u64 > BPS
+        || params.max_payout_bps_of_oi as u64 > BPS
+        || params.max_daily_payout_bps_of_vault as u64 > BPS
+    {
+        msg!("Error: Invalid insurance parameters");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+    registry.insurance_params = params;
+
+    msg!("UpdateInsuranceParams executed successfully");
+    Ok(())
+}
+
+/// Process update_vesting_params instruction
+pub fn process_update_vesting_params(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    params: PnlVestingParams,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    if params.tau_slots == 0 {
+        msg!("Error: Vesting time constant must be positive");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+    registry.pnl_vesting_params = params;
+
+    msg!("UpdateVestingParams executed successfully");
+    Ok(())
+}
+
+/// Process update_haircut_caps instruction
+///
+/// # Arguments
+/// * `max_per_event_bps` - Max haircut of positive PnL per loss event
+/// * `max_per_day_bps` - Max cumulative haircut per day
+pub fn process_update_haircut_caps(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    max_per_event_bps: u16,
+    max_per_day_bps: u16,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    if max_per_event_bps as u64 > BPS || max_per_day_bps as u64 > BPS {
+        msg!("Error: Invalid haircut caps");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+    registry.global_haircut.max_haircut_per_event_bps = max_per_event_bps;
+    registry.global_haircut.max_haircut_per_day_bps = max_per_day_bps;
+
+    msg!("UpdateHaircutCaps executed successfully");
+    Ok(())
+}
+
+/// Maximum collateral mint decimals (keeps 10^decimals well inside u128)
+const MAX_COLLATERAL_DECIMALS: u8 = 18;
+
+/// Process set_collateral instruction
+///
+/// Registers a non-quote collateral mint or updates an existing one.
+/// Lowering the weight takes effect on every portfolio at its next margin
+/// refresh; a weight of zero stops the mint counting toward equity while
+/// balances stay withdrawable. Changing the oracle clears the cached price
+/// until the collateral price crank runs against the new feed.
+///
+/// # Arguments
+/// * `registry` - Slab registry
+/// * `governance` - Signer (must match registry.governance)
+/// * `mint` - Collateral mint
+/// * `oracle` - Price oracle account for the mint
+/// * `weight_bps` - Share of oracle value counted as equity (bps, <= 100%)
+/// * `decimals` - Mint decimals
+///
+/// # Returns
+/// * The collateral's registry slot
+pub fn process_set_collateral(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    mint: &Pubkey,
+    oracle: &Pubkey,
+    weight_bps: u64,
+    decimals: u8,
+) -> Result<usize, PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    if mint == &Pubkey::default()
+        || oracle == &Pubkey::default()
+        || weight_bps > BPS
+        || decimals > MAX_COLLATERAL_DECIMALS
+    {
+        msg!("Error: Invalid collateral parameters");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+
+    let idx = registry.set_collateral(*mint, *oracle, weight_bps, decimals).map_err(|_| {
+        msg!("Error: Collateral table is full");
+        PercolatorError::PoolFull
+    })?;
+
+    msg!("SetCollateral executed successfully");
+    Ok(idx)
+}
+
+/// Process transfer_governance instruction (step 1 of 2)
+///
+/// Proposes `new_governance`; nothing changes until it accepts. Proposing
+/// the default pubkey cancels a pending transfer.
+pub fn process_transfer_governance(
+    registry: &mut SlabRegistry,
+    governance: &Pubkey,
+    new_governance: &Pubkey,
+) -> Result<(), PercolatorError> {
+    validate_governance(registry, governance)?;
+
+    registry.pending_governance = *new_governance;
+
+    msg!("TransferGovernance executed successfully");
+    Ok(())
+}
+
+/// Process accept_governance instruction (step 2 of 2)
+///
+/// Must be signed by the pending governance authority.
+pub fn process_accept_governance(
+    registry: &mut SlabRegistry,
+    signer: &Pubkey,
+) -> Result<(), PercolatorError> {
+    if registry.pending_governance == Pubkey::default() || &registry.pending_governance != signer {
+        msg!("Error: Signer is not the pending governance authority");
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    registry.governance = registry.pending_governance;
+    registry.pending_governance = Pubkey::default();
+
+    msg!("AcceptGovernance executed successfully");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTER: Pubkey = [9; 32];
+    const GOV: Pubkey = [1; 32];
+    const SLAB_PROGRAM: Pubkey = [2; 32];
+    const SLAB: Pubkey = [3; 32];
+    const AUTHORITY: Pubkey = [10; 32];
+
+    fn registry() -> SlabRegistry {
+        SlabRegistry::new(ROUTER, GOV, 0)
+    }
+
+    fn header() -> SlabHeader {
+        SlabHeader::new(SLAB_PROGRAM, [4; 32], AUTHORITY, [5; 32], 100_000_000, 20, 1_000_000, 255)
+    }
+
+    fn slab<'a>(owner: &'a Pubkey, header: &'a SlabHeader) -> SlabAccount<'a> {
+        SlabAccount { key: &SLAB, owner, header }
+    }
+
+    fn registration() -> SlabRegistration {
+        SlabRegistration {
+            version_hash: SlabHeader::VERSION_HASH,
+            oracle_id: [6; 32],
+            imr: 1000,
+            mmr: 500,
+            maker_fee_cap: 10,
+            taker_fee_cap: 20,
+            latency_sla_ms: 1000,
+            max_exposure: 0,
+        }
+    }
+
+    #[test]
+    fn test_register_slab_checks_header() {
+        let mut registry = registry();
+
+        assert_eq!(
+            process_register_slab(&mut registry, &[7; 32], &AUTHORITY, slab(&SLAB_PROGRAM, &header()), &registration(), 0),
+            Err(PercolatorError::Unauthorized)
+        );
+
+        let mut bad_hash = registration();
+        bad_hash.version_hash = [0; 32];
+        assert_eq!(
+            process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &header()), &bad_hash, 0),
+            Err(PercolatorError::SlabVersionMismatch)
+        );
+
+        let mut other_router = header();
+        other_router.router_id = [8; 32];
+        assert_eq!(
+            process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &other_router), &registration(), 0),
+            Err(PercolatorError::InvalidSlab)
+        );
+        // The router program ID never signs slab CPIs
+        let mut program_router = header();
+        program_router.router_id = ROUTER;
+        assert_eq!(
+            process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &program_router), &registration(), 0),
+            Err(PercolatorError::InvalidSlab)
+        );
+        assert_eq!(
+            process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&[8; 32], &header()), &registration(), 0),
+            Err(PercolatorError::InvalidAccountOwner)
+        );
+
+        let idx = process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &header()), &registration(), 7).unwrap();
+        assert_eq!(idx, 0);
+        assert_eq!(registry.find_instrument(&[5; 32]), Some(0));
+        assert_eq!(registry.slabs[0].registered_ts, 7);
+        assert_eq!(
+            process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &header()), &registration(), 0),
+            Err(PercolatorError::InvalidSlab)
+        );
+    }
+
+    /// A slab registered through the PDA the entrypoint derives accepts CPIs
+    /// signed with the authority execute_cross_slab validates
+    #[test]
+    #[cfg(target_os = "solana")]
+    fn test_register_then_execute_share_router_authority() {
+        use crate::pda::derive_authority_pda;
+
+        let mut registry = registry();
+        let (authority, _) = derive_authority_pda(&registry.router_id);
+        let mut slab_header = header();
+        slab_header.router_id = authority;
+        process_register_slab(&mut registry, &GOV, &authority, slab(&SLAB_PROGRAM, &slab_header), &registration(), 0)
+            .unwrap();
+
+        // validate_router_authority derives the signer from the portfolio's router_id
+        let portfolio = crate::state::Portfolio::new(registry.router_id, [11; 32], 0);
+        assert_eq!(derive_authority_pda(&portfolio.router_id).0, slab_header.router_id);
+    }
+
+    #[test]
+    fn test_pause_resume_and_deactivate() {
+        let mut registry = registry();
+        process_register_slab(&mut registry, &GOV, &AUTHORITY, slab(&SLAB_PROGRAM, &header()), &registration(), 0).unwrap();
+
+        process_set_slab_status(&mut registry, &GOV, &SLAB, SlabStatus::Paused).unwrap();
+        assert!(registry.slabs[0].paused);
+        process_set_slab_status(&mut registry, &GOV, &SLAB, SlabStatus::Active).unwrap();
+        assert!(!registry.slabs[0].paused);
+
+        process_set_slab_status(&mut registry, &GOV, &SLAB, SlabStatus::Deactivated).unwrap();
+        assert!(registry.find_slab(&SLAB).is_none());
+        assert_eq!(
+            process_set_slab_status(&mut registry, &GOV, &SLAB, SlabStatus::Active),
+            Err(PercolatorError::SlabNotRegistered)
+        );
+    }
+
+    #[test]
+    fn test_param_updates_are_validated() {
+        let mut registry = registry();
+        let mut params = RiskParams {
+            imr: 1000,
+            mmr: 500,
+            liq_band_bps: 300,
+            preliq_buffer: 5_000_000,
+            preliq_band_bps: 150,
+            router_cap_per_slab: 1_000,
+            oracle_tolerance_bps: 25,
+            liquidation_fee_bps: 100,
+            liquidation_keeper_share_bps: 2_500,
+            oracle_max_age_secs: 30,
+            oracle_max_conf_bps: 50,
+            funding_interval_secs: 1_800,
+            funding_cap_bps: 25,
+        };
+        process_update_risk_params(&mut registry, &GOV, &params).unwrap();
+        assert_eq!(registry.mmr, 500);
+        assert_eq!(registry.liquidation_keeper_share_bps, 2_500);
+        assert_eq!(registry.funding_interval_secs, 1_800);
+
+        params.mmr = 1_500;
+        assert_eq!(
+            process_update_risk_params(&mut registry, &GOV, &params),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+        assert_eq!(registry.mmr, 500);
+
+        assert_eq!(
+            process_update_haircut_caps(&mut registry, &GOV, 10_001, 5_000),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+        process_update_haircut_caps(&mut registry, &GOV, 2_000, 4_000).unwrap();
+        assert_eq!(registry.global_haircut.max_haircut_per_event_bps, 2_000);
+
+        assert_eq!(
+            process_update_vesting_params(&mut registry, &GOV, PnlVestingParams { tau_slots: 0, cliff_slots: 0 }),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+    }
+
+    #[test]
+    fn test_set_collateral() {
+        let mut registry = registry();
+        let mint: Pubkey = [20; 32];
+
+        assert_eq!(
+            process_set_collateral(&mut registry, &GOV, &mint, &[21; 32], 10_001, 9),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+        assert_eq!(
+            process_set_collateral(&mut registry, &[7; 32], &mint, &[21; 32], 8_000, 9),
+            Err(PercolatorError::Unauthorized)
+        );
+
+        assert_eq!(process_set_collateral(&mut registry, &GOV, &mint, &[21; 32], 8_000, 9), Ok(0));
+        registry.collaterals[0].price = 150_000_000;
+
+        // Reweighting keeps the cached price; a new oracle clears it
+        assert_eq!(process_set_collateral(&mut registry, &GOV, &mint, &[21; 32], 7_000, 9), Ok(0));
+        assert_eq!(registry.collaterals[0].weight_bps, 7_000);
+        assert_eq!(registry.collaterals[0].price, 150_000_000);
+        process_set_collateral(&mut registry, &GOV, &mint, &[22; 32], 7_000, 9).unwrap();
+        assert_eq!(registry.collaterals[0].price, 0);
+        assert_eq!(registry.collateral_count, 1);
+    }
+
+    #[test]
+    fn test_two_step_governance_transfer() {
+        let mut registry = registry();
+        let next: Pubkey = [7; 32];
+
+        assert_eq!(
+            process_accept_governance(&mut registry, &next),
+            Err(PercolatorError::Unauthorized)
+        );
+        process_transfer_governance(&mut registry, &GOV, &next).unwrap();
+        assert_eq!(registry.governance, GOV);
+        assert_eq!(
+            process_accept_governance(&mut registry, &[8; 32]),
+            Err(PercolatorError::Unauthorized)
+        );
+
+        process_accept_governance(&mut registry, &next).unwrap();
+        assert_eq!(registry.governance, next);
+        assert_eq!(registry.pending_governance, Pubkey::default());
+        assert_eq!(
+            process_transfer_governance(&mut registry, &GOV, &GOV),
+            Err(PercolatorError::Unauthorized)
+        );
+    }
+}
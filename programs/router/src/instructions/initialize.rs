@@ -7,17 +7,20 @@ use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 
 /// Process initialize instruction for registry
 ///
-/// Initializes the slab registry account with governance authority.
+/// Initializes the slab registry account with governance authority and
+/// binds the quote mint principal is denominated in.
 /// This is called once during router deployment.
 ///
 /// # Arguments
 /// * `program_id` - The router program ID
 /// * `registry_account` - The registry account to initialize (must be PDA)
 /// * `governance` - The governance authority pubkey
+/// * `quote_mint` - Mint deposited and withdrawn 1:1 as principal
 pub fn process_initialize_registry(
     program_id: &Pubkey,
     registry_account: &AccountInfo,
     governance: &Pubkey,
+    quote_mint: &Pubkey,
 ) -> Result<(), PercolatorError> {
     // Derive and verify registry PDA
     let (expected_pda, bump) = derive_registry_pda(program_id);
@@ -48,6 +51,7 @@ pub fn process_initialize_registry(
     let registry = unsafe { borrow_account_data_mut::<SlabRegistry>(registry_account)? };
 
     registry.initialize_in_place(*program_id, *governance, bump);
+    registry.quote_mint = *quote_mint;
 
     msg!("Registry initialized successfully");
     Ok(())
@@ -0,0 +1,366 @@
+//! Insurance fund custody - token movements behind `InsuranceState`
+//!
+//! The insurance fund's tokens live in a dedicated vault per collateral mint
+//! (PDA ["insurance", mint]), apart from user collateral; governance creates
+//! it with `initialize_insurance_vault` before the mint is traded. Whenever an
+//! instruction changes `InsuranceState.vault_balance` (taker fee accrual,
+//! liquidation penalties, bad debt payouts), the net change is moved between
+//! the collateral vault and the insurance vault with an SPL transfer, so the
+//! recorded fund balance is always backed by tokens in its own account.
+
+use crate::instructions::validate_governance;
+use crate::pda::{INSURANCE_SEED, VAULT_SEED};
+use crate::state::{SlabRegistry, Vault};
+use crate::token::{read_token_account, transfer, transfer_signed, validate_token_program};
+use percolator_common::*;
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Accounts needed to move tokens between the collateral and insurance vaults
+pub struct InsuranceAccounts<'a> {
+    /// Collateral vault PDA (authority of the collateral token account)
+    pub vault_account: &'a AccountInfo,
+    /// Collateral vault token account (must match `Vault.token_account`)
+    pub vault_token_account: &'a AccountInfo,
+    /// Insurance vault PDA (authority of the insurance token account)
+    pub insurance_account: &'a AccountInfo,
+    /// Insurance vault token account (must match its `Vault.token_account`)
+    pub insurance_token_account: &'a AccountInfo,
+    /// SPL Token program
+    pub token_program: &'a AccountInfo,
+}
+
+/// Mirror a change in the recorded insurance balance in vault accounting
+///
+/// Growth of the fund is taken out of the collateral vault's available
+/// balance; payouts flow back into it.
+///
+/// # Returns
+/// * Net flow into the insurance vault (negative = paid out)
+pub fn rebalance_vaults(
+    balance_before: u128,
+    balance_after: u128,
+    vault: &mut Vault,
+    insurance_vault: &mut Vault,
+) -> Result<i128, PercolatorError> {
+    if balance_after > balance_before {
+        let inflow = balance_after - balance_before;
+        vault.withdraw(inflow).map_err(|_| {
+            msg!("Error: Collateral vault cannot fund insurance accrual");
+            PercolatorError::InsufficientFunds
+        })?;
+        insurance_vault.deposit(inflow);
+        Ok(inflow.min(i128::MAX as u128) as i128)
+    } else {
+        let outflow = balance_before - balance_after;
+        if outflow == 0 {
+            return Ok(0);
+        }
+        insurance_vault.withdraw(outflow).map_err(|_| {
+            msg!("Error: Insurance vault balance below recorded fund");
+            PercolatorError::InsufficientFunds
+        })?;
+        vault.deposit(outflow);
+        Ok(-(outflow.min(i128::MAX as u128) as i128))
+    }
+}
+
+/// Settle the insurance fund's net change during an instruction in tokens
+///
+/// `balance_before` is `registry.insurance_state.vault_balance` captured at
+/// the start of the instruction. The insurance account's owner and PDA are
+/// checked by the entrypoint.
+pub(crate) fn settle_insurance_flow(
+    balance_before: u128,
+    registry: &SlabRegistry,
+    vault: &mut Vault,
+    accounts: &InsuranceAccounts,
+) -> Result<(), PercolatorError> {
+    let balance_after = registry.insurance_state.vault_balance;
+    if balance_after == balance_before {
+        return Ok(());
+    }
+
+    let insurance_vault = unsafe { borrow_account_data_mut::<Vault>(accounts.insurance_account)? };
+    if insurance_vault.mint != vault.mint {
+        msg!("Error: Insurance vault mint does not match collateral vault");
+        return Err(PercolatorError::InvalidMint);
+    }
+    if accounts.vault_token_account.key() != &vault.token_account
+        || accounts.insurance_token_account.key() != &insurance_vault.token_account
+    {
+        msg!("Error: Vault token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    validate_token_program(accounts.token_program)?;
+
+    let flow = rebalance_vaults(balance_before, balance_after, vault, insurance_vault)?;
+
+    if flow > 0 {
+        // Collateral vault -> insurance vault (collateral vault PDA signs)
+        let bump_array = [vault.bump];
+        let seeds = [
+            Seed::from(VAULT_SEED),
+            Seed::from(vault.mint.as_ref()),
+            Seed::from(&bump_array[..]),
+        ];
+        transfer_signed(
+            accounts.vault_token_account,
+            accounts.insurance_token_account,
+            accounts.vault_account,
+            accounts.token_program,
+            flow as u64,
+            Signer::from(&seeds),
+        )?;
+        msg!("Insurance accrual moved to insurance vault");
+    } else {
+        // Insurance vault -> collateral vault (insurance vault PDA signs)
+        let bump_array = [insurance_vault.bump];
+        let seeds = [
+            Seed::from(INSURANCE_SEED),
+            Seed::from(insurance_vault.mint.as_ref()),
+            Seed::from(&bump_array[..]),
+        ];
+        transfer_signed(
+            accounts.insurance_token_account,
+            accounts.vault_token_account,
+            accounts.insurance_account,
+            accounts.token_program,
+            flow.unsigned_abs() as u64,
+            Signer::from(&seeds),
+        )?;
+        msg!("Insurance payout moved to collateral vault");
+    }
+
+    Ok(())
+}
+
+/// Process initialize_insurance_vault instruction (governance)
+///
+/// Writes the insurance vault PDA for `mint` and binds it to a token account
+/// the PDA owns, so trades and liquidations have somewhere to move the
+/// fund's tokens. The entrypoint checks the PDA address and passes its bump.
+///
+/// # Arguments
+/// * `registry` - Slab registry (governance authority)
+/// * `insurance_vault` - Uninitialized insurance vault account data
+/// * `insurance_key` - Insurance vault PDA address (token account owner)
+/// * `governance` - Governance signer
+/// * `insurance_token_account` - SPL Token account for `mint` owned by the PDA
+/// * `mint` - Collateral mint the fund is held in
+/// * `bump` - PDA bump seed
+pub fn process_initialize_insurance_vault(
+    registry: &SlabRegistry,
+    insurance_vault: &mut Vault,
+    insurance_key: &Pubkey,
+    governance: &AccountInfo,
+    insurance_token_account: &AccountInfo,
+    mint: Pubkey,
+    bump: u8,
+) -> Result<(), PercolatorError> {
+    validate_signer(governance)?;
+    validate_governance(registry, governance.key())?;
+
+    if insurance_vault.router_id != Pubkey::default() {
+        msg!("Error: Insurance vault already initialized");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let (token_mint, token_owner) = read_token_account(insurance_token_account)?;
+    if token_mint != mint {
+        msg!("Error: Insurance token account mint mismatch");
+        return Err(PercolatorError::InvalidMint);
+    }
+    if &token_owner != insurance_key {
+        msg!("Error: Insurance token account not owned by the insurance vault");
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    *insurance_vault = Vault {
+        router_id: registry.router_id,
+        mint,
+        token_account: *insurance_token_account.key(),
+        balance: 0,
+        total_pledged: 0,
+        bump,
+        _padding: [0; 7],
+    };
+
+    msg!("InitializeInsuranceVault executed successfully");
+    Ok(())
+}
+
+/// Process top_up_insurance instruction (governance)
+///
+/// Transfers `amount` from a governance-controlled token account into the
+/// insurance vault and credits the fund.
+///
+/// # Arguments
+/// * `registry` - Slab registry holding the insurance state
+/// * `insurance_vault` - Insurance vault for the mint
+/// * `governance` - Governance signer (owner of the source token account)
+/// * `source_token_account` - Token account the top-up is drawn from
+/// * `insurance_token_account` - Destination (must match `insurance_vault.token_account`)
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount to add (base units)
+pub fn process_top_up_insurance(
+    registry: &mut SlabRegistry,
+    insurance_vault: &mut Vault,
+    governance: &AccountInfo,
+    source_token_account: &AccountInfo,
+    insurance_token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u128,
+) -> Result<(), PercolatorError> {
+    validate_signer(governance)?;
+    validate_governance(registry, governance.key())?;
+
+    if amount == 0 || amount > u64::MAX as u128 {
+        msg!("Error: Invalid top-up amount");
+        return Err(PercolatorError::InvalidAmount);
+    }
+    if insurance_token_account.key() != &insurance_vault.token_account {
+        msg!("Error: Insurance token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    validate_token_program(token_program)?;
+
+    transfer(
+        source_token_account,
+        insurance_token_account,
+        governance,
+        token_program,
+        amount as u64,
+    )?;
+
+    insurance_vault.deposit(amount);
+    registry.insurance_state.top_up(amount);
+
+    msg!("TopUpInsurance executed successfully");
+    Ok(())
+}
+
+/// Process withdraw_insurance_surplus instruction (governance)
+///
+/// Surplus may only leave the fund while no bad debt is left uncovered.
+/// Tokens are transferred out of the insurance vault signed by its PDA.
+///
+/// # Arguments
+/// * `registry` - Slab registry holding the insurance state
+/// * `insurance_vault` - Insurance vault for the mint
+/// * `governance` - Governance signer
+/// * `insurance_account` - Insurance vault PDA account (token authority)
+/// * `insurance_token_account` - Source (must match `insurance_vault.token_account`)
+/// * `destination_token_account` - Token account receiving the surplus
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount to withdraw (base units)
+pub fn process_withdraw_insurance_surplus(
+    registry: &mut SlabRegistry,
+    insurance_vault: &mut Vault,
+    governance: &AccountInfo,
+    insurance_account: &AccountInfo,
+    insurance_token_account: &AccountInfo,
+    destination_token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u128,
+) -> Result<(), PercolatorError> {
+    validate_signer(governance)?;
+    validate_governance(registry, governance.key())?;
+
+    if amount == 0 || amount > u64::MAX as u128 {
+        msg!("Error: Invalid withdrawal amount");
+        return Err(PercolatorError::InvalidAmount);
+    }
+    if registry.insurance_state.uncovered_bad_debt > 0 {
+        msg!("Error: Cannot withdraw surplus while bad debt is uncovered");
+        return Err(PercolatorError::InsuranceDebtOutstanding);
+    }
+    if insurance_token_account.key() != &insurance_vault.token_account {
+        msg!("Error: Insurance token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    validate_token_program(token_program)?;
+
+    registry.insurance_state.withdraw_surplus(amount).map_err(|_| {
+        msg!("Error: Withdrawal exceeds insurance fund balance");
+        PercolatorError::InsufficientFunds
+    })?;
+    insurance_vault.withdraw(amount).map_err(|_| {
+        msg!("Error: Insurance vault balance below recorded fund");
+        PercolatorError::InsufficientFunds
+    })?;
+
+    let bump_array = [insurance_vault.bump];
+    let seeds = [
+        Seed::from(INSURANCE_SEED),
+        Seed::from(insurance_vault.mint.as_ref()),
+        Seed::from(&bump_array[..]),
+    ];
+    transfer_signed(
+        insurance_token_account,
+        destination_token_account,
+        insurance_account,
+        token_program,
+        amount as u64,
+        Signer::from(&seeds),
+    )?;
+
+    msg!("WithdrawInsuranceSurplus executed successfully");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+
+    fn vault(balance: u128) -> Vault {
+        Vault {
+            router_id: Pubkey::default(),
+            mint: Pubkey::default(),
+            token_account: Pubkey::default(),
+            balance,
+            total_pledged: 0,
+            bump: 0,
+            _padding: [0; 7],
+        }
+    }
+
+    #[test]
+    fn test_rebalance_moves_accrual_and_payout() {
+        let mut collateral = vault(1_000);
+        let mut insurance = vault(100);
+
+        // Fee accrual leaves the collateral vault
+        assert_eq!(rebalance_vaults(100, 130, &mut collateral, &mut insurance), Ok(30));
+        assert_eq!(collateral.balance, 970);
+        assert_eq!(insurance.balance, 130);
+
+        // Payout flows back
+        assert_eq!(rebalance_vaults(130, 80, &mut collateral, &mut insurance), Ok(-50));
+        assert_eq!(collateral.balance, 1_020);
+        assert_eq!(insurance.balance, 80);
+
+        assert_eq!(rebalance_vaults(80, 80, &mut collateral, &mut insurance), Ok(0));
+    }
+
+    #[test]
+    fn test_rebalance_respects_pledged_collateral() {
+        let mut collateral = vault(1_000);
+        collateral.total_pledged = 990;
+        let mut insurance = vault(0);
+
+        assert_eq!(
+            rebalance_vaults(0, 20, &mut collateral, &mut insurance),
+            Err(PercolatorError::InsufficientFunds)
+        );
+        assert_eq!(
+            rebalance_vaults(20, 0, &mut collateral, &mut insurance),
+            Err(PercolatorError::InsufficientFunds)
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! Liquidate user positions via reduce-only cross-slab execution
 
+use crate::instructions::InsuranceAccounts;
 use crate::state::{Portfolio, SlabRegistry, Vault};
 use percolator_common::*;
-use pinocchio::{account_info::AccountInfo, msg};
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 
 /// Liquidation mode based on health
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,21 +38,77 @@ pub fn determine_mode(health: i128, preliq_buffer: i128) -> Option<LiquidationMo
     }
 }
 
+/// Liquidation penalty split between the keeper and the insurance fund
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiquidationPenalty {
+    /// Credited to the calling keeper's portfolio
+    pub keeper: u128,
+    /// Accrued to the insurance fund
+    pub insurance: u128,
+}
+
+impl LiquidationPenalty {
+    /// Total charged to the liquidated user
+    pub fn total(&self) -> u128 {
+        self.keeper + self.insurance
+    }
+}
+
+/// Calculate the liquidation penalty (using verified math)
+///
+/// penalty = notional * fee_bps / 10_000, capped at the user's remaining
+/// positive equity so the penalty can never create or deepen bad debt.
+/// The keeper receives `keeper_share_bps` of it, insurance the remainder.
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in fee calculations.
+pub fn calculate_liquidation_penalty(
+    liquidated_notional: u128,
+    equity: i128,
+    fee_bps: u64,
+    keeper_share_bps: u64,
+) -> LiquidationPenalty {
+    use model_safety::math::{div_u128, min_u128, mul_u128, sub_u128};
+
+    if equity <= 0 {
+        return LiquidationPenalty::default();
+    }
+
+    let uncapped = div_u128(mul_u128(liquidated_notional, fee_bps as u128), 10_000);
+    let total = min_u128(uncapped, equity as u128);
+
+    let keeper_share_bps = keeper_share_bps.min(10_000) as u128;
+    let keeper = div_u128(mul_u128(total, keeper_share_bps), 10_000);
+
+    LiquidationPenalty {
+        keeper,
+        insurance: sub_u128(total, keeper),
+    }
+}
+
 /// Process liquidate user instruction
 ///
 /// This instruction liquidates an undercollateralized user by executing
-/// reduce-only orders across slabs to bring them back to health.
+/// reduce-only orders across slabs to bring them back to health. The
+/// calling keeper earns a share of the liquidation penalty.
 ///
 /// # Arguments
 /// * `portfolio` - User's portfolio account (to be liquidated)
+/// * `keeper_portfolio` - Calling keeper's portfolio (receives keeper reward)
+/// * `keeper` - Keeper pubkey (signer, must own `keeper_portfolio`)
 /// * `registry` - Slab registry with liquidation parameters
 /// * `vault` - Collateral vault
 /// * `router_authority` - Router authority PDA (for CPI signing)
 /// * `oracle_accounts` - Oracle price feed accounts (for price validation)
 /// * `slab_accounts` - Array of slab accounts to execute on
 /// * `receipt_accounts` - Array of receipt PDAs (one per slab)
+/// * `lp_portfolio_accounts` - Portfolios of each slab's LP owner (one per slab)
+/// * `insurance` - Vault token accounts for insurance accruals and payouts
 /// * `is_preliq` - Force pre-liquidation mode (if false, auto-determine)
-/// * `current_ts` - Current timestamp (for rate limiting)
+/// * `now_ts` - Clock sysvar timestamp (oracle staleness, cooldown,
+///   insurance and haircut caps)
 ///
 /// # Returns
 /// * Updates portfolio with reduced exposures
@@ -60,19 +117,60 @@ pub fn determine_mode(health: i128, preliq_buffer: i128) -> Option<LiquidationMo
 /// * All-or-nothing atomicity
 pub fn process_liquidate_user(
     portfolio: &mut Portfolio,
+    keeper_portfolio: &mut Portfolio,
+    keeper: &Pubkey,
     registry: &mut SlabRegistry,
     vault: &mut Vault,
     router_authority: &AccountInfo,
     oracle_accounts: &[AccountInfo],
     slab_accounts: &[AccountInfo],
     receipt_accounts: &[AccountInfo],
+    lp_portfolio_accounts: &[AccountInfo],
+    insurance: &InsuranceAccounts,
     is_preliq: bool,
-    current_ts: u64,
+    now_ts: u64,
 ) -> Result<(), PercolatorError> {
     msg!("Liquidate: Starting liquidation check");
 
-    // Step 1: Calculate health = equity - MM
-    let health = portfolio.equity.saturating_sub(portfolio.mm as i128);
+    if &keeper_portfolio.user != keeper {
+        msg!("Error: Keeper portfolio does not belong to keeper");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    // Step 0: Read oracle prices from oracle accounts
+    // Each oracle is bound to a slab by instrument pubkey (never by account
+    // position). Oracle prices refresh the registry marks so margin is valued
+    // at oracle.
+    use crate::liquidation::planner::OraclePrice;
+    const MAX_ORACLES: usize = 16;
+    if oracle_accounts.len() > MAX_ORACLES {
+        msg!("Error: Too many oracle accounts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    let mut oracle_prices = [OraclePrice { instrument_idx: 0, price: 0 }; MAX_ORACLES];
+    let mut oracle_count = 0;
+
+    for oracle_account in oracle_accounts.iter() {
+        let (instrument_idx, price, price_ts) =
+            read_bound_oracle(oracle_account, registry, slab_accounts, now_ts)?;
+
+        registry.set_instrument_mark(instrument_idx, price, price_ts);
+        oracle_prices[oracle_count] = OraclePrice {
+            instrument_idx,
+            price,
+        };
+        oracle_count += 1;
+    }
+    msg!("Liquidate: Read oracle prices from oracle accounts");
+
+    // Step 1: Calculate health = equity - MM (shared margin engine, incl. LP buckets)
+    // Vesting, haircut catchup and funding are applied first, so health and
+    // mode reflect the same equity the liquidation then executes against
+    use crate::instructions::touch_portfolio;
+    use crate::margin::refresh_margin;
+    touch_portfolio(portfolio, registry);
+    let margin = refresh_margin(portfolio, registry, now_ts)?;
+    let health = portfolio.equity.saturating_sub(margin.total_mm as i128);
     msg!("Liquidate: Health calculated");
 
     // Store health in portfolio for tracking
@@ -118,49 +216,13 @@ pub fn process_liquidate_user(
 
     // Step 3: Check rate limiting (for pre-liquidation deleveraging)
     if mode == LiquidationMode::PreLiquidation {
-        let time_since_last = current_ts.saturating_sub(portfolio.last_liquidation_ts);
+        let time_since_last = now_ts.saturating_sub(portfolio.last_liquidation_ts);
         if time_since_last < portfolio.cooldown_seconds {
             msg!("Error: Cooldown period not elapsed");
             return Err(PercolatorError::LiquidationCooldown);
         }
     }
 
-    // Step 4: Read oracle prices from oracle accounts
-    use crate::liquidation::planner::OraclePrice;
-    const MAX_ORACLES: usize = 16;
-    let mut oracle_prices = [OraclePrice { instrument_idx: 0, price: 0 }; MAX_ORACLES];
-    let mut oracle_count = 0;
-
-    for (i, oracle_account) in oracle_accounts.iter().enumerate() {
-        if i >= MAX_ORACLES {
-            break;
-        }
-
-        // Read PriceOracle struct from account data
-        // PriceOracle is 128 bytes total
-        let oracle_data = oracle_account.try_borrow_data()
-            .map_err(|_| PercolatorError::InvalidAccount)?;
-
-        if oracle_data.len() < 128 {
-            msg!("Warning: Oracle account too small, skipping");
-            continue;
-        }
-
-        // Extract price (at offset 72: magic(8) + version(1) + bump(1) + padding(6) + authority(32) + instrument(32) + price(8))
-        let price_bytes = [
-            oracle_data[72], oracle_data[73], oracle_data[74], oracle_data[75],
-            oracle_data[76], oracle_data[77], oracle_data[78], oracle_data[79],
-        ];
-        let price = i64::from_le_bytes(price_bytes);
-
-        // Use index as instrument_idx for v0 (in production, would map instrument pubkey to index)
-        oracle_prices[oracle_count] = OraclePrice {
-            instrument_idx: i as u16,
-            price,
-        };
-        oracle_count += 1;
-    }
-    msg!("Liquidate: Read oracle prices from oracle accounts");
 
     // Step 5: Build SlabInfo array and call reduce-only planner
     use crate::liquidation::planner::{plan_reduce_only, SlabInfo};
@@ -170,6 +232,7 @@ pub fn process_liquidate_user(
         slab_idx: 0,
         instrument_idx: 0,
         mark_price: 0,
+        seqno: 0,
     }; MAX_SLABS_FOR_LIQ];
     let mut slab_count = 0;
 
@@ -178,27 +241,36 @@ pub fn process_liquidate_user(
             break;
         }
 
-        // Read SlabHeader to get mark price
-        let slab_data = slab_account.try_borrow_data()
-            .map_err(|_| PercolatorError::InvalidAccount)?;
+        // Resolve registry slab index (same key space as portfolio exposures)
+        let slab_idx = match registry.find_slab(slab_account.key()) {
+            Some((idx, _)) => idx,
+            None => {
+                msg!("Warning: Slab not registered or inactive, skipping");
+                continue;
+            }
+        };
 
-        if slab_data.len() < 96 {
-            msg!("Warning: Slab account too small, skipping");
+        // Read SlabHeader to get instrument and mark price
+        let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+        if &header.magic != SlabHeader::MAGIC {
+            msg!("Warning: Invalid slab account data, skipping");
             continue;
         }
 
-        // mark_px is at offset 88 in SlabHeader
-        let mark_bytes = [
-            slab_data[88], slab_data[89], slab_data[90], slab_data[91],
-            slab_data[92], slab_data[93], slab_data[94], slab_data[95],
-        ];
-        let mark_price = i64::from_le_bytes(mark_bytes);
+        let instrument_idx = match registry.find_instrument(&header.instrument) {
+            Some(idx) => idx,
+            None => {
+                msg!("Warning: Slab instrument not known to registry, skipping");
+                continue;
+            }
+        };
 
         slab_infos[slab_count] = SlabInfo {
             slab_id: *slab_account.key(),
-            slab_idx: i as u16,
-            instrument_idx: i as u16, // v0: use slab index as instrument index
-            mark_price,
+            slab_idx,
+            instrument_idx,
+            mark_price: header.mark_px,
+            seqno: header.seqno,
         };
         slab_count += 1;
     }
@@ -216,87 +288,230 @@ pub fn process_liquidate_user(
     msg!("Liquidate: Planner generated liquidation plan");
 
     // Step 6: Execute via process_execute_cross_slab
-    if plan.split_count == 0 {
+    // Execute the liquidation using the same cross-slab logic as normal orders.
+    // Splits are matched to slab accounts by key, and the IM check is skipped:
+    // a partially liquidated portfolio is reduce-only and may stay below IM.
+    use crate::instructions::{execute_splits, NO_SLIPPAGE_LIMIT};
+    let insurance_before = registry.insurance_state.vault_balance;
+
+    // Fill events between LiquidationStart and LiquidationEnd are the
+    // liquidation's own fills
+    emit(&Event::LiquidationStart {
+        account: portfolio.user,
+        keeper: *keeper,
+        equity: portfolio.equity,
+        mm: margin.total_mm,
+        is_preliq: mode == LiquidationMode::PreLiquidation,
+    });
+    let liquidated_notional = if plan.split_count == 0 {
+        // Nothing left to close, but a deficit must still be settled
         msg!("Liquidate: No splits planned, no execution needed");
-        return Ok(());
-    }
+        0
+    } else {
+        let notional = execute_splits(
+            portfolio,
+            true,
+            registry,
+            router_authority,
+            slab_accounts,
+            receipt_accounts,
+            lp_portfolio_accounts,
+            plan.get_splits(),
+            NO_SLIPPAGE_LIMIT,
+        )?;
+        crate::margin::refresh_margin(portfolio, registry, now_ts)?;
+        msg!("Liquidate: Execution complete via cross-slab logic");
+        notional
+    };
 
-    // Execute the liquidation using the same cross-slab logic as normal orders
-    // Clone the user pubkey before the mutable borrow to avoid borrow checker issues
-    let user_pubkey = portfolio.user;
-    use crate::instructions::process_execute_cross_slab;
-    process_execute_cross_slab(
-        portfolio,
-        &user_pubkey,
-        vault,
-        registry,
-        router_authority,
-        &slab_accounts[..plan.split_count],
-        &receipt_accounts[..plan.split_count],
-        plan.get_splits(),
-    )?;
-    msg!("Liquidate: Execution complete via cross-slab logic");
+    // Step 6.5: Charge the liquidation penalty (capped at remaining equity)
+    let penalty = calculate_liquidation_penalty(
+        liquidated_notional,
+        portfolio.equity,
+        registry.liquidation_fee_bps,
+        registry.liquidation_keeper_share_bps,
+    );
+    if penalty.total() > 0 {
+        let (pnl_before, keeper_pnl_before) = (portfolio.pnl, keeper_portfolio.pnl);
+        portfolio.book_fill(0, penalty.total() as i128);
+        keeper_portfolio.book_fill(penalty.keeper as i128, 0);
+        registry.global_haircut.track_pnl_change(pnl_before, portfolio.pnl);
+        registry.global_haircut.track_pnl_change(keeper_pnl_before, keeper_portfolio.pnl);
+        registry.insurance_state.accrue_liquidation_fee(penalty.insurance);
+        msg!("Liquidate: Penalty charged to user, split to keeper and insurance");
+    }
 
     // Step 7: Update portfolio health and timestamp
-    portfolio.health = portfolio.equity.saturating_sub(portfolio.mm as i128);
-    portfolio.last_liquidation_ts = current_ts;
+    portfolio.health = portfolio.equity.saturating_sub(portfolio.calculate_total_mm() as i128);
+    portfolio.last_liquidation_ts = now_ts;
 
     msg!("Liquidate: Portfolio updated");
 
     // Step 7.5: Settle bad debt via insurance fund if equity < 0
-    if portfolio.equity < 0 {
-        let bad_debt = portfolio.equity.abs() as u128;
-
-        // Calculate event notional (sum of liquidation fill notionals)
-        let mut event_notional: u128 = 0;
-        for split in plan.get_splits() {
-            let notional = ((split.qty.abs() as u128) * (split.limit_px.abs() as u128)) / 1_000_000;
-            event_notional = event_notional.saturating_add(notional);
-        }
-
-        let (payout, uncovered) = registry.insurance_state.settle_bad_debt(
-            bad_debt,
-            event_notional,
-            &registry.insurance_params,
-            current_ts,
-        );
-
-        if payout > 0 {
-            // Apply insurance payout to portfolio equity
-            portfolio.equity = portfolio.equity.saturating_add(payout as i128);
-            msg!("Insurance payout applied to cover bad debt");
-        }
+    let bad_debt = settle_bad_debt(portfolio, registry, liquidated_notional, now_ts);
+
+    // Step 7.6: Back the fund's net change (fees in, payout out) with tokens
+    use crate::instructions::settle_insurance_flow;
+    settle_insurance_flow(insurance_before, registry, vault, insurance)?;
+
+    // Step 8: Close the liquidation's event span
+    emit(&Event::LiquidationEnd {
+        account: portfolio.user,
+        notional: liquidated_notional,
+        penalty: penalty.total(),
+        bad_debt,
+    });
+    msg!("Liquidate: Liquidation completed successfully");
 
-        if uncovered > 0 {
-            msg!("Warning: Uncovered bad debt remains after insurance payout");
+    Ok(())
+}
 
-            // Trigger global haircut to socialize the uncovered loss across all users
-            // Apply haircut: new_index = old_index * (tvl - loss) / tvl
-            let tvl = vault.balance as i128;  // Simplified: use vault balance as TVL proxy
+/// Settle a portfolio's newly booked deficit (using verified math)
+///
+/// Only the part of the negative equity not already booked by an earlier
+/// partial liquidation is new bad debt. It is paid from insurance first;
+/// the uncovered rest is socialized across winners' PnL, and whatever the
+/// haircut could not absorb stays booked on the portfolio (and in the
+/// fund's `uncovered_bad_debt`) for auto-deleveraging to recover. If equity
+/// has recovered since the last booking, the difference is released.
+///
+/// # Arguments
+/// * `portfolio` - Liquidated portfolio, margin already refreshed
+/// * `registry` - Slab registry (insurance and haircut state)
+/// * `event_notional` - Liquidation fill notional (per-event payout cap)
+/// * `now_ts` - Clock sysvar timestamp (daily caps)
+///
+/// # Returns
+/// * Newly booked bad debt
+pub fn settle_bad_debt(
+    portfolio: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    event_notional: u128,
+    now_ts: u64,
+) -> u128 {
+    use model_safety::math::{add_u128, sub_u128};
+
+    let deficit = (-portfolio.equity).max(0) as u128;
+    let booked = portfolio.booked_bad_debt as u128;
+    if deficit <= booked {
+        if booked > deficit {
+            registry.insurance_state.recover_bad_debt(sub_u128(booked, deficit));
+            portfolio.booked_bad_debt = deficit as u64;
+        }
+        return 0;
+    }
+    let bad_debt = sub_u128(deficit, booked);
+
+    let (payout, uncovered) = registry.insurance_state.settle_bad_debt(
+        bad_debt,
+        event_notional,
+        &registry.insurance_params,
+        now_ts,
+    );
+
+    if payout > 0 {
+        // Apply insurance payout to portfolio equity
+        portfolio.equity = portfolio.equity.saturating_add(payout as i128);
+        msg!("Insurance payout applied to cover bad debt");
+    }
+    emit(&Event::InsurancePayout {
+        account: portfolio.user,
+        amount: payout,
+        uncovered,
+    });
+
+    let mut unabsorbed = uncovered;
+    if uncovered > 0 {
+        msg!("Warning: Uncovered bad debt remains after insurance payout");
+
+        // Socialize the uncovered loss across winners' positive PnL
+        // (capped per event and per day); applied lazily on user touch.
+        // The socialized part is no longer owed, so it leaves the fund's
+        // uncovered total rather than also waiting for ADL.
+        let socialized = registry.global_haircut.socialize_loss(uncovered, now_ts);
+        if socialized > 0 {
+            portfolio.equity = portfolio.equity.saturating_add(socialized as i128);
+            registry.insurance_state.recover_bad_debt(socialized);
+            unabsorbed = sub_u128(uncovered, socialized);
+            msg!("Global haircut triggered to socialize uncovered bad debt");
+            emit(&Event::Haircut {
+                event_id: registry.global_haircut.last_event_id,
+                shortfall: uncovered,
+                socialized,
+                pnl_index: registry.global_haircut.pnl_index,
+            });
+        }
+    }
 
-            if tvl > 0 {
-                let loss = uncovered as i128;
-                let tvl_after_loss = tvl.saturating_sub(loss).max(1);  // Ensure non-zero denominator
+    portfolio.booked_bad_debt = add_u128(booked, unabsorbed).min(u64::MAX as u128) as u64;
+    bad_debt
+}
 
-                // Apply haircut ratio to global PnL index
-                // new_index = old_index * tvl_after_loss / tvl
-                let old_index = registry.global_haircut.pnl_index;
-                registry.global_haircut.pnl_index = (old_index * tvl_after_loss) / tvl;
+/// Read an oracle account and bind it to one of the slabs being liquidated on
+///
+/// The oracle must be a valid `PriceOracle` whose instrument matches a
+/// registered slab's header instrument and whose account is owned by that
+/// slab's `oracle_id` program. Its price must pass the registry's freshness
+/// and confidence limits, with age measured against `now_ts`, which callers
+/// take from the Clock sysvar.
+///
+/// # Returns
+/// * `(instrument_idx, price, price_ts)` keyed by the registry instrument
+///   table, with the oracle's publish time
+pub(crate) fn read_bound_oracle(
+    oracle_account: &AccountInfo,
+    registry: &SlabRegistry,
+    slab_accounts: &[AccountInfo],
+    now_ts: u64,
+) -> Result<(u16, i64, u64), PercolatorError> {
+    use crate::liquidation::oracle::check_oracle_price;
+
+    let oracle = unsafe { borrow_account_data::<PriceOracle>(oracle_account)? };
+    if !oracle.validate() {
+        msg!("Error: Invalid oracle account data");
+        return Err(PercolatorError::InvalidAccount);
+    }
 
-                msg!("Global haircut triggered to socialize uncovered bad debt");
-            }
+    let mut instrument_matched = false;
+    let mut bound = false;
+    for slab_account in slab_accounts.iter() {
+        let entry = match registry.find_slab(slab_account.key()) {
+            Some((_, entry)) => entry,
+            None => continue,
+        };
+        let header = unsafe { borrow_account_data::<SlabHeader>(slab_account)? };
+        if &header.magic != SlabHeader::MAGIC || header.instrument != oracle.instrument {
+            continue;
+        }
+        instrument_matched = true;
+        if oracle_account.owner() == &entry.oracle_id {
+            bound = true;
+            break;
         }
     }
 
-    // Step 8: Emit liquidation events (simplified for v0)
-    // In production, emit LiquidationStart, LiquidationFill, LiquidationEnd
-    msg!("Liquidate: Liquidation completed successfully");
+    if !instrument_matched {
+        msg!("Error: Oracle instrument does not match any slab");
+        return Err(PercolatorError::InvalidInstrument);
+    }
+    if !bound {
+        msg!("Error: Oracle not owned by the slab's oracle program");
+        return Err(PercolatorError::InvalidAccountOwner);
+    }
 
-    let _ = vault; // Will be used in production
-    let _ = router_authority; // Will be used for CPI signing
-    let _ = oracle_accounts; // Will be used for price reading
+    let price = check_oracle_price(
+        oracle,
+        now_ts,
+        registry.oracle_max_age_secs,
+        registry.oracle_max_conf_bps,
+    )?;
 
-    Ok(())
+    let instrument_idx = registry.find_instrument(&oracle.instrument).ok_or_else(|| {
+        msg!("Error: Oracle instrument not known to registry");
+        PercolatorError::InvalidInstrument
+    })?;
+
+    Ok((instrument_idx, price, oracle.timestamp as u64))
 }
 
 #[cfg(test)]
@@ -342,12 +557,13 @@ mod tests {
     fn test_liquidation_mode_price_bands() {
         use crate::state::{SlabRegistry, SlabEntry};
         use pinocchio::pubkey::Pubkey;
-        use percolator_common::MAX_SLABS;
+        use percolator_common::{MAX_INSTRUMENTS, MAX_SLABS};
 
         // Create registry with different bands for pre-liq vs hard liq
         let registry = SlabRegistry {
             router_id: Pubkey::default(),
             governance: Pubkey::default(),
+            pending_governance: Pubkey::default(),
             slab_count: 0,
             bump: 0,
             _padding: [0; 5],
@@ -359,11 +575,32 @@ mod tests {
             router_cap_per_slab: 1_000_000,
             min_equity_to_quote: 100_000_000,
             oracle_tolerance_bps: 50,
+            liquidation_fee_bps: 50,
+            liquidation_keeper_share_bps: 5_000,
+            oracle_max_age_secs: 60,
+            oracle_max_conf_bps: 100,
+            funding_interval_secs: 3_600,
+            funding_cap_bps: 50,
             _padding2: [0; 8],
             insurance_params: crate::state::insurance::InsuranceParams::default(),
             insurance_state: crate::state::insurance::InsuranceState::default(),
             pnl_vesting_params: crate::state::pnl_vesting::PnlVestingParams::default(),
             global_haircut: crate::state::pnl_vesting::GlobalHaircut::default(),
+            exit_bucket_params: crate::state::withdrawal_limits::ExitBucketParams::default(),
+            withdrawal_thresholds: crate::state::withdrawal_limits::WithdrawalThresholds::default(),
+            emergency_mode: crate::state::withdrawal_limits::EmergencyMode::default(),
+            global_exit_bucket: crate::state::withdrawal_limits::GlobalExitBucket::default(),
+            instrument_count: 0,
+            _padding3: [0; 6],
+            instruments: [Pubkey::default(); MAX_INSTRUMENTS],
+            instrument_marks: [0; MAX_INSTRUMENTS],
+            instrument_mark_ts: [0; MAX_INSTRUMENTS],
+            instrument_cum_funding: [0; MAX_INSTRUMENTS],
+            instrument_funding_ts: [0; MAX_INSTRUMENTS],
+            quote_mint: Pubkey::default(),
+            collateral_count: 0,
+            _padding4: [0; 6],
+            collaterals: [crate::state::CollateralEntry::default(); crate::state::MAX_COLLATERALS],
             slabs: [SlabEntry {
                 slab_id: Pubkey::default(),
                 version_hash: [0; 32],
@@ -376,7 +613,8 @@ mod tests {
                 max_exposure: 0,
                 registered_ts: 0,
                 active: false,
-                _padding: [0; 7],
+                paused: false,
+                _padding: [0; 6],
             }; MAX_SLABS],
         };
 
@@ -389,6 +627,26 @@ mod tests {
         assert_eq!(hardliq_band, 200);
     }
 
+    #[test]
+    fn test_liquidation_penalty_split() {
+        // $10k liquidated at 0.5%: $50 penalty, 50/50 split
+        let penalty = calculate_liquidation_penalty(10_000_000_000, 1_000_000_000, 50, 5_000);
+        assert_eq!(penalty.total(), 50_000_000);
+        assert_eq!(penalty.keeper, 25_000_000);
+        assert_eq!(penalty.insurance, 25_000_000);
+    }
+
+    #[test]
+    fn test_liquidation_penalty_capped_by_equity() {
+        // Only $20 of equity left: penalty capped so equity ends at zero
+        let penalty = calculate_liquidation_penalty(10_000_000_000, 20_000_000, 50, 5_000);
+        assert_eq!(penalty.total(), 20_000_000);
+
+        // Already in bad debt: no penalty at all
+        let penalty = calculate_liquidation_penalty(10_000_000_000, -1, 50, 5_000);
+        assert_eq!(penalty.total(), 0);
+    }
+
     #[test]
     fn test_liquidation_respects_oracle_alignment() {
         // This test verifies that liquidation planning uses oracle alignment
@@ -406,4 +664,46 @@ mod tests {
         let misaligned_mark = 1_010_000;  // 1.0% diff
         assert!(!validate_oracle_alignment(misaligned_mark, oracle_price, tolerance_bps));
     }
+
+    #[test]
+    fn test_bad_debt_booked_once_across_partial_liquidations() {
+        const S: i128 = 1_000_000;
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        portfolio.update_equity(-100 * S);
+        assert_eq!(settle_bad_debt(&mut portfolio, &mut registry, 0, 0), 100 * S as u128);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, 100 * S as u128);
+
+        // A second partial liquidation with the same deficit books nothing new
+        assert_eq!(settle_bad_debt(&mut portfolio, &mut registry, 0, 0), 0);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, 100 * S as u128);
+
+        // Only the newly realized shortfall is added
+        portfolio.update_equity(-130 * S);
+        assert_eq!(settle_bad_debt(&mut portfolio, &mut registry, 0, 0), 30 * S as u128);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, 130 * S as u128);
+
+        // Recovered equity releases the booked difference
+        portfolio.update_equity(-50 * S);
+        assert_eq!(settle_bad_debt(&mut portfolio, &mut registry, 0, 0), 0);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, 50 * S as u128);
+        assert_eq!(portfolio.booked_bad_debt, 50 * S as u64);
+    }
+
+    #[test]
+    fn test_socialized_bad_debt_leaves_uncovered_total() {
+        const S: i128 = 1_000_000;
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry.global_haircut.total_positive_pnl = 10_000 * S as u128;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        portfolio.update_equity(-100 * S);
+        settle_bad_debt(&mut portfolio, &mut registry, 0, 0);
+
+        // Whatever the haircut absorbed is not also left for ADL to recover
+        assert!(portfolio.equity > -100 * S);
+        assert_eq!(registry.insurance_state.uncovered_bad_debt, (-portfolio.equity) as u128);
+        assert_eq!(portfolio.booked_bad_debt as i128, -portfolio.equity);
+    }
 }
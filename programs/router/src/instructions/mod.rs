@@ -8,6 +8,14 @@ pub mod execute_cross_slab;
 pub mod liquidate_user;
 pub mod burn_lp_shares;
 pub mod cancel_lp_orders;
+pub mod auto_deleverage;
+pub mod update_funding;
+pub mod governance;
+pub mod insurance_fund;
+pub mod collateral_price;
+pub mod capability;
+pub mod reserve_commit;
+pub mod update_marks;
 
 pub use initialize::*;
 pub use initialize_portfolio::*;
@@ -17,6 +25,14 @@ pub use execute_cross_slab::*;
 pub use liquidate_user::*;
 pub use burn_lp_shares::*;
 pub use cancel_lp_orders::*;
+pub use auto_deleverage::*;
+pub use update_funding::*;
+pub use governance::*;
+pub use insurance_fund::*;
+pub use collateral_price::*;
+pub use capability::*;
+pub use reserve_commit::*;
+pub use update_marks::*;
 
 /// Instruction discriminator (v0 minimal)
 #[repr(u8)]
@@ -38,6 +54,54 @@ pub enum RouterInstruction {
     BurnLpShares = 6,
     /// Cancel Slab LP orders (ONLY way to reduce Slab LP exposure)
     CancelLpOrders = 7,
+    /// Auto-deleverage a bankrupt position against ranked counterparties
+    AutoDeleverage = 8,
+    /// Accrue funding for an instrument (permissionless crank)
+    UpdateFunding = 9,
+    /// Register a slab (governance)
+    RegisterSlab = 10,
+    /// Pause, resume or deactivate a slab (governance)
+    SetSlabStatus = 11,
+    /// Update per-slab margin ratios (governance)
+    UpdateSlabParams = 12,
+    /// Update global risk, liquidation, oracle and funding params (governance)
+    UpdateRiskParams = 13,
+    /// Update insurance params (governance)
+    UpdateInsuranceParams = 14,
+    /// Update PnL vesting params (governance)
+    UpdateVestingParams = 15,
+    /// Update haircut caps (governance)
+    UpdateHaircutCaps = 16,
+    /// Propose a new governance authority (governance)
+    TransferGovernance = 17,
+    /// Accept a pending governance transfer (new authority)
+    AcceptGovernance = 18,
+    /// Add tokens to the insurance vault (governance)
+    TopUpInsurance = 19,
+    /// Withdraw insurance surplus while no bad debt is uncovered (governance)
+    WithdrawInsuranceSurplus = 20,
+    /// Register or reweight a non-quote collateral mint (governance)
+    SetCollateral = 21,
+    /// Cache a collateral mint's oracle price (permissionless crank)
+    UpdateCollateralPrice = 22,
+    /// Pledge principal to a (user, slab, mint) escrow
+    PledgeEscrow = 23,
+    /// Return escrowed funds to principal once all caps have expired
+    ReleaseEscrow = 24,
+    /// Issue a scoped, expiring debit capability against an escrow
+    IssueCap = 25,
+    /// Debit an escrow under a cap (slab LP owner)
+    CapDebit = 26,
+    /// Reserve liquidity across slabs (two-phase, phase 1)
+    ReserveCrossSlab = 27,
+    /// Commit reserved holds across slabs (two-phase, phase 2)
+    CommitCrossSlab = 28,
+    /// Cancel reserved holds across slabs
+    CancelCrossSlab = 29,
+    /// Cache instrument marks from registry-bound oracles (permissionless crank)
+    UpdateMarks = 30,
+    /// Initialize the insurance vault for a mint (governance)
+    InitializeInsuranceVault = 31,
 }
 
 // Note: Instruction dispatching is handled in entrypoint.rs
@@ -0,0 +1,467 @@
+//! Two-phase cross-slab execution: reserve, then commit or cancel
+//!
+//! ReserveCrossSlab prices an order across slabs without filling it: each
+//! slab locks the maker slices the split would match and reports worst
+//! price, VWAP and maximum charge. CommitCrossSlab fills every hold at its
+//! captured maker prices and applies the receipts exactly like
+//! ExecuteCrossSlab, followed by the same margin check. CancelCrossSlab
+//! releases holds that will not be committed. Each hold is bound to the
+//! reserving user's pubkey as its commitment, so only that user can
+//! commit or cancel it. Holds lock maker liquidity, so reserving requires
+//! initial margin for the worst-case fill and each slab keeps at most one
+//! open hold per user.
+
+use crate::instructions::{
+    apply_fill_receipt, find_slab_account, invoke_slab, read_fill_receipt, resolve_slab,
+    settle_insurance_flow, touch_portfolio, validate_quote, validate_router_authority,
+    InsuranceAccounts, SlabSplit,
+};
+use crate::state::{Portfolio, SlabRegistry, Vault};
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, instruction::AccountMeta, msg, pubkey::Pubkey};
+
+/// A slab hold to commit
+#[derive(Debug, Clone, Copy)]
+pub struct HoldLeg {
+    /// Slab account pubkey
+    pub slab_id: Pubkey,
+    /// Hold ID from the slab's reserve receipt
+    pub hold_id: u64,
+    /// Side (0 = buy, 1 = sell), must match the hold
+    pub side: u8,
+    /// Maximum quantity to accept from the hold (1e6 scale)
+    pub qty: i64,
+}
+
+/// Process reserve cross-slab instruction
+///
+/// Validates each split against the quote the client priced off (same
+/// seqno and slippage checks as ExecuteCrossSlab) and checks the user can
+/// margin every split filling in full at its limit price, then CPIs to
+/// each slab's reserve. Holds may cover less than requested, but never
+/// beyond the split's quantity or limit price.
+///
+/// # Arguments
+/// * `portfolio` - User's portfolio account
+/// * `user` - User pubkey (signer, hold commitment)
+/// * `registry` - Slab registry (slab whitelist, margin rates)
+/// * `router_authority` - Router authority PDA (for CPI signing)
+/// * `slab_accounts` - Slabs to reserve on
+/// * `receipt_accounts` - Reserve receipt accounts (one per slab)
+/// * `splits` - How to split the order across slabs
+/// * `max_slippage_bps` - Max distance of any hold from the best quote
+/// * `ttl_ms` - Hold lifetime (milliseconds, clamped by the slab)
+/// * `route_id` - Client route ID recorded on every hold
+/// * `now_ts` - Clock sysvar timestamp (mark freshness)
+///
+/// # Returns
+/// * Total maximum charge across all holds (1e6 scale)
+pub fn process_reserve_cross_slab(
+    portfolio: &mut Portfolio,
+    user: &Pubkey,
+    registry: &mut SlabRegistry,
+    router_authority: &AccountInfo,
+    slab_accounts: &[AccountInfo],
+    receipt_accounts: &[AccountInfo],
+    splits: &[SlabSplit],
+    max_slippage_bps: u64,
+    ttl_ms: u64,
+    route_id: u64,
+    now_ts: u64,
+) -> Result<u128, PercolatorError> {
+    if &portfolio.user != user {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+    if slab_accounts.len() != splits.len() || receipt_accounts.len() != splits.len() {
+        msg!("Error: Mismatched slab/receipt/split counts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    validate_tradable(registry, slab_accounts)?;
+    let authority_bump = validate_router_authority(&portfolio.router_id, router_authority)?;
+
+    // Phase 1: Validate every quote before any CPI
+    for split in splits.iter() {
+        let i = find_slab_account(slab_accounts, &split.slab_id)?;
+        validate_quote(&slab_accounts[i], split, max_slippage_bps)?;
+    }
+
+    // Holds lock the book for their TTL: require initial margin for the
+    // worst case before any CPI
+    touch_portfolio(portfolio, registry);
+    use crate::margin::refresh_margin;
+    let margin = refresh_margin(portfolio, registry, now_ts)?;
+    let required = margin.total_im.saturating_add(reserve_margin(registry, splits)?);
+    if portfolio.equity < required as i128 {
+        msg!("Error: Insufficient margin to reserve");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    // Phase 2: Reserve on each slab and check the holds
+    let mut total_max_charge: u128 = 0;
+    for split in splits.iter() {
+        let i = find_slab_account(slab_accounts, &split.slab_id)?;
+        let slab_account = &slab_accounts[i];
+        let receipt_account = &receipt_accounts[i];
+
+        // Layout: discriminator (1) + expected_seqno (4) + side (1) + qty (8)
+        // + limit_px (8) + ttl_ms (8) + route_id (8) + commitment (32)
+        let mut instruction_data = [0u8; 70];
+        instruction_data[0] = 6; // Reserve discriminator
+        instruction_data[1..5].copy_from_slice(&split.expected_seqno.to_le_bytes());
+        instruction_data[5] = split.side;
+        instruction_data[6..14].copy_from_slice(&split.qty.to_le_bytes());
+        instruction_data[14..22].copy_from_slice(&split.limit_px.to_le_bytes());
+        instruction_data[22..30].copy_from_slice(&ttl_ms.to_le_bytes());
+        instruction_data[30..38].copy_from_slice(&route_id.to_le_bytes());
+        instruction_data[38..70].copy_from_slice(user);
+
+        let account_metas = [
+            AccountMeta::writable(slab_account.key()),
+            AccountMeta::writable(receipt_account.key()),
+            AccountMeta::writable_signer(router_authority.key()),
+        ];
+        invoke_slab(
+            slab_account,
+            &account_metas,
+            &instruction_data,
+            &[slab_account, receipt_account, router_authority],
+            authority_bump,
+        )?;
+
+        if receipt_account.owner() != slab_account.owner() {
+            msg!("Error: Receipt not owned by slab program");
+            return Err(PercolatorError::InvalidAccount);
+        }
+        let receipt = unsafe { *borrow_account_data::<ReserveReceipt>(receipt_account)? };
+        validate_hold(&receipt, split)?;
+
+        total_max_charge = total_max_charge.saturating_add(receipt.max_charge.unsigned_abs() as u128);
+    }
+
+    msg!("ReserveCrossSlab completed successfully");
+    Ok(total_max_charge)
+}
+
+/// Process commit cross-slab instruction
+///
+/// Commits each hold on its slab and applies the fill receipts like
+/// ExecuteCrossSlab: exposures, fees, LP credit and insurance accrual,
+/// then an initial margin check on the resulting net exposure.
+///
+/// # Arguments
+/// * `portfolio` - User's portfolio account
+/// * `user` - User pubkey (signer, hold commitment)
+/// * `vault` - Collateral vault
+/// * `registry` - Slab registry with insurance state
+/// * `router_authority` - Router authority PDA (for CPI signing)
+/// * `slab_accounts` - Slabs holding the reservations
+/// * `receipt_accounts` - Fill receipt accounts (one per slab)
+/// * `lp_portfolio_accounts` - Portfolios of each slab's LP owner (one per slab, fee credit)
+/// * `legs` - Holds to commit
+/// * `insurance` - Vault token accounts the insurance accrual moves between
+/// * `now_ts` - Clock sysvar timestamp (mark freshness)
+///
+/// # Returns
+/// * Total filled notional across all slabs (1e6 scale)
+pub fn process_commit_cross_slab(
+    portfolio: &mut Portfolio,
+    user: &Pubkey,
+    vault: &mut Vault,
+    registry: &mut SlabRegistry,
+    router_authority: &AccountInfo,
+    slab_accounts: &[AccountInfo],
+    receipt_accounts: &[AccountInfo],
+    lp_portfolio_accounts: &[AccountInfo],
+    legs: &[HoldLeg],
+    insurance: &InsuranceAccounts,
+    now_ts: u64,
+) -> Result<u128, PercolatorError> {
+    if &portfolio.user != user {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    // Apply PnL vesting, haircut catchup and funding on user touch
+    touch_portfolio(portfolio, registry);
+
+    if slab_accounts.len() != legs.len()
+        || receipt_accounts.len() != legs.len()
+        || lp_portfolio_accounts.len() != legs.len()
+    {
+        msg!("Error: Mismatched slab/receipt/LP portfolio/leg counts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    validate_tradable(registry, slab_accounts)?;
+    let authority_bump = validate_router_authority(&portfolio.router_id, router_authority)?;
+
+    let insurance_before = registry.insurance_state.vault_balance;
+    let mut total_notional: u128 = 0;
+
+    for leg in legs.iter() {
+        let i = find_slab_account(slab_accounts, &leg.slab_id)?;
+        let slab_account = &slab_accounts[i];
+        let receipt_account = &receipt_accounts[i];
+
+        let (slab_idx, instrument_idx) = resolve_slab(registry, slab_account)?;
+        // The slab stamps the receipt with its seqno at commit start
+        let seqno = unsafe { borrow_account_data::<SlabHeader>(slab_account)? }.seqno;
+
+        // Layout: discriminator (1) + hold_id (8) + side (1) + commitment (32)
+        let mut instruction_data = [0u8; 42];
+        instruction_data[0] = 7; // Commit discriminator
+        instruction_data[1..9].copy_from_slice(&leg.hold_id.to_le_bytes());
+        instruction_data[9] = leg.side;
+        instruction_data[10..42].copy_from_slice(user);
+
+        let account_metas = [
+            AccountMeta::writable(slab_account.key()),
+            AccountMeta::writable(receipt_account.key()),
+            AccountMeta::writable_signer(router_authority.key()),
+        ];
+        invoke_slab(
+            slab_account,
+            &account_metas,
+            &instruction_data,
+            &[slab_account, receipt_account, router_authority],
+            authority_bump,
+        )?;
+
+        let receipt = read_fill_receipt(slab_account, receipt_account, seqno)?;
+        let notional = apply_fill_receipt(
+            portfolio,
+            registry,
+            slab_account,
+            &lp_portfolio_accounts[i],
+            slab_idx,
+            instrument_idx,
+            leg.side,
+            leg.qty,
+            &receipt,
+        )?;
+        total_notional = total_notional.saturating_add(notional);
+    }
+
+    // Snapshot funding for exposures opened by these fills
+    use crate::instructions::settle_portfolio_funding;
+    settle_portfolio_funding(portfolio, registry);
+
+    // Same margin engine and check as ExecuteCrossSlab
+    use crate::margin::refresh_margin;
+    refresh_margin(portfolio, registry, now_ts)?;
+    if !portfolio.has_sufficient_margin_venue_aware() {
+        msg!("Error: Insufficient margin");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    // Move the insurance share of the fees into the insurance vault
+    settle_insurance_flow(insurance_before, registry, vault, insurance)?;
+
+    msg!("CommitCrossSlab completed successfully");
+    Ok(total_notional)
+}
+
+/// Process cancel cross-slab instruction
+///
+/// Releases the user's holds; holds already committed or expired are
+/// skipped by the slab.
+///
+/// # Arguments
+/// * `portfolio` - User's portfolio account
+/// * `user` - User pubkey (signer, hold commitment)
+/// * `router_authority` - Router authority PDA (for CPI signing)
+/// * `slab_accounts` - Slabs holding the reservations
+/// * `hold_ids` - Hold to cancel on each slab (same order as `slab_accounts`)
+pub fn process_cancel_cross_slab(
+    portfolio: &Portfolio,
+    user: &Pubkey,
+    router_authority: &AccountInfo,
+    slab_accounts: &[AccountInfo],
+    hold_ids: &[u64],
+) -> Result<(), PercolatorError> {
+    if &portfolio.user != user {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+    if slab_accounts.len() != hold_ids.len() {
+        msg!("Error: Mismatched slab/hold counts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+    let authority_bump = validate_router_authority(&portfolio.router_id, router_authority)?;
+
+    for (slab_account, hold_id) in slab_accounts.iter().zip(hold_ids.iter()) {
+        // Layout: discriminator (1) + hold_id (8) + commitment (32)
+        let mut instruction_data = [0u8; 41];
+        instruction_data[0] = 8; // Cancel discriminator
+        instruction_data[1..9].copy_from_slice(&hold_id.to_le_bytes());
+        instruction_data[9..41].copy_from_slice(user);
+
+        let account_metas = [
+            AccountMeta::writable(slab_account.key()),
+            AccountMeta::writable_signer(router_authority.key()),
+        ];
+        invoke_slab(
+            slab_account,
+            &account_metas,
+            &instruction_data,
+            &[slab_account, router_authority],
+            authority_bump,
+        )?;
+    }
+
+    msg!("CancelCrossSlab completed successfully");
+    Ok(())
+}
+
+/// Verify every slab is registered, active and not paused
+fn validate_tradable(
+    registry: &SlabRegistry,
+    slab_accounts: &[AccountInfo],
+) -> Result<(), PercolatorError> {
+    for slab_account in slab_accounts.iter() {
+        let (_, entry) = registry.find_slab(slab_account.key()).ok_or_else(|| {
+            msg!("Error: Slab not registered or inactive");
+            PercolatorError::SlabNotRegistered
+        })?;
+        if entry.paused {
+            msg!("Error: Slab is paused");
+            return Err(PercolatorError::SlabPaused);
+        }
+    }
+    Ok(())
+}
+
+/// Worst-case margin a reservation can consume
+///
+/// Each split is charged initial margin on its full quantity at its limit
+/// price (a hold never exceeds either), without netting against existing
+/// exposure, plus the taker fee at the slab's registered cap.
+///
+/// # Returns
+/// * Extra margin required on top of the portfolio's current IM (1e6 scale)
+fn reserve_margin(registry: &SlabRegistry, splits: &[SlabSplit]) -> Result<u128, PercolatorError> {
+    const BPS: u128 = 10_000;
+    const SCALE: u128 = 1_000_000;
+
+    let mut required: u128 = 0;
+    for split in splits.iter() {
+        let (_, entry) = registry.find_slab(&split.slab_id).ok_or_else(|| {
+            msg!("Error: Slab not registered or inactive");
+            PercolatorError::SlabNotRegistered
+        })?;
+        let imr = if entry.imr > 0 { entry.imr } else { registry.imr };
+
+        let notional =
+            (split.qty.unsigned_abs() as u128).saturating_mul(split.limit_px.unsigned_abs() as u128) / SCALE;
+        let im = notional.saturating_mul(imr as u128) / BPS;
+        let fee = notional.saturating_mul(entry.taker_fee_cap as u128) / BPS;
+        required = required.saturating_add(im).saturating_add(fee);
+    }
+    Ok(required)
+}
+
+/// Validate a reserve receipt against the split that produced it
+///
+/// The hold must be written at the quoted seqno, non-empty, no larger
+/// than the split and no worse than its limit price.
+fn validate_hold(receipt: &ReserveReceipt, split: &SlabSplit) -> Result<(), PercolatorError> {
+    if !receipt.is_used() {
+        msg!("Error: Reserve receipt was not written");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if receipt.seqno_reserved != split.expected_seqno {
+        msg!("Error: Reserve receipt seqno mismatch");
+        return Err(PercolatorError::SeqnoMismatch);
+    }
+    if receipt.reserved_qty <= 0 || receipt.reserved_qty > split.qty.abs() {
+        msg!("Error: Reserved quantity out of range");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+
+    let beyond_limit = if split.side == 0 {
+        receipt.worst_px > split.limit_px
+    } else {
+        receipt.worst_px < split.limit_px
+    };
+    if receipt.worst_px <= 0 || beyond_limit {
+        msg!("Error: Reserved price beyond limit");
+        return Err(PercolatorError::SlippageExceeded);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: i64 = 1_000_000;
+
+    fn split(side: u8, limit_px: i64) -> SlabSplit {
+        SlabSplit {
+            slab_id: [1; 32],
+            qty: 3 * S,
+            side,
+            limit_px,
+            expected_seqno: 4,
+        }
+    }
+
+    fn receipt(seqno: u32, qty: i64, worst_px: i64) -> ReserveReceipt {
+        let mut receipt = ReserveReceipt::new();
+        let fill = HoldFill {
+            qty,
+            vwap_px: worst_px,
+            worst_px,
+            max_charge: qty * worst_px / S,
+        };
+        receipt.write(seqno, 1, fill, 60_000);
+        receipt
+    }
+
+    #[test]
+    fn test_validate_hold() {
+        assert_eq!(validate_hold(&receipt(4, 2 * S, 101 * S), &split(0, 101 * S)), Ok(()));
+        assert_eq!(validate_hold(&receipt(4, 2 * S, 99 * S), &split(1, 99 * S)), Ok(()));
+
+        assert_eq!(
+            validate_hold(&ReserveReceipt::new(), &split(0, 101 * S)),
+            Err(PercolatorError::InvalidAccount)
+        );
+        assert_eq!(
+            validate_hold(&receipt(3, 2 * S, 101 * S), &split(0, 101 * S)),
+            Err(PercolatorError::SeqnoMismatch)
+        );
+        assert_eq!(
+            validate_hold(&receipt(4, 4 * S, 101 * S), &split(0, 101 * S)),
+            Err(PercolatorError::InvalidQuantity)
+        );
+        assert_eq!(
+            validate_hold(&receipt(4, 2 * S, 102 * S), &split(0, 101 * S)),
+            Err(PercolatorError::SlippageExceeded)
+        );
+        assert_eq!(
+            validate_hold(&receipt(4, 2 * S, 98 * S), &split(1, 99 * S)),
+            Err(PercolatorError::SlippageExceeded)
+        );
+    }
+
+    #[test]
+    fn test_reserve_margin_covers_full_split_at_limit() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        // 10% IMR, 0.1% taker fee cap
+        registry
+            .register_slab([1; 32], [0; 32], Pubkey::default(), 1000, 500, 0, 10, 0, 0, 0)
+            .unwrap();
+
+        // 3 @ 101 = 303 notional: 30.3 IM + 0.303 fee
+        let required = reserve_margin(&registry, &[split(0, 101 * S)]).unwrap();
+        assert_eq!(required, 30_603_000);
+
+        // Two splits add up without netting (buy and sell both charge)
+        let required = reserve_margin(&registry, &[split(0, 101 * S), split(1, 101 * S)]).unwrap();
+        assert_eq!(required, 2 * 30_603_000);
+
+        let unknown = SlabSplit { slab_id: [2; 32], ..split(0, 101 * S) };
+        assert_eq!(reserve_margin(&registry, &[unknown]), Err(PercolatorError::SlabNotRegistered));
+    }
+}
@@ -0,0 +1,180 @@
+//! Funding rate accrual for perpetuals
+//!
+//! A permissionless crank moves each instrument's cumulative funding index
+//! by the premium of the slab's book mid over the oracle index price,
+//! capped per interval. The slab header mark is itself an oracle copy, so
+//! the book is the only independent price. Portfolios settle against the index whenever they are touched,
+//! so longs pay shorts while the mark trades above index and vice versa.
+
+use crate::state::{Portfolio, SlabRegistry};
+use percolator_common::*;
+use pinocchio::msg;
+
+/// Basis point denominator
+const BPS: i128 = 10_000;
+
+/// Funding rate for one interval (using verified math)
+///
+/// The premium `(mark - index) / index` in basis points, clamped to
+/// `±cap_bps`.
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in rate calculations.
+pub fn funding_rate_bps(mark_px: i64, index_px: i64, cap_bps: u64) -> i64 {
+    use model_safety::math::{div_i128, max_i128, min_i128, mul_i128, sub_i128};
+
+    if mark_px <= 0 || index_px <= 0 {
+        return 0;
+    }
+
+    let premium = div_i128(mul_i128(sub_i128(mark_px as i128, index_px as i128), BPS), index_px as i128);
+    let cap = cap_bps.min(i64::MAX as u64) as i128;
+    max_i128(min_i128(premium, cap), -cap) as i64
+}
+
+/// Process update_funding instruction
+///
+/// Accrues one interval of funding into the instrument's cumulative index:
+/// `index_px * rate_bps / 10_000` per unit of position. At most one accrual
+/// happens per `funding_interval_secs`; missed intervals are not back-filled.
+/// The first crank for an instrument only starts its funding clock.
+///
+/// # Arguments
+/// * `registry` - Slab registry holding the funding indices
+/// * `instrument_idx` - Registry instrument index
+/// * `mark_px` - Slab book mid price (1e6 scale)
+/// * `index_px` - Oracle index price (1e6 scale)
+/// * `now_ts` - Current unix timestamp
+///
+/// # Returns
+/// * The change applied to the cumulative funding index
+pub fn process_update_funding(
+    registry: &mut SlabRegistry,
+    instrument_idx: u16,
+    mark_px: i64,
+    index_px: i64,
+    now_ts: u64,
+) -> Result<i64, PercolatorError> {
+    use model_safety::math::{div_i128, mul_i128};
+
+    if instrument_idx >= registry.instrument_count {
+        msg!("Error: Unknown instrument");
+        return Err(PercolatorError::InvalidInstrument);
+    }
+    if mark_px <= 0 || index_px <= 0 {
+        msg!("Error: Funding requires positive mark and index prices");
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    let idx = instrument_idx as usize;
+    let last_ts = registry.instrument_funding_ts[idx];
+    if last_ts == 0 {
+        registry.instrument_funding_ts[idx] = now_ts;
+        msg!("UpdateFunding: funding clock started");
+        return Ok(0);
+    }
+    if now_ts < last_ts.saturating_add(registry.funding_interval_secs) {
+        msg!("Error: Funding interval has not elapsed");
+        return Err(PercolatorError::FundingNotDue);
+    }
+
+    let rate_bps = funding_rate_bps(mark_px, index_px, registry.funding_cap_bps);
+    let delta = div_i128(mul_i128(index_px as i128, rate_bps as i128), BPS) as i64;
+
+    registry.instrument_cum_funding[idx] = registry.instrument_cum_funding[idx].saturating_add(delta);
+    registry.instrument_funding_ts[idx] = now_ts;
+
+    emit(&Event::Funding {
+        instrument_idx,
+        rate_bps,
+        cum_funding: registry.instrument_cum_funding[idx],
+        ts: now_ts,
+    });
+
+    msg!("UpdateFunding executed successfully");
+    Ok(delta)
+}
+
+/// Settle a portfolio's accrued funding into its PnL
+///
+/// Called on every user touch before positions or balances change.
+pub(crate) fn settle_portfolio_funding(portfolio: &mut Portfolio, registry: &mut SlabRegistry) {
+    let owed = portfolio.settle_funding(&registry.instrument_cum_funding);
+    if owed != 0 {
+        let pnl_before = portfolio.pnl;
+        portfolio.book_fill(-owed, 0);
+        registry.global_haircut.track_pnl_change(pnl_before, portfolio.pnl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+
+    const S: i64 = 1_000_000;
+
+    fn registry() -> SlabRegistry {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry.find_or_add_instrument(&Pubkey::from([10; 32])).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_rate_is_capped_premium() {
+        // Mark 0.2% over index
+        assert_eq!(funding_rate_bps(1002 * S / 10, 100 * S, 50), 20);
+        // 5% under index hits the 0.5% cap
+        assert_eq!(funding_rate_bps(95 * S, 100 * S, 50), -50);
+        assert_eq!(funding_rate_bps(95 * S, 0, 50), 0);
+    }
+
+    #[test]
+    fn test_crank_respects_interval() {
+        let mut registry = registry();
+
+        // First crank only starts the clock
+        assert_eq!(process_update_funding(&mut registry, 0, 101 * S, 100 * S, 1_000), Ok(0));
+        assert_eq!(
+            process_update_funding(&mut registry, 0, 101 * S, 100 * S, 1_000 + 3_599),
+            Err(PercolatorError::FundingNotDue)
+        );
+
+        // 1% premium capped at 0.5% of $100 = $0.50 per unit
+        let delta = process_update_funding(&mut registry, 0, 101 * S, 100 * S, 1_000 + 3_600).unwrap();
+        assert_eq!(delta, S / 2);
+        assert_eq!(registry.instrument_cum_funding[0], S / 2);
+        assert_eq!(
+            process_update_funding(&mut registry, 1, 101 * S, 100 * S, 10_000),
+            Err(PercolatorError::InvalidInstrument)
+        );
+    }
+
+    #[test]
+    fn test_settlement_moves_pnl_from_longs_to_shorts() {
+        let mut registry = registry();
+        let mut long = Portfolio::new(Pubkey::default(), Pubkey::from([1; 32]), 0);
+        let mut short = Portfolio::new(Pubkey::default(), Pubkey::from([2; 32]), 0);
+        long.apply_fill(0, 0, 10 * S, 100 * S);
+        short.apply_fill(0, 0, -10 * S, 100 * S);
+
+        // Opening takes the snapshot without paying anything
+        registry.instrument_cum_funding[0] = 3 * S;
+        settle_portfolio_funding(&mut long, &mut registry);
+        settle_portfolio_funding(&mut short, &mut registry);
+        assert_eq!(long.pnl, 0);
+
+        registry.instrument_cum_funding[0] += S / 2;
+        settle_portfolio_funding(&mut long, &mut registry);
+        settle_portfolio_funding(&mut short, &mut registry);
+        assert_eq!(long.pnl, -5 * S as i128);
+        assert_eq!(long.equity, -5 * S as i128);
+        assert_eq!(short.pnl, 5 * S as i128);
+
+        // Settling twice is a no-op
+        settle_portfolio_funding(&mut long, &mut registry);
+        assert_eq!(long.pnl, -5 * S as i128);
+    }
+}
@@ -0,0 +1,43 @@
+//! Instrument mark crank
+//!
+//! A permissionless crank copies each instrument's oracle price into the
+//! registry mark cache. The oracle must be bound to a registered slab
+//! trading the instrument, so slab headers (whose mark and oracle the slab's
+//! LP controls) never feed the margin engine. Margin refreshes reject marks
+//! older than `oracle_max_age_secs`, so traders bundle this crank ahead of
+//! trades and withdrawals.
+
+use crate::instructions::read_bound_oracle;
+use crate::state::SlabRegistry;
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg};
+
+/// Process update_marks instruction
+///
+/// Each oracle is bound to one of `slab_accounts` by instrument and must be
+/// owned by that slab's registered oracle program; its reading must pass
+/// the registry's freshness and confidence guards.
+///
+/// # Arguments
+/// * `registry` - Slab registry holding the mark cache
+/// * `oracle_accounts` - Oracle price accounts to cache
+/// * `slab_accounts` - Registered slabs the oracles are bound through
+/// * `now_ts` - Clock sysvar timestamp
+///
+/// # Returns
+/// * Number of marks refreshed
+pub fn process_update_marks(
+    registry: &mut SlabRegistry,
+    oracle_accounts: &[AccountInfo],
+    slab_accounts: &[AccountInfo],
+    now_ts: u64,
+) -> Result<usize, PercolatorError> {
+    for oracle_account in oracle_accounts.iter() {
+        let (instrument_idx, price, price_ts) =
+            read_bound_oracle(oracle_account, registry, slab_accounts, now_ts)?;
+        registry.set_instrument_mark(instrument_idx, price, price_ts);
+    }
+
+    msg!("UpdateMarks executed successfully");
+    Ok(oracle_accounts.len())
+}
@@ -1,24 +1,334 @@
 //! Withdraw instruction - withdraw collateral from vault
 
-use crate::state::Vault;
+use crate::pda::VAULT_SEED;
+use crate::state::{Portfolio, SlabRegistry, Vault};
+use crate::token::{transfer_signed, validate_token_program};
 use percolator_common::*;
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    msg,
+};
+
+/// Which bucket of the portfolio a withdrawal is drawn from
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WithdrawSource {
+    /// Deposited principal
+    Principal = 0,
+    /// Vested realized PnL
+    VestedPnl = 1,
+}
+
+impl WithdrawSource {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(WithdrawSource::Principal),
+            1 => Some(WithdrawSource::VestedPnl),
+            _ => None,
+        }
+    }
+}
 
 /// Process withdraw instruction
 ///
 /// Withdraws collateral from the router vault to user's token account.
-/// Ensures sufficient available (non-pledged) balance exists.
+/// Applies PnL vesting and haircut catchup, then rejects any amount that
+/// would leave free collateral negative or equity below the venue-aware
+/// total IM. Principal and vested PnL are withdrawn separately; unvested
+/// PnL is never withdrawable.
+///
+/// The request is then run through the exit buckets (principal fast lane,
+/// free PnL allowance, per-user and global rolling caps). Only the
+/// immediate part is paid out; the remainder is queued on the portfolio
+/// and can be claimed (amount = 0) or topped up once the window refills.
+/// After debiting the portfolio and the vault's available (non-pledged)
+/// balance, tokens are transferred out of the vault token account signed
+/// by the vault PDA.
+///
+/// A vault whose mint is registered as weighted collateral pays out of the
+/// portfolio's balance in that mint instead. The margin check removes the
+/// balance's weighted value rather than the raw amount, and the exit
+/// buckets (which meter quote outflow) do not apply; a queued quote
+/// withdrawal is left untouched.
+///
+/// # Arguments
+/// * `vault` - Collateral vault for the withdrawn mint
+/// * `portfolio` - User's portfolio account
+/// * `registry` - Slab registry (haircut, vesting and exit bucket state)
+/// * `vault_account` - Vault PDA account (authority of the vault token account)
+/// * `user_token_account` - Destination token account
+/// * `vault_token_account` - Source token account (must match `vault.token_account`)
+/// * `user_authority` - User signer (owner of the portfolio)
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount to withdraw (base units, 0 = claim queued withdrawal)
+/// * `source` - Principal or vested PnL (collateral mints: principal only)
 pub fn process_withdraw(
     vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    registry: &mut SlabRegistry,
+    vault_account: &AccountInfo,
+    user_token_account: &AccountInfo,
+    vault_token_account: &AccountInfo,
+    user_authority: &AccountInfo,
+    token_program: &AccountInfo,
     amount: u128,
+    source: WithdrawSource,
 ) -> Result<(), PercolatorError> {
+    // Verify user authority owns the portfolio
+    validate_signer(user_authority)?;
+    if &portfolio.user != user_authority.key() {
+        msg!("Error: Portfolio does not belong to user");
+        return Err(PercolatorError::InvalidPortfolio);
+    }
+
+    // Verify source is the vault's token account
+    if vault_token_account.key() != &vault.token_account {
+        msg!("Error: Vault token account mismatch");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    validate_token_program(token_program)?;
+
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    // Queue readiness and rolling caps are time-based: never fall back to ts 0
+    let clock = Clock::get().map_err(|_| {
+        msg!("Error: Clock sysvar unavailable");
+        PercolatorError::InvalidAccount
+    })?;
+    let (current_slot, current_ts) = (clock.slot, clock.unix_timestamp as u64);
+
+    // Weighted collateral is withdrawn from its own balance
+    let collateral_idx = registry.find_collateral(&vault.mint);
+    if collateral_idx.is_none() && vault.mint != registry.quote_mint {
+        msg!("Error: Mint is neither the quote mint nor registered collateral");
+        return Err(PercolatorError::InvalidMint);
+    }
+    if collateral_idx.is_some() && source != WithdrawSource::Principal {
+        msg!("Error: Collateral mints can only be withdrawn as principal");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // Fold any queued withdrawal into this request
+    let queued = match collateral_idx {
+        Some(_) => 0,
+        None => portfolio.queued_withdrawal,
+    };
+    if queued > 0 {
+        if current_ts < portfolio.queued_withdrawal_ready_ts {
+            msg!("Error: Queued withdrawal not yet claimable");
+            return Err(PercolatorError::WithdrawalQueued);
+        }
+        if portfolio.queued_withdrawal_source != source as u8 {
+            msg!("Error: Withdraw source does not match queued withdrawal");
+            return Err(PercolatorError::InvalidInstruction);
+        }
+    }
+    let requested = amount.saturating_add(queued);
+
     // Validate amount
-    if amount == 0 {
+    if requested == 0 {
         return Err(PercolatorError::InvalidQuantity);
     }
+    if requested > u64::MAX as u128 {
+        msg!("Error: Withdraw amount exceeds token transfer limit");
+        return Err(PercolatorError::InvalidAmount);
+    }
+
+    // Apply PnL vesting, haircut catchup and funding on user touch
+    use crate::state::on_user_touch;
+    on_user_touch(
+        portfolio.principal,
+        &mut portfolio.pnl,
+        &mut portfolio.vested_pnl,
+        &mut portfolio.last_slot,
+        &mut portfolio.pnl_index_checkpoint,
+        &registry.global_haircut,
+        &registry.pnl_vesting_params,
+        current_slot,
+    );
+    use crate::instructions::settle_portfolio_funding;
+    settle_portfolio_funding(portfolio, registry);
+
+    // Margin check: remaining equity must still cover IM (shared margin engine)
+    use crate::margin::refresh_margin;
+    refresh_margin(portfolio, registry, current_ts)?;
+    if let Some(idx) = collateral_idx {
+        return withdraw_collateral(
+            vault,
+            portfolio,
+            registry,
+            idx,
+            vault_account,
+            user_token_account,
+            vault_token_account,
+            token_program,
+            requested,
+        );
+    }
+    if !portfolio.withdrawal_keeps_margin(requested) {
+        msg!("Error: Withdrawal would breach initial margin");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    // Source balance: principal or vested PnL (unvested PnL is never withdrawable)
+    let (source_balance, free_pnl_eligible, fast_lane_principal) = match source {
+        WithdrawSource::Principal => (portfolio.principal, 0, portfolio.principal),
+        WithdrawSource::VestedPnl => (portfolio.vested_pnl, portfolio.vested_pnl, 0),
+    };
+    if source_balance < 0 || (source_balance as u128) < requested {
+        msg!("Error: Insufficient balance in withdraw source");
+        return Err(PercolatorError::InsufficientBalance);
+    }
+
+    // Rate limit through exit buckets
+    use crate::state::{plan_withdrawal, EmergencyMode};
+    let emergency = EmergencyMode {
+        active: registry.emergency_mode.is_active(current_ts),
+        ..registry.emergency_mode
+    };
+    let plan = plan_withdrawal(
+        requested as i128,
+        source_balance,
+        free_pnl_eligible,
+        fast_lane_principal,
+        portfolio.equity,
+        &mut portfolio.exit_bucket,
+        vault.balance as i128,
+        &mut registry.global_exit_bucket,
+        &registry.exit_bucket_params,
+        &registry.withdrawal_thresholds,
+        &emergency,
+        current_ts,
+    );
+
+    // Record whatever could not go out now
+    portfolio.queued_withdrawal = plan.queued as u128;
+    portfolio.queued_withdrawal_source = source as u8;
+    portfolio.queued_withdrawal_ready_ts = if plan.queued > 0 {
+        msg!("Withdrawal partially queued by exit buckets");
+        current_ts.saturating_add(plan.eta_secs)
+    } else {
+        0
+    };
+
+    let immediate = plan.immediate as u128;
+    if immediate == 0 {
+        emit(&Event::Withdraw {
+            account: portfolio.user,
+            amount: 0,
+            queued: portfolio.queued_withdrawal,
+        });
+        return Ok(());
+    }
+
+    // Debit portfolio accounting from the requested source
+    let pnl_before = portfolio.pnl;
+    match source {
+        WithdrawSource::Principal => portfolio.debit_principal(immediate),
+        WithdrawSource::VestedPnl => portfolio.debit_vested_pnl(immediate),
+    }
+    .map_err(|_| PercolatorError::InsufficientBalance)?;
+    registry.global_haircut.track_pnl_change(pnl_before, portfolio.pnl);
 
     // Attempt withdrawal
+    vault.withdraw(immediate)
+        .map_err(|_| PercolatorError::InsufficientFunds)?;
+
+    emit(&Event::Withdraw {
+        account: portfolio.user,
+        amount: immediate,
+        queued: portfolio.queued_withdrawal,
+    });
+
+    transfer_from_vault(
+        vault,
+        vault_account,
+        vault_token_account,
+        user_token_account,
+        token_program,
+        immediate,
+    )
+}
+
+/// Withdraw a weighted collateral mint from the portfolio's balance in it
+///
+/// Expects the portfolio to have just been touched and margin-refreshed.
+fn withdraw_collateral(
+    vault: &mut Vault,
+    portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    idx: usize,
+    vault_account: &AccountInfo,
+    user_token_account: &AccountInfo,
+    vault_token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u128,
+) -> Result<(), PercolatorError> {
+    use model_safety::math::{sub_i128, sub_u128};
+
+    let balance = portfolio.collateral_balances[idx];
+    if balance < amount {
+        msg!("Error: Insufficient collateral balance");
+        return Err(PercolatorError::InsufficientBalance);
+    }
+
+    // Margin check on the weighted value leaving equity
+    let entry = &registry.collaterals[idx];
+    let removed = sub_u128(entry.weighted_value(balance), entry.weighted_value(balance - amount));
+    if !portfolio.withdrawal_keeps_margin(removed) {
+        msg!("Error: Withdrawal would breach initial margin");
+        return Err(PercolatorError::PortfolioInsufficientMargin);
+    }
+
+    portfolio
+        .debit_collateral(idx, amount)
+        .map_err(|_| PercolatorError::InsufficientBalance)?;
+    portfolio.set_collateral_value(sub_i128(portfolio.collateral_value, removed as i128));
+
     vault.withdraw(amount)
         .map_err(|_| PercolatorError::InsufficientFunds)?;
 
+    emit(&Event::Withdraw {
+        account: portfolio.user,
+        amount,
+        queued: 0,
+    });
+
+    transfer_from_vault(
+        vault,
+        vault_account,
+        vault_token_account,
+        user_token_account,
+        token_program,
+        amount,
+    )
+}
+
+/// Move tokens: vault -> user (vault PDA signs)
+fn transfer_from_vault(
+    vault: &Vault,
+    vault_account: &AccountInfo,
+    vault_token_account: &AccountInfo,
+    user_token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u128,
+) -> Result<(), PercolatorError> {
+    let bump_array = [vault.bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault.mint.as_ref()),
+        Seed::from(&bump_array[..]),
+    ];
+    let signer = Signer::from(&seeds);
+
+    transfer_signed(
+        vault_token_account,
+        user_token_account,
+        vault_account,
+        token_program,
+        amount as u64,
+        signer,
+    )?;
+
     Ok(())
 }
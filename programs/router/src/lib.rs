@@ -3,6 +3,8 @@
 pub mod state;
 pub mod instructions;
 pub mod pda;
+pub mod token;
+pub mod margin;
 pub mod liquidation;
 pub mod chooser;
 
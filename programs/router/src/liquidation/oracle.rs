@@ -1,7 +1,45 @@
 //! Oracle alignment validation for liquidations
 
+use percolator_common::{PercolatorError, PriceOracle};
 use pinocchio::msg;
 
+/// Check an oracle reading is fresh and tight enough to liquidate against
+///
+/// # Arguments
+/// * `oracle` - Oracle account state (already bound to a slab)
+/// * `now_ts` - Current unix timestamp (seconds)
+/// * `max_age_secs` - Maximum accepted age of the reading
+/// * `max_conf_bps` - Maximum confidence interval as basis points of price
+///
+/// # Returns
+/// * The oracle price, or `StalePrice` if it is non-positive, too old or
+///   too uncertain
+pub fn check_oracle_price(
+    oracle: &PriceOracle,
+    now_ts: u64,
+    max_age_secs: u64,
+    max_conf_bps: u64,
+) -> Result<i64, PercolatorError> {
+    if oracle.price <= 0 || oracle.timestamp < 0 {
+        msg!("Oracle: Price not initialized");
+        return Err(PercolatorError::StalePrice);
+    }
+
+    let age = now_ts.saturating_sub(oracle.timestamp as u64);
+    if age > max_age_secs {
+        msg!("Oracle: Price is stale");
+        return Err(PercolatorError::StalePrice);
+    }
+
+    let max_conf = (oracle.price as u128 * max_conf_bps as u128) / 10_000;
+    if oracle.confidence < 0 || oracle.confidence as u128 > max_conf {
+        msg!("Oracle: Confidence interval too wide");
+        return Err(PercolatorError::StalePrice);
+    }
+
+    Ok(oracle.price)
+}
+
 /// Validate if slab mark price is aligned with oracle price
 ///
 /// Returns true if the difference between slab mark and oracle price
@@ -74,6 +112,21 @@ pub fn calculate_price_band(oracle_price: i64, band_bps: u64) -> (i64, i64) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pinocchio::pubkey::Pubkey;
+
+    #[test]
+    fn test_check_oracle_price_freshness_and_confidence() {
+        let mut oracle = PriceOracle::new(Pubkey::default(), Pubkey::default(), 100_000_000, 0);
+        oracle.update_price(100_000_000, 1_000, 500_000); // $100 +/- $0.50
+
+        assert_eq!(check_oracle_price(&oracle, 1_060, 60, 100), Ok(100_000_000));
+        assert_eq!(check_oracle_price(&oracle, 1_061, 60, 100), Err(PercolatorError::StalePrice));
+        // 0.5% confidence is wider than a 0.25% limit
+        assert_eq!(check_oracle_price(&oracle, 1_000, 60, 25), Err(PercolatorError::StalePrice));
+
+        oracle.update_price(0, 1_000, 0);
+        assert_eq!(check_oracle_price(&oracle, 1_000, 60, 100), Err(PercolatorError::StalePrice));
+    }
 
     #[test]
     fn test_oracle_alignment_within_tolerance() {
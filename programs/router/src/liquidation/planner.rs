@@ -1,7 +1,12 @@
 //! Reduce-only liquidation planner
+//!
+//! Liquidations are partial: the planner closes only as much as needed to
+//! bring equity back to maintenance margin plus `preliq_buffer`, largest
+//! risk first. Full close-out is kept for bad debt.
 
 use crate::instructions::SlabSplit;
 use crate::liquidation::oracle::{calculate_price_band, validate_oracle_alignment};
+use crate::margin::net_exposures;
 use crate::state::{Portfolio, SlabRegistry};
 use percolator_common::*;
 use pinocchio::{msg, pubkey::Pubkey};
@@ -9,6 +14,12 @@ use pinocchio::{msg, pubkey::Pubkey};
 /// Maximum splits for a single liquidation (v0 limit for stack safety)
 pub const MAX_LIQUIDATION_SPLITS: usize = 8;
 
+/// Basis point denominator
+const BPS: u128 = 10_000;
+
+/// Price/quantity scale (1e6)
+const SCALE: u128 = 1_000_000;
+
 /// Liquidation plan containing splits and expected results
 #[derive(Debug, Clone)]
 pub struct LiquidationPlan {
@@ -22,6 +33,8 @@ pub struct LiquidationPlan {
     pub band_px_low: i64,
     /// Price band upper bound (for buys)
     pub band_px_high: i64,
+    /// Whether every exposure is being closed (bad debt fallback)
+    pub full_close: bool,
 }
 
 impl LiquidationPlan {
@@ -33,11 +46,13 @@ impl LiquidationPlan {
                 qty: 0,
                 side: 0,
                 limit_px: 0,
+                expected_seqno: 0,
             }; MAX_LIQUIDATION_SPLITS],
             split_count: 0,
             expected_reduction: 0,
             band_px_low: 0,
             band_px_high: 0,
+            full_close: false,
         }
     }
 
@@ -83,12 +98,155 @@ pub struct SlabInfo {
     pub instrument_idx: u16,
     /// Mark price from slab (1e6 scale)
     pub mark_price: i64,
+    /// Current slab seqno (liquidations price off the live book)
+    pub seqno: u32,
+}
+
+/// Reduction needed to bring a portfolio back above its target health
+#[derive(Debug, Clone, Copy)]
+pub struct ReductionTarget {
+    /// Close every exposure (bad debt, or the target is out of reach)
+    pub full_close: bool,
+    /// Net quantity to reduce per instrument (1e6 scale); the sign is the
+    /// direction of the net position being reduced
+    pub qty: [i128; MAX_INSTRUMENTS],
+    /// Instrument indices, largest MM contribution first
+    pub order: [u16; MAX_INSTRUMENTS],
+}
+
+/// Equity once every exposure is closed at its cached mark (using verified math)
+///
+/// Replaces the unrealized PnL last booked into equity with each
+/// exposure's `qty * (mark - entry_px) / 1e6` at the current marks.
+fn post_close_equity(portfolio: &Portfolio, registry: &SlabRegistry) -> i128 {
+    use model_safety::math::{add_i128, mul_i128, sub_i128};
+
+    let mut pnl: i128 = 0;
+    for i in 0..portfolio.exposure_count as usize {
+        let (_, instrument_idx, qty) = portfolio.exposures[i];
+        let mark_px = registry.instrument_marks[instrument_idx as usize];
+        if qty == 0 || mark_px <= 0 {
+            continue;
+        }
+        let diff = sub_i128(mark_px as i128, portfolio.exposure_entry_px[i] as i128);
+        pnl = add_i128(pnl, mul_i128(qty as i128, diff) / SCALE as i128);
+    }
+
+    add_i128(sub_i128(portfolio.equity, portfolio.unrealized_pnl), pnl)
+}
+
+/// Size the minimum reduction that brings equity back to MM + buffer
+///
+/// Closing notional releases `mmr` of maintenance margin per unit but costs
+/// `liquidation_fee_bps` of equity in penalty, so each instrument improves
+/// health at `mmr - fee` per unit of notional closed. Instruments are
+/// reduced in order of MM contribution until the deficit to
+/// `total_mm + preliq_buffer` is covered.
+///
+/// Health is measured on post-close equity: every exposure's loss (or
+/// gain) against its entry price is realized at the cached mark, so a
+/// losing position's close is not counted as pure margin release.
+///
+/// Falls back to full close-out when post-close equity is already
+/// non-positive (bad debt) or when closing every net position would still
+/// miss the target.
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in liquidation sizing.
+pub fn size_reduction(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+) -> Result<ReductionTarget, PercolatorError> {
+    use model_safety::math::{div_u128, min_u128, mul_u128};
+
+    let nets = net_exposures(portfolio, registry)?;
+
+    // MM contribution per instrument at the cached mark
+    let mut mm = [0u128; MAX_INSTRUMENTS];
+    for (i, net) in nets.iter().enumerate() {
+        let mark_px = registry.instrument_marks[i];
+        if net.qty == 0 || mark_px <= 0 {
+            continue;
+        }
+        let notional = div_u128(mul_u128(net.qty.unsigned_abs(), mark_px as u128), SCALE);
+        mm[i] = div_u128(mul_u128(notional, net.mmr as u128), BPS);
+    }
+
+    // Largest risk first (stable insertion sort, MAX_INSTRUMENTS is small)
+    let mut order = [0u16; MAX_INSTRUMENTS];
+    for (i, slot) in order.iter_mut().enumerate() {
+        *slot = i as u16;
+    }
+    for i in 1..MAX_INSTRUMENTS {
+        let mut j = i;
+        while j > 0 && mm[order[j] as usize] > mm[order[j - 1] as usize] {
+            order.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+
+    let mut target = ReductionTarget {
+        full_close: false,
+        qty: [0; MAX_INSTRUMENTS],
+        order,
+    };
+
+    let equity = post_close_equity(portfolio, registry);
+    if equity <= 0 {
+        msg!("Planner: Bad debt, full close-out");
+        target.full_close = true;
+        return Ok(target);
+    }
+
+    let required = (portfolio.calculate_total_mm() as i128).saturating_add(registry.preliq_buffer);
+    if equity >= required {
+        return Ok(target);
+    }
+    let mut deficit = (required - equity) as u128;
+
+    for &instrument_idx in order.iter() {
+        let i = instrument_idx as usize;
+        if deficit == 0 || mm[i] == 0 {
+            break;
+        }
+
+        let rate_bps = nets[i].mmr.saturating_sub(registry.liquidation_fee_bps) as u128;
+        if rate_bps == 0 {
+            // Closing this instrument costs as much equity as it releases
+            continue;
+        }
+
+        // Smallest notional (and qty) whose released margin covers the deficit
+        let mark_px = registry.instrument_marks[i] as u128;
+        let notional_needed = mul_u128(deficit, BPS).div_ceil(rate_bps);
+        let qty_needed = mul_u128(notional_needed, SCALE).div_ceil(mark_px);
+        let reduce = min_u128(nets[i].qty.unsigned_abs(), qty_needed);
+
+        target.qty[i] = if nets[i].qty > 0 { reduce as i128 } else { -(reduce as i128) };
+
+        if reduce == qty_needed {
+            deficit = 0;
+        } else {
+            let released = div_u128(mul_u128(div_u128(mul_u128(reduce, mark_px), SCALE), rate_bps), BPS);
+            deficit = deficit.saturating_sub(released);
+        }
+    }
+
+    if deficit > 0 {
+        msg!("Planner: Target health out of reach, full close-out");
+        target.full_close = true;
+    }
+
+    Ok(target)
 }
 
 /// Plan reduce-only liquidation execution
 ///
 /// This function analyzes the portfolio's exposures and plans how to
-/// reduce them across available slabs using reduce-only orders.
+/// reduce them across available slabs using reduce-only orders, closing
+/// only the amount sized by `size_reduction`.
 ///
 /// # Arguments
 /// * `portfolio` - User's portfolio with exposures
@@ -104,12 +262,15 @@ pub struct SlabInfo {
 ///
 /// # Algorithm
 /// 1. Determine price band based on mode (pre-liq vs hard liq)
-/// 2. For each exposure in portfolio:
+/// 2. Size the reduction per instrument (full close-out for bad debt)
+/// 3. For each exposure, largest-risk instrument first:
 ///    - If qty > 0 (long), plan sell orders
 ///    - If qty < 0 (short), plan buy orders
-/// 3. Filter slabs by oracle alignment
-/// 4. Apply per-slab caps
-/// 5. Set limit prices within band
+///    - Partial mode only reduces exposures in the net direction, up to the
+///      instrument's remaining target
+/// 4. Filter slabs by oracle alignment
+/// 5. Apply per-slab caps
+/// 6. Set limit prices within band
 pub fn plan_reduce_only(
     portfolio: &Portfolio,
     registry: &SlabRegistry,
@@ -138,78 +299,96 @@ pub fn plan_reduce_only(
 
     msg!("Planner: Determined price band based on mode");
 
-    // Process each exposure in the portfolio
-    for i in 0..portfolio.exposure_count as usize {
-        let (exp_slab_idx, exp_instrument_idx, qty) = portfolio.exposures[i];
-
-        if qty == 0 {
-            continue; // Skip zero exposures
-        }
-
-        msg!("Planner: Processing portfolio exposure");
-
-        // Find oracle price for this instrument
-        let oracle_price = find_oracle_price(oracle_prices, oracle_count, exp_instrument_idx);
-        if oracle_price == 0 {
-            msg!("Planner: No oracle price available for instrument");
-            continue; // Skip if no oracle price
-        }
-
-        // Calculate price band
-        let (band_low, band_high) = calculate_price_band(oracle_price, band_bps);
-        plan.band_px_low = band_low;
-        plan.band_px_high = band_high;
+    let target = size_reduction(portfolio, registry)?;
+    plan.full_close = target.full_close;
+    let mut remaining = target.qty;
 
-        msg!("Planner: Calculated price band for liquidation");
+    // Process each exposure in the portfolio, largest-risk instrument first
+    for &instrument_idx in target.order.iter() {
+        for i in 0..portfolio.exposure_count as usize {
+            let (exp_slab_idx, exp_instrument_idx, qty) = portfolio.exposures[i];
 
-        // Determine side and limit price
-        let (side, limit_px) = if qty > 0 {
-            // Long position: need to sell (reduce-only)
-            // Use lower band as limit (willing to sell at discount)
-            (1u8, band_low) // side=1 is sell
-        } else {
-            // Short position: need to buy (reduce-only)
-            // Use upper band as limit (willing to buy at premium)
-            (0u8, band_high) // side=0 is buy
-        };
+            if qty == 0 || exp_instrument_idx != instrument_idx {
+                continue; // Skip zero exposures and other instruments
+            }
 
-        let qty_to_reduce = qty.abs();
+            // Partial mode: reduce only in the net direction, up to the target
+            let want = remaining[instrument_idx as usize];
+            let qty_to_reduce = if target.full_close {
+                qty.abs()
+            } else if want == 0 || (want > 0) != (qty > 0) {
+                continue;
+            } else {
+                want.unsigned_abs().min(qty.unsigned_abs() as u128) as i64
+            };
 
-        // Find aligned slabs for this instrument
-        for j in 0..slab_count {
-            let slab_info = &slab_infos[j];
+            msg!("Planner: Processing portfolio exposure");
 
-            // Match by slab index and instrument
-            if slab_info.slab_idx != exp_slab_idx || slab_info.instrument_idx != exp_instrument_idx {
-                continue;
+            // Find oracle price for this instrument
+            let oracle_price = find_oracle_price(oracle_prices, oracle_count, exp_instrument_idx);
+            if oracle_price == 0 {
+                msg!("Planner: No oracle price available for instrument");
+                continue; // Skip if no oracle price
             }
 
-            // Check oracle alignment
-            if !validate_oracle_alignment(
-                slab_info.mark_price,
-                oracle_price,
-                registry.oracle_tolerance_bps,
-            ) {
-                msg!("Planner: Skipping misaligned slab");
-                continue; // Skip misaligned slabs
+            // Calculate price band
+            let (band_low, band_high) = calculate_price_band(oracle_price, band_bps);
+            plan.band_px_low = band_low;
+            plan.band_px_high = band_high;
+
+            msg!("Planner: Calculated price band for liquidation");
+
+            // Determine side and limit price
+            let (side, limit_px) = if qty > 0 {
+                // Long position: need to sell (reduce-only)
+                // Use lower band as limit (willing to sell at discount)
+                (1u8, band_low) // side=1 is sell
+            } else {
+                // Short position: need to buy (reduce-only)
+                // Use upper band as limit (willing to buy at premium)
+                (0u8, band_high) // side=0 is buy
+            };
+
+            // Find aligned slabs for this instrument
+            for j in 0..slab_count {
+                let slab_info = &slab_infos[j];
+
+                // Match by slab index and instrument
+                if slab_info.slab_idx != exp_slab_idx || slab_info.instrument_idx != exp_instrument_idx {
+                    continue;
+                }
+
+                // Check oracle alignment
+                if !validate_oracle_alignment(
+                    slab_info.mark_price,
+                    oracle_price,
+                    registry.oracle_tolerance_bps,
+                ) {
+                    msg!("Planner: Skipping misaligned slab");
+                    continue; // Skip misaligned slabs
+                }
+
+                // Apply per-slab cap
+                let capped_qty = qty_to_reduce.min(registry.router_cap_per_slab as i64);
+
+                msg!("Planner: Adding split to liquidation plan");
+
+                let remaining_want = &mut remaining[instrument_idx as usize];
+                *remaining_want -= remaining_want.signum() * capped_qty as i128;
+
+                // Add split to plan
+                plan.add_split(SlabSplit {
+                    slab_id: slab_info.slab_id,
+                    qty: capped_qty,
+                    side,
+                    limit_px,
+                    expected_seqno: slab_info.seqno,
+                })?;
+
+                // For v0, we only plan one split per exposure
+                // In production, we could split across multiple slabs
+                break;
             }
-
-            // Apply per-slab cap
-            let capped_qty = qty_to_reduce.min(registry.router_cap_per_slab as i64);
-
-            msg!("Planner: Adding split to liquidation plan");
-
-            // Add split to plan
-            plan.add_split(SlabSplit {
-                slab_id: slab_info.slab_id,
-                qty: capped_qty,
-                side,
-                limit_px,
-            })?;
-
-            // For v0, we only plan one split per exposure
-            // In production, we could split across multiple slabs
-            break;
         }
     }
 
@@ -252,6 +431,7 @@ mod tests {
             qty: 100,
             side: 1,
             limit_px: 1_000_000,
+            expected_seqno: 0,
         };
 
         plan.add_split(split).unwrap();
@@ -261,6 +441,105 @@ mod tests {
         assert_eq!(plan.get_splits().len(), 1);
     }
 
+    const S: i64 = 1_000_000;
+
+    /// Two instruments on one slab at default rates (MM 2.5%, fee 0.5%):
+    /// long 100 @ $100 (MM $250) and short 100 @ $10 (MM $25)
+    fn risky_portfolio(equity: i64) -> (Portfolio, SlabRegistry) {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        registry
+            .register_slab(Pubkey::from([1; 32]), [0; 32], Pubkey::default(), 0, 0, 0, 0, 0, 0, 0)
+            .unwrap();
+        registry.find_or_add_instrument(&Pubkey::from([10; 32])).unwrap();
+        registry.find_or_add_instrument(&Pubkey::from([11; 32])).unwrap();
+        registry.set_instrument_mark(0, 100 * S, 0);
+        registry.set_instrument_mark(1, 10 * S, 0);
+
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.apply_fill(0, 0, 100 * S, 100 * S);
+        portfolio.apply_fill(0, 1, -100 * S, 10 * S);
+        portfolio.update_equity(equity as i128);
+        crate::margin::refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        (portfolio, registry)
+    }
+
+    #[test]
+    fn test_size_reduction_stops_at_target() {
+        // MM $275 + $10 buffer - $200 equity = $85 deficit; closing BTC
+        // releases 2% net of the fee, so $4,250 notional = 42.5 units
+        let (portfolio, registry) = risky_portfolio(200 * S);
+        let target = size_reduction(&portfolio, &registry).unwrap();
+
+        assert!(!target.full_close);
+        assert_eq!(target.order[0], 0);
+        assert_eq!(target.qty[0], 42_500_000);
+        assert_eq!(target.qty[1], 0);
+    }
+
+    #[test]
+    fn test_size_reduction_counts_loss_on_close() {
+        // Long entered at $102 is $200 under water at the $100 mark: $400
+        // of realized equity is only $200 once the loss is realized, so the
+        // target matches the $200 portfolio
+        let mut registry = risky_portfolio(0).1;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.apply_fill(0, 0, 100 * S, 102 * S);
+        portfolio.apply_fill(0, 1, -100 * S, 10 * S);
+        portfolio.update_equity(400 * S as i128);
+        crate::margin::refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(size_reduction(&portfolio, &registry).unwrap().qty[0], 42_500_000);
+
+        // A further $1 drop the portfolio has not been marked at yet still
+        // counts: $185 deficit at 2% of $99 = 93.43 units
+        registry.set_instrument_mark(0, 99 * S, 0);
+        assert_eq!(size_reduction(&portfolio, &registry).unwrap().qty[0], 93_434_344);
+    }
+
+    #[test]
+    fn test_size_reduction_largest_risk_first() {
+        // $215 deficit: instrument 0 closes fully ($200), then 75 units of
+        // the short releases the last $15
+        let (portfolio, registry) = risky_portfolio(70 * S);
+        let target = size_reduction(&portfolio, &registry).unwrap();
+
+        assert!(!target.full_close);
+        assert_eq!(target.qty[0], 100 * S as i128);
+        assert_eq!(target.qty[1], -75 * S as i128);
+    }
+
+    #[test]
+    fn test_size_reduction_full_close_on_bad_debt() {
+        let (portfolio, registry) = risky_portfolio(-S);
+        assert!(size_reduction(&portfolio, &registry).unwrap().full_close);
+    }
+
+    #[test]
+    fn test_size_reduction_full_close_when_unreachable() {
+        // Closing everything releases only $220 of a $225 deficit
+        let (portfolio, registry) = risky_portfolio(60 * S);
+        assert!(size_reduction(&portfolio, &registry).unwrap().full_close);
+    }
+
+    #[test]
+    fn test_plan_partial_liquidation() {
+        let (portfolio, registry) = risky_portfolio(200 * S);
+        let oracles = [
+            OraclePrice { instrument_idx: 0, price: 100 * S },
+            OraclePrice { instrument_idx: 1, price: 10 * S },
+        ];
+        let slabs = [
+            SlabInfo { slab_id: Pubkey::from([1; 32]), slab_idx: 0, instrument_idx: 1, mark_price: 10 * S, seqno: 3 },
+            SlabInfo { slab_id: Pubkey::from([1; 32]), slab_idx: 0, instrument_idx: 0, mark_price: 100 * S, seqno: 3 },
+        ];
+
+        let plan = plan_reduce_only(&portfolio, &registry, &oracles, 2, &slabs, 2, false).unwrap();
+        assert!(!plan.full_close);
+        assert_eq!(plan.split_count, 1);
+        assert_eq!(plan.splits[0].qty, 42_500_000);
+        assert_eq!(plan.splits[0].side, 1);
+        assert_eq!(plan.splits[0].expected_seqno, 3);
+    }
+
     #[test]
     fn test_find_oracle_price_found() {
         let oracles = [
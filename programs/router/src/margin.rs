@@ -0,0 +1,426 @@
+//! Portfolio margin engine
+//!
+//! Single source of truth for IM/MM, shared by trade, withdraw and
+//! liquidate so all three agree on a portfolio's requirements:
+//! - Exposures are netted per instrument across slabs
+//! - Each net position is valued at the instrument's mark price
+//!   (registry cache, refreshed only from registry-bound oracles and
+//!   rejected once older than `oracle_max_age_secs`)
+//! - Rates are the strictest per-slab `imr`/`mmr` among slabs holding the
+//!   instrument, falling back to the registry defaults
+//! - LP bucket margin is added on top (venue-aware totals)
+//! - Open exposures are marked to market into equity against their entry
+//!   VWAP, so a mark move alone can make a portfolio liquidatable
+//! - Non-quote collateral is revalued into equity at its cached oracle
+//!   price times the governance collateral weight
+
+use crate::state::{Portfolio, SlabRegistry};
+use percolator_common::{PercolatorError, MAX_INSTRUMENTS};
+use pinocchio::msg;
+
+/// Basis point denominator
+const BPS: u128 = 10_000;
+
+/// Price/quantity scale (1e6)
+const SCALE: u128 = 1_000_000;
+
+/// Margin requirement for a portfolio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarginRequirement {
+    /// Trader (principal) initial margin
+    pub im: u128,
+    /// Trader (principal) maintenance margin
+    pub mm: u128,
+    /// Total initial margin including LP buckets
+    pub total_im: u128,
+    /// Total maintenance margin including LP buckets
+    pub total_mm: u128,
+}
+
+/// Net position for one instrument across all slabs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstrumentNet {
+    /// Net quantity (1e6 scale)
+    pub qty: i128,
+    /// Strictest initial margin ratio among contributing slabs (bps)
+    pub imr: u64,
+    /// Strictest maintenance margin ratio among contributing slabs (bps)
+    pub mmr: u64,
+}
+
+/// Group a portfolio's exposures into net positions per instrument
+///
+/// # Returns
+/// * Net position and strictest rates, indexed by registry instrument index
+pub fn net_exposures(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+) -> Result<[InstrumentNet; MAX_INSTRUMENTS], PercolatorError> {
+    let mut nets = [InstrumentNet::default(); MAX_INSTRUMENTS];
+    for i in 0..portfolio.exposure_count as usize {
+        let (slab_idx, instrument_idx, qty) = portfolio.exposures[i];
+        if qty == 0 {
+            continue;
+        }
+        if instrument_idx as usize >= MAX_INSTRUMENTS {
+            msg!("Error: Exposure instrument out of range");
+            return Err(PercolatorError::InvalidInstrument);
+        }
+
+        // Per-slab rates, registry defaults when unset or slab no longer active
+        let (slab_imr, slab_mmr) = match registry.slabs.get(slab_idx as usize) {
+            Some(entry) if (slab_idx as usize) < registry.slab_count as usize && entry.active => {
+                (entry.imr, entry.mmr)
+            }
+            _ => (0, 0),
+        };
+        let imr = if slab_imr > 0 { slab_imr } else { registry.imr };
+        let mmr = if slab_mmr > 0 { slab_mmr } else { registry.mmr };
+
+        let net = &mut nets[instrument_idx as usize];
+        net.qty += qty as i128;
+        net.imr = net.imr.max(imr);
+        net.mmr = net.mmr.max(mmr);
+    }
+
+    Ok(nets)
+}
+
+/// Cached oracle mark for an instrument with open exposure
+///
+/// # Returns
+/// * The mark, or `StalePrice` if it is missing or older than
+///   `registry.oracle_max_age_secs`
+pub fn fresh_mark(registry: &SlabRegistry, instrument_idx: u16, now_ts: u64) -> Result<i64, PercolatorError> {
+    registry.fresh_mark(instrument_idx, now_ts).ok_or_else(|| {
+        msg!("Error: No fresh mark price for instrument with open exposure");
+        PercolatorError::StalePrice
+    })
+}
+
+/// Calculate trader margin on net exposure per instrument (using verified math)
+///
+/// # Returns
+/// * `(im, mm)` excluding LP buckets
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in margin calculations.
+pub fn calculate_exposure_margin(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+    now_ts: u64,
+) -> Result<(u128, u128), PercolatorError> {
+    use model_safety::math::{add_u128, div_u128, max_u128, mul_u128};
+
+    // Step 1: Group exposures by instrument
+    let nets = net_exposures(portfolio, registry)?;
+
+    // Step 2: Value each net position at mark and apply rates
+    let mut im: u128 = 0;
+    let mut mm: u128 = 0;
+    for (instrument_idx, net) in nets.iter().enumerate() {
+        if net.qty == 0 {
+            continue;
+        }
+
+        let mark_px = fresh_mark(registry, instrument_idx as u16, now_ts)?;
+
+        // notional = |qty| * px / 1e6
+        let notional = div_u128(mul_u128(net.qty.unsigned_abs(), mark_px as u128), SCALE);
+
+        let instrument_im = div_u128(mul_u128(notional, net.imr as u128), BPS);
+        let instrument_mm = div_u128(mul_u128(notional, net.mmr as u128), BPS);
+        im = add_u128(im, instrument_im);
+        mm = add_u128(mm, instrument_mm);
+    }
+
+    // IM is never below MM (guards against misconfigured rates)
+    Ok((max_u128(im, mm), mm))
+}
+
+/// Weighted quote value of a portfolio's non-quote collateral (using verified math)
+///
+/// # Returns
+/// * Sum of each balance at its cached oracle price times its weight, or
+///   `StalePrice` if a held mint has never been priced or its price is
+///   older than `oracle_max_age_secs`
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in collateral valuation.
+pub fn calculate_collateral_value(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+    now_ts: u64,
+) -> Result<u128, PercolatorError> {
+    use model_safety::math::add_u128;
+
+    let mut value: u128 = 0;
+    for (entry, &balance) in registry.collaterals[..registry.collateral_count as usize]
+        .iter()
+        .zip(portfolio.collateral_balances.iter())
+    {
+        if balance == 0 {
+            continue;
+        }
+        if entry.price <= 0 {
+            msg!("Error: No oracle price for held collateral");
+            return Err(PercolatorError::StalePrice);
+        }
+        if now_ts.saturating_sub(entry.price_ts) > registry.oracle_max_age_secs {
+            msg!("Error: Collateral price is stale");
+            return Err(PercolatorError::StalePrice);
+        }
+        value = add_u128(value, entry.weighted_value(balance));
+    }
+
+    Ok(value)
+}
+
+/// Mark-to-market PnL of a portfolio's open exposures (using verified math)
+///
+/// Sum over exposures of `qty * (mark - entry_px) / 1e6`, with each
+/// exposure valued at its instrument's cached oracle mark.
+///
+/// # Returns
+/// * Unrealized PnL, or `StalePrice` if an open instrument has no fresh mark
+///
+/// # Safety
+///
+/// Uses formally verified arithmetic from model_safety::math to prevent
+/// overflow bugs in valuation.
+pub fn calculate_unrealized_pnl(
+    portfolio: &Portfolio,
+    registry: &SlabRegistry,
+    now_ts: u64,
+) -> Result<i128, PercolatorError> {
+    use model_safety::math::{add_i128, mul_i128, sub_i128};
+
+    let mut pnl: i128 = 0;
+    for i in 0..portfolio.exposure_count as usize {
+        let (_, instrument_idx, qty) = portfolio.exposures[i];
+        if qty == 0 {
+            continue;
+        }
+
+        let mark_px = fresh_mark(registry, instrument_idx, now_ts)?;
+
+        let diff = sub_i128(mark_px as i128, portfolio.exposure_entry_px[i] as i128);
+        pnl = add_i128(pnl, mul_i128(qty as i128, diff) / SCALE as i128);
+    }
+
+    Ok(pnl)
+}
+
+/// Recompute and store a portfolio's margin requirements
+///
+/// Revalues non-quote collateral and open exposures into equity, updates `portfolio.im`/`mm`
+/// (trader margin, which also refreshes free collateral) and returns the
+/// venue-aware totals including LP buckets. This is the one margin path
+/// used by trade, withdraw and liquidate; `now_ts` (Clock sysvar) bounds
+/// the age of the marks it values exposures at.
+pub fn refresh_margin(
+    portfolio: &mut Portfolio,
+    registry: &SlabRegistry,
+    now_ts: u64,
+) -> Result<MarginRequirement, PercolatorError> {
+    let collateral_value = calculate_collateral_value(portfolio, registry, now_ts)?;
+    portfolio.set_collateral_value(collateral_value as i128);
+    let unrealized_pnl = calculate_unrealized_pnl(portfolio, registry, now_ts)?;
+    portfolio.set_unrealized_pnl(unrealized_pnl);
+
+    let (im, mm) = calculate_exposure_margin(portfolio, registry, now_ts)?;
+    portfolio.update_margin(im, mm);
+
+    Ok(MarginRequirement {
+        im,
+        mm,
+        total_im: portfolio.calculate_total_im(),
+        total_mm: portfolio.calculate_total_mm(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{LpBucket, VenueId};
+    use pinocchio::pubkey::Pubkey;
+
+    const SCALE_I64: i64 = 1_000_000;
+
+    fn registry_with_slabs() -> SlabRegistry {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+        // Slab 0: 10% IMR / 5% MMR
+        registry
+            .register_slab(Pubkey::from([1; 32]), [0; 32], Pubkey::default(), 1000, 500, 0, 0, 0, 0, 0)
+            .unwrap();
+        // Slab 1: rates unset, uses registry defaults (5% / 2.5%)
+        registry
+            .register_slab(Pubkey::from([2; 32]), [0; 32], Pubkey::default(), 0, 0, 0, 0, 0, 0, 0)
+            .unwrap();
+        registry.find_or_add_instrument(&Pubkey::from([10; 32])).unwrap();
+        registry.find_or_add_instrument(&Pubkey::from([11; 32])).unwrap();
+        registry.set_instrument_mark(0, 50_000 * SCALE_I64, 0);
+        registry.set_instrument_mark(1, 3_000 * SCALE_I64, 0);
+        registry
+    }
+
+    #[test]
+    fn test_nets_across_slabs_per_instrument() {
+        let registry = registry_with_slabs();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // +10 / -10 of instrument 0 on two slabs nets to zero
+        portfolio.update_exposure(0, 0, 10 * SCALE_I64);
+        portfolio.update_exposure(1, 0, -10 * SCALE_I64);
+
+        let (im, mm) = calculate_exposure_margin(&portfolio, &registry, 0).unwrap();
+        assert_eq!(im, 0);
+        assert_eq!(mm, 0);
+    }
+
+    #[test]
+    fn test_instruments_do_not_net_against_each_other() {
+        let registry = registry_with_slabs();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // +1 BTC on slab 1 (defaults 5%/2.5%), -10 ETH on slab 1
+        portfolio.update_exposure(1, 0, SCALE_I64);
+        portfolio.update_exposure(1, 1, -10 * SCALE_I64);
+
+        let (im, mm) = calculate_exposure_margin(&portfolio, &registry, 0).unwrap();
+        // BTC: $50k * 5% = $2.5k; ETH: $30k * 5% = $1.5k
+        assert_eq!(im, 4_000 * SCALE_I64 as u128);
+        assert_eq!(mm, 2_000 * SCALE_I64 as u128);
+    }
+
+    #[test]
+    fn test_strictest_slab_rate_applies() {
+        let registry = registry_with_slabs();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // Net +1 BTC held across slab 0 (10%) and slab 1 (5%)
+        portfolio.update_exposure(0, 0, 3 * SCALE_I64);
+        portfolio.update_exposure(1, 0, -2 * SCALE_I64);
+
+        let (im, mm) = calculate_exposure_margin(&portfolio, &registry, 0).unwrap();
+        assert_eq!(im, 5_000 * SCALE_I64 as u128);
+        assert_eq!(mm, 2_500 * SCALE_I64 as u128);
+    }
+
+    #[test]
+    fn test_missing_mark_rejected() {
+        let mut registry = registry_with_slabs();
+        registry.instrument_marks[1] = 0;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.update_exposure(0, 1, SCALE_I64);
+
+        assert_eq!(
+            calculate_exposure_margin(&portfolio, &registry, 0),
+            Err(PercolatorError::StalePrice)
+        );
+    }
+
+    #[test]
+    fn test_stale_mark_rejected() {
+        let mut registry = registry_with_slabs();
+        registry.set_instrument_mark(0, 50_000 * SCALE_I64, 1_000);
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.apply_fill(0, 0, SCALE_I64, 50_000 * SCALE_I64);
+
+        // Registry default max age is 60s
+        assert!(refresh_margin(&mut portfolio, &registry, 1_060).is_ok());
+        assert_eq!(
+            refresh_margin(&mut portfolio, &registry, 1_061),
+            Err(PercolatorError::StalePrice)
+        );
+    }
+
+    #[test]
+    fn test_refresh_includes_lp_buckets() {
+        let registry = registry_with_slabs();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(10_000 * SCALE_I64 as u128);
+        portfolio.apply_fill(0, 0, SCALE_I64, 50_000 * SCALE_I64);
+
+        let mut bucket = LpBucket::new_slab(VenueId::new_slab(Pubkey::from([1; 32])));
+        bucket.im = 1_000;
+        bucket.mm = 500;
+        portfolio.add_lp_bucket(bucket).unwrap();
+
+        let req = refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(req.im, 5_000 * SCALE_I64 as u128);
+        assert_eq!(req.total_im, req.im + 1_000);
+        assert_eq!(req.total_mm, req.mm + 500);
+        assert_eq!(portfolio.im, req.im);
+        assert_eq!(portfolio.free_collateral, 5_000 * SCALE_I64 as i128);
+    }
+
+    #[test]
+    fn test_mark_move_alone_triggers_liquidation() {
+        let mut registry = registry_with_slabs();
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(2_000 * SCALE_I64 as u128);
+
+        // Long 1 BTC at $50k on slab 1: MM $1,250, no PnL yet
+        portfolio.apply_fill(1, 0, SCALE_I64, 50_000 * SCALE_I64);
+        let req = refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(req.total_mm, 1_250 * SCALE_I64 as u128);
+        assert_eq!(portfolio.equity, 2_000 * SCALE_I64 as i128);
+        assert!(portfolio.is_above_maintenance_venue_aware());
+
+        // A $1k drop is a $1k unrealized loss: equity $1,000 < MM $1,225
+        registry.set_instrument_mark(0, 49_000 * SCALE_I64, 0);
+        let req = refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(portfolio.unrealized_pnl, -1_000 * SCALE_I64 as i128);
+        assert_eq!(portfolio.equity, 1_000 * SCALE_I64 as i128);
+        assert_eq!(req.total_mm, 1_225 * SCALE_I64 as u128);
+        assert!(!portfolio.is_above_maintenance_venue_aware());
+        assert!(!portfolio.withdrawal_keeps_margin(1));
+
+        // Marking back replaces, rather than accumulates, the unrealized PnL
+        registry.set_instrument_mark(0, 50_000 * SCALE_I64, 0);
+        refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(portfolio.equity, 2_000 * SCALE_I64 as i128);
+        assert_eq!(portfolio.principal, 2_000 * SCALE_I64 as i128);
+    }
+
+    #[test]
+    fn test_refresh_revalues_weighted_collateral() {
+        let mut registry = registry_with_slabs();
+        let sol = Pubkey::from([20; 32]);
+        let idx = registry.set_collateral(sol, Pubkey::from([21; 32]), 8_000, 9).unwrap();
+
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(1_000 * SCALE_I64 as u128);
+        portfolio.credit_collateral(idx, 10_000_000_000); // 10 SOL
+
+        // Held but never priced
+        assert_eq!(
+            refresh_margin(&mut portfolio, &registry, 0),
+            Err(PercolatorError::StalePrice)
+        );
+
+        // 10 SOL at $150, 80% weight = $1,200
+        registry.collaterals[idx].price = 150 * SCALE_I64;
+        refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(portfolio.equity, 2_200 * SCALE_I64 as i128);
+
+        // A price drop flows straight into equity
+        registry.collaterals[idx].price = 100 * SCALE_I64;
+        refresh_margin(&mut portfolio, &registry, 0).unwrap();
+        assert_eq!(portfolio.equity, 1_800 * SCALE_I64 as i128);
+        assert_eq!(portfolio.principal, 1_000 * SCALE_I64 as i128);
+
+        // A price older than the oracle age limit no longer counts
+        registry.collaterals[idx].price_ts = 1_000;
+        refresh_margin(&mut portfolio, &registry, 1_060).unwrap();
+        assert_eq!(
+            refresh_margin(&mut portfolio, &registry, 1_061),
+            Err(PercolatorError::StalePrice)
+        );
+    }
+}
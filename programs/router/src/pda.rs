@@ -8,6 +8,9 @@ use pinocchio::pubkey::{find_program_address, Pubkey};
 /// Seed prefix for vault accounts (one per mint)
 pub const VAULT_SEED: &[u8] = b"vault";
 
+/// Seed prefix for insurance fund vault accounts (one per mint)
+pub const INSURANCE_SEED: &[u8] = b"insurance";
+
 /// Seed prefix for escrow accounts (per user, slab, mint)
 pub const ESCROW_SEED: &[u8] = b"escrow";
 
@@ -51,6 +54,22 @@ pub fn derive_vault_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
     find_program_address(&[VAULT_SEED, mint.as_ref()], program_id)
 }
 
+/// Derive insurance vault PDA for a given mint
+///
+/// Insurance vault holds the insurance fund's tokens for a specific mint,
+/// kept apart from user collateral so fund solvency can be checked against
+/// its own token account.
+///
+/// # Arguments
+/// * `mint` - The mint pubkey for which to derive the insurance vault
+/// * `program_id` - The router program ID
+///
+/// # Returns
+/// * `(Pubkey, u8)` - The derived PDA and its bump seed
+pub fn derive_insurance_vault_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[INSURANCE_SEED, mint.as_ref()], program_id)
+}
+
 /// Derive escrow PDA for a user on a specific slab with a specific mint
 ///
 /// Escrow holds user funds pledged to a specific slab
@@ -154,6 +173,19 @@ mod tests {
         assert_eq!(bump1, bump2);
     }
 
+    #[test]
+    #[cfg(target_os = "solana")]
+    fn test_insurance_vault_pda_differs_from_vault() {
+        let program_id = Pubkey::default();
+        let mint = Pubkey::default();
+
+        let (vault, _) = derive_vault_pda(&mint, &program_id);
+        let (insurance, _) = derive_insurance_vault_pda(&mint, &program_id);
+
+        // Insurance funds never share an account with user collateral
+        assert_ne!(vault, insurance);
+    }
+
     #[test]
     #[cfg(target_os = "solana")]
     fn test_escrow_pda_derivation() {
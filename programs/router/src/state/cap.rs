@@ -0,0 +1,127 @@
+//! Capability (Cap) account - scoped, expiring debit authorization
+
+use percolator_common::MAX_CAP_TTL_MS;
+use pinocchio::pubkey::Pubkey;
+
+/// Authorization for a slab to debit up to `amount_max` from one escrow
+/// PDA: ["cap", user, slab, mint, nonce]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Cap {
+    /// Router program ID
+    pub router_id: Pubkey,
+    /// Escrow this cap debits
+    pub escrow: Pubkey,
+    /// Scope: user
+    pub user: Pubkey,
+    /// Scope: slab allowed to debit
+    pub slab: Pubkey,
+    /// Scope: collateral mint
+    pub mint: Pubkey,
+    /// Maximum total debit
+    pub amount_max: u128,
+    /// Remaining debit allowance
+    pub remaining: u128,
+    /// Issue time (milliseconds)
+    pub issued_ms: u64,
+    /// Expiry time (milliseconds, at most `MAX_CAP_TTL_MS` after issue)
+    pub expiry_ms: u64,
+    /// Escrow nonce consumed by this cap
+    pub nonce: u64,
+    /// Bump seed
+    pub bump: u8,
+    /// Padding
+    pub _padding: [u8; 7],
+}
+
+impl Cap {
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Clamp a requested TTL to `MAX_CAP_TTL_MS`
+    pub fn capped_ttl(ttl_ms: u64) -> u64 {
+        ttl_ms.min(MAX_CAP_TTL_MS)
+    }
+
+    /// Issue the cap in-place
+    pub fn issue_in_place(
+        &mut self,
+        router_id: Pubkey,
+        escrow: Pubkey,
+        user: Pubkey,
+        slab: Pubkey,
+        mint: Pubkey,
+        amount_max: u128,
+        now_ms: u64,
+        ttl_ms: u64,
+        nonce: u64,
+        bump: u8,
+    ) {
+        self.router_id = router_id;
+        self.escrow = escrow;
+        self.user = user;
+        self.slab = slab;
+        self.mint = mint;
+        self.amount_max = amount_max;
+        self.remaining = amount_max;
+        self.issued_ms = now_ms;
+        self.expiry_ms = now_ms.saturating_add(Self::capped_ttl(ttl_ms));
+        self.nonce = nonce;
+        self.bump = bump;
+        self._padding = [0; 7];
+    }
+
+    /// Whether the cap has been issued
+    pub fn is_issued(&self) -> bool {
+        self.router_id != Pubkey::default()
+    }
+
+    /// Whether the cap has expired
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expiry_ms
+    }
+
+    /// Whether the cap covers this (user, slab, mint)
+    pub fn in_scope(&self, user: &Pubkey, slab: &Pubkey, mint: &Pubkey) -> bool {
+        &self.user == user && &self.slab == slab && &self.mint == mint
+    }
+
+    /// Consume part of the allowance
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn consume(&mut self, amount: u128) -> Result<(), ()> {
+        use model_safety::math::sub_u128;
+
+        if self.remaining < amount {
+            return Err(());
+        }
+        self.remaining = sub_u128(self.remaining, amount);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_lifecycle_and_ttl() {
+        let mut cap: Cap = unsafe { core::mem::zeroed() };
+        assert!(!cap.is_issued());
+
+        // A 10 minute request is clamped to 2 minutes
+        cap.issue_in_place([9; 32], [8; 32], [1; 32], [2; 32], [3; 32], 500, 1_000, 600_000, 0, 255);
+        assert!(cap.is_issued());
+        assert_eq!(cap.expiry_ms, 1_000 + MAX_CAP_TTL_MS);
+        assert!(!cap.is_expired(1_000 + MAX_CAP_TTL_MS - 1));
+        assert!(cap.is_expired(1_000 + MAX_CAP_TTL_MS));
+
+        assert!(cap.in_scope(&[1; 32], &[2; 32], &[3; 32]));
+        assert!(!cap.in_scope(&[1; 32], &[4; 32], &[3; 32]));
+
+        assert!(cap.consume(300).is_ok());
+        assert!(cap.consume(201).is_err());
+        assert_eq!(cap.remaining, 200);
+    }
+}
@@ -0,0 +1,78 @@
+//! Weighted collateral mints
+//!
+//! The quote mint is credited 1:1 to portfolio principal. Any other mint
+//! governance registers here is held as a separate per-mint balance and
+//! counts toward equity at its oracle price times a collateral weight
+//! (e.g. SOL at 80%).
+
+use pinocchio::pubkey::Pubkey;
+
+/// Maximum number of non-quote collateral mints
+pub const MAX_COLLATERALS: usize = 8;
+
+/// Registered collateral mint
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CollateralEntry {
+    /// Collateral mint
+    pub mint: Pubkey,
+    /// Price oracle account for the mint (oracle instrument = mint)
+    pub oracle: Pubkey,
+    /// Collateral weight (basis points of oracle value counted as equity)
+    pub weight_bps: u64,
+    /// Last accepted oracle price (quote per whole token, 1e6 scale, 0 = unknown)
+    pub price: i64,
+    /// Oracle publish time of the last accepted price (unix seconds)
+    pub price_ts: u64,
+    /// Mint decimals
+    pub decimals: u8,
+    /// Padding
+    pub _padding: [u8; 7],
+}
+
+impl CollateralEntry {
+    /// Weighted quote value of `amount` base units (using verified math)
+    ///
+    /// `amount * price / 10^decimals * weight_bps / 10_000`, rounded down.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic from model_safety::math to prevent
+    /// overflow bugs in collateral valuation.
+    pub fn weighted_value(&self, amount: u128) -> u128 {
+        use model_safety::math::{div_u128, mul_u128};
+
+        if self.price <= 0 {
+            return 0;
+        }
+        let value = div_u128(mul_u128(amount, self.price as u128), 10u128.pow(self.decimals as u32));
+        div_u128(mul_u128(value, self.weight_bps as u128), 10_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_value() {
+        // SOL: 9 decimals, $150, 80% weight
+        let sol = CollateralEntry {
+            mint: [1; 32],
+            oracle: [2; 32],
+            weight_bps: 8_000,
+            price: 150_000_000,
+            price_ts: 0,
+            decimals: 9,
+            _padding: [0; 7],
+        };
+
+        // 2 SOL = $300 -> $240 of equity
+        assert_eq!(sol.weighted_value(2_000_000_000), 240_000_000);
+        assert_eq!(sol.weighted_value(0), 0);
+
+        // Unpriced collateral counts for nothing
+        let unpriced = CollateralEntry { price: 0, ..sol };
+        assert_eq!(unpriced.weighted_value(2_000_000_000), 0);
+    }
+}
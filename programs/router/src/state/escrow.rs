@@ -0,0 +1,104 @@
+//! Escrow account for collateral pledged to a single slab
+
+use pinocchio::pubkey::Pubkey;
+
+/// Collateral a user has pledged to one slab, debitable only through Caps
+/// PDA: ["escrow", user, slab, mint]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Escrow {
+    /// Router program ID
+    pub router_id: Pubkey,
+    /// Pledging user
+    pub user: Pubkey,
+    /// Slab the funds are pledged to
+    pub slab: Pubkey,
+    /// Collateral mint
+    pub mint: Pubkey,
+    /// Pledged balance (base units, also pledged on the vault)
+    pub balance: u128,
+    /// Nonce of the next Cap issued against this escrow (anti-replay)
+    pub nonce: u64,
+    /// Latest expiry of any Cap issued against this escrow (milliseconds)
+    pub caps_expiry_ms: u64,
+    /// Bump seed
+    pub bump: u8,
+    /// Padding
+    pub _padding: [u8; 15],
+}
+
+impl Escrow {
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Initialize an empty escrow in-place
+    pub fn initialize_in_place(&mut self, router_id: Pubkey, user: Pubkey, slab: Pubkey, mint: Pubkey, bump: u8) {
+        self.router_id = router_id;
+        self.user = user;
+        self.slab = slab;
+        self.mint = mint;
+        self.balance = 0;
+        self.nonce = 0;
+        self.caps_expiry_ms = 0;
+        self.bump = bump;
+        self._padding = [0; 15];
+    }
+
+    /// Whether the escrow has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.router_id != Pubkey::default()
+    }
+
+    /// Credit pledged funds
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow.
+    pub fn credit(&mut self, amount: u128) {
+        use model_safety::math::add_u128;
+        self.balance = add_u128(self.balance, amount);
+    }
+
+    /// Debit pledged funds
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn debit(&mut self, amount: u128) -> Result<(), ()> {
+        use model_safety::math::sub_u128;
+
+        if self.balance < amount {
+            return Err(());
+        }
+        self.balance = sub_u128(self.balance, amount);
+        Ok(())
+    }
+
+    /// Take the next Cap nonce; each nonce is handed out exactly once
+    pub fn next_nonce(&mut self) -> u64 {
+        let nonce = self.nonce;
+        self.nonce = self.nonce.saturating_add(1);
+        nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escrow_credit_debit_and_nonce() {
+        let mut escrow: Escrow = unsafe { core::mem::zeroed() };
+        assert!(!escrow.is_initialized());
+        escrow.initialize_in_place([9; 32], [1; 32], [2; 32], [3; 32], 255);
+        assert!(escrow.is_initialized());
+
+        escrow.credit(1_000);
+        assert!(escrow.debit(1_001).is_err());
+        assert!(escrow.debit(400).is_ok());
+        assert_eq!(escrow.balance, 600);
+
+        assert_eq!(escrow.next_nonce(), 0);
+        assert_eq!(escrow.next_nonce(), 1);
+        assert_eq!(escrow.nonce, 2);
+    }
+}
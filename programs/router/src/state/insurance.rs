@@ -182,6 +182,46 @@ impl InsuranceState {
         (payout, uncovered)
     }
 
+    /// Accrue the insurance share of a liquidation penalty
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow.
+    pub fn accrue_liquidation_fee(&mut self, amount: u128) {
+        use model_safety::math::add_u128;
+
+        self.vault_balance = add_u128(self.vault_balance, amount);
+        self.total_fees_accrued = add_u128(self.total_fees_accrued, amount);
+    }
+
+    /// Accrue the insurance share of a fill's fees
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow.
+    pub fn accrue_trading_fee(&mut self, amount: u128) {
+        use model_safety::math::add_u128;
+
+        self.vault_balance = add_u128(self.vault_balance, amount);
+        self.total_fees_accrued = add_u128(self.total_fees_accrued, amount);
+    }
+
+    /// Record bad debt recovered by auto-deleveraging
+    ///
+    /// # Returns
+    /// * Amount actually removed from `uncovered_bad_debt`
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn recover_bad_debt(&mut self, amount: u128) -> u128 {
+        use model_safety::math::{min_u128, sub_u128};
+
+        let recovered = min_u128(amount, self.uncovered_bad_debt);
+        self.uncovered_bad_debt = sub_u128(self.uncovered_bad_debt, recovered);
+        recovered
+    }
+
     /// Manual top-up of insurance vault (governance only)
     ///
     /// # Safety
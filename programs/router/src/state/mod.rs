@@ -4,7 +4,11 @@ pub mod registry;
 pub mod lp_bucket;
 pub mod insurance;
 pub mod pnl_vesting;
+pub mod withdrawal_limits;
 pub mod model_bridge;
+pub mod collateral;
+pub mod escrow;
+pub mod cap;
 
 #[cfg(test)]
 pub mod withdrawal_limits_test;
@@ -15,4 +19,8 @@ pub use registry::*;
 pub use lp_bucket::*;
 pub use insurance::*;
 pub use pnl_vesting::*;
+pub use withdrawal_limits::*;
 pub use model_bridge::*;
+pub use collateral::*;
+pub use escrow::*;
+pub use cap::*;
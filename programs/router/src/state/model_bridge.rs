@@ -29,7 +29,8 @@
 //! - **Vesting algorithms differ**: Production uses exponential, model uses linear
 //! - **Global haircut**: Production tracks pnl_index_checkpoint, model doesn't
 //! - **Position tracking**: Production has complex exposures, model has simple position_size
-//! - **Multiple vaults**: Production has per-mint vaults, model assumes single collateral
+//! - **Multiple vaults**: Production has per-mint vaults and weighted non-quote
+//!   collateral; the model sees quote principal only (collateral value enters via equity)
 //!
 //! # Usage
 //!
@@ -15,6 +15,9 @@
 /// Fixed-point scale for global haircut index (1e9)
 pub const FP_ONE: i128 = 1_000_000_000;
 
+/// Seconds per day (daily haircut cap window)
+const SECONDS_PER_DAY: u64 = 86400;
+
 /// PnL vesting parameters (governance configurable)
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -56,6 +59,16 @@ pub struct GlobalHaircut {
     /// Haircut governance params
     pub max_haircut_per_event_bps: u16,  // e.g., 3000 = 30% max per event
     pub max_haircut_per_day_bps: u16,     // e.g., 5000 = 50% max per day
+
+    /// Outstanding positive PnL (vested + unvested) across all portfolios,
+    /// net of haircuts already applied to the index
+    pub total_positive_pnl: u128,
+
+    /// Day (unix_ts / 86400) that `haircut_today` accumulates over
+    pub haircut_day: u64,
+
+    /// Haircut removed during `haircut_day` (1e9 = 100%)
+    pub haircut_today: i128,
 }
 
 impl Default for GlobalHaircut {
@@ -66,7 +79,76 @@ impl Default for GlobalHaircut {
             cumulative_haircut: 0,
             max_haircut_per_event_bps: 3000,  // 30% max per event
             max_haircut_per_day_bps: 5000,     // 50% max per day
+            total_positive_pnl: 0,
+            haircut_day: 0,
+            haircut_today: 0,
+        }
+    }
+}
+
+impl GlobalHaircut {
+    /// Track a portfolio's PnL change in the positive PnL aggregate
+    ///
+    /// Only the positive part of PnL counts, since losses are never
+    /// haircutted. Call with the PnL before and after every realized change
+    /// (fills, penalties, PnL withdrawals). Haircut catchup on user touch is
+    /// not tracked: it was already removed from the aggregate when the
+    /// index moved.
+    pub fn track_pnl_change(&mut self, old_pnl: i128, new_pnl: i128) {
+        let old_pos = old_pnl.max(0) as u128;
+        let new_pos = new_pnl.max(0) as u128;
+        self.total_positive_pnl = self
+            .total_positive_pnl
+            .saturating_sub(old_pos)
+            .saturating_add(new_pos);
+    }
+
+    /// Socialize an uncovered loss across outstanding positive PnL (using verified math)
+    ///
+    /// Mirrors `model_safety::transitions::socialize_losses`: only winners'
+    /// PnL is haircutted, principal never is. The fraction comes from
+    /// `calculate_haircut_fraction` against `total_positive_pnl`, capped by
+    /// `max_haircut_per_event_bps` and by what remains of
+    /// `max_haircut_per_day_bps` today. Each haircut gets a new event ID.
+    ///
+    /// # Returns
+    /// * Amount of the loss actually socialized
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic from model_safety::math to prevent
+    /// overflow/underflow bugs in haircut calculations.
+    pub fn socialize_loss(&mut self, shortfall: u128, now_ts: u64) -> u128 {
+        use model_safety::math::{add_i128, div_i128, div_u128, max_i128, min_i128, mul_i128, mul_u128, sub_i128};
+
+        // Roll the daily window
+        let day = now_ts / SECONDS_PER_DAY;
+        if day != self.haircut_day {
+            self.haircut_day = day;
+            self.haircut_today = 0;
+        }
+
+        let keep = calculate_haircut_fraction(
+            shortfall,
+            self.total_positive_pnl,
+            self.max_haircut_per_event_bps,
+        );
+
+        let max_per_day = div_i128(mul_i128(self.max_haircut_per_day_bps as i128, FP_ONE), 10_000);
+        let day_remaining = max_i128(sub_i128(max_per_day, self.haircut_today), 0);
+        let removed = min_i128(sub_i128(FP_ONE, keep), day_remaining);
+        if removed <= 0 {
+            return 0;
         }
+
+        self.pnl_index = div_i128(mul_i128(self.pnl_index, sub_i128(FP_ONE, removed)), FP_ONE);
+        self.cumulative_haircut = add_i128(self.cumulative_haircut, removed);
+        self.haircut_today = add_i128(self.haircut_today, removed);
+        self.last_event_id = self.last_event_id.wrapping_add(1);
+
+        let socialized = div_u128(mul_u128(self.total_positive_pnl, removed as u128), FP_ONE as u128);
+        self.total_positive_pnl = self.total_positive_pnl.saturating_sub(socialized);
+        socialized
     }
 }
 
@@ -366,6 +448,37 @@ mod tests {
         assert_eq!(h, expected);
     }
 
+    #[test]
+    fn test_socialize_loss_uses_positive_pnl_and_caps() {
+        let mut global = GlobalHaircut::default();
+        let day = 10 * SECONDS_PER_DAY;
+
+        // Winners +1000 and +500, loser -700 (not counted)
+        global.track_pnl_change(0, 1_000);
+        global.track_pnl_change(0, 500);
+        global.track_pnl_change(0, -700);
+        global.track_pnl_change(500, 1_000);
+        assert_eq!(global.total_positive_pnl, 2_000);
+
+        // 10% of winners' PnL covers the loss
+        assert_eq!(global.socialize_loss(200, day), 200);
+        assert_eq!(global.pnl_index, FP_ONE * 9 / 10);
+        assert_eq!(global.total_positive_pnl, 1_800);
+        assert_eq!(global.last_event_id, 1);
+
+        // Per-event cap: 30% of 1800
+        assert_eq!(global.socialize_loss(1_800, day), 540);
+        assert_eq!(global.haircut_today, FP_ONE * 4 / 10);
+
+        // Per-day cap: only 10% left today
+        assert_eq!(global.socialize_loss(1_260, day), 126);
+        assert_eq!(global.socialize_loss(1_000, day + 1), 0);
+        assert_eq!(global.last_event_id, 3);
+
+        // Next day the daily budget resets
+        assert!(global.socialize_loss(1_000, day + SECONDS_PER_DAY) > 0);
+    }
+
     // ===== Warm-up Vesting Tests (W01-W03) =====
 
     #[test]
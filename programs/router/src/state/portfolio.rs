@@ -3,10 +3,14 @@
 use pinocchio::pubkey::Pubkey;
 use percolator_common::{MAX_INSTRUMENTS, MAX_SLABS};
 use crate::state::lp_bucket::{LpBucket, VenueId, MAX_LP_BUCKETS};
+use crate::state::collateral::MAX_COLLATERALS;
 
 /// Exposure key: (slab_index, instrument_index)
 pub type ExposureKey = (u16, u16);
 
+/// Funding snapshot of an exposure opened since the last funding settlement
+pub const FUNDING_UNSET: i64 = i64::MIN;
+
 /// User portfolio tracking cross-margin state
 /// PDA: ["portfolio", router_id, user]
 #[repr(C)]
@@ -39,8 +43,8 @@ pub struct Portfolio {
     pub last_liquidation_ts: u64,
     /// Cooldown period between deleveraging attempts (seconds)
     pub cooldown_seconds: u64,
-    /// Padding for alignment
-    pub _padding2: [u8; 8],
+    /// Negative equity already booked as uncovered bad debt (base units)
+    pub booked_bad_debt: u64,
 
     // PnL vesting state
     /// Principal = deposits - withdrawals (never haircutted)
@@ -56,10 +60,36 @@ pub struct Portfolio {
     /// Padding for alignment
     pub _padding4: [u8; 8],
 
+    // Withdrawal rate limiting
+    /// Per-user exit bucket (rolling-window withdrawal limiter)
+    pub exit_bucket: crate::state::withdrawal_limits::UserExitBucket,
+    /// Amount waiting for exit capacity (base units)
+    pub queued_withdrawal: u128,
+    /// Earliest time the queued withdrawal may be claimed (unix seconds)
+    pub queued_withdrawal_ready_ts: u64,
+    /// Source of the queued withdrawal (0 = principal, 1 = vested PnL)
+    pub queued_withdrawal_source: u8,
+    /// Padding for alignment
+    pub _padding5: [u8; 7],
+
+    // Non-quote collateral
+    /// Balance per registered collateral mint (base units), indexed like
+    /// `registry.collaterals`
+    pub collateral_balances: [u128; MAX_COLLATERALS],
+    /// Weighted quote value of `collateral_balances` currently counted in equity
+    pub collateral_value: i128,
+    /// Mark-to-market PnL of open exposures currently counted in equity
+    pub unrealized_pnl: i128,
+
     /// Principal exposures: (slab_idx, instrument_idx) -> position qty
     /// These are TRADER positions, separate from LP exposure
     /// Using fixed-size array for simplicity (can optimize with HashMap-like structure)
     pub exposures: [(u16, u16, i64); MAX_SLABS * MAX_INSTRUMENTS],
+    /// Entry VWAP per exposure (1e6 scale), indexed in parallel with `exposures`
+    pub exposure_entry_px: [i64; MAX_SLABS * MAX_INSTRUMENTS],
+    /// Cumulative funding index at last settlement per exposure, indexed in
+    /// parallel with `exposures` (`FUNDING_UNSET` until first settled)
+    pub exposure_funding: [i64; MAX_SLABS * MAX_INSTRUMENTS],
 
     /// LP buckets: venue-scoped liquidity provider exposure
     /// AMM LP reduced ONLY by burn_lp_shares()
@@ -94,7 +124,7 @@ impl Portfolio {
         self.health = 0;  // equity - MM = 0 - 0 = 0
         self.last_liquidation_ts = 0;
         self.cooldown_seconds = 60;  // 1 minute default cooldown
-        self._padding2 = [0; 8];
+        self.booked_bad_debt = 0;
 
         // Initialize PnL vesting state
         self.principal = 0;  // No deposits yet
@@ -104,6 +134,18 @@ impl Portfolio {
         self.pnl_index_checkpoint = crate::state::pnl_vesting::FP_ONE;  // Start at 1.0 (no haircut)
         self._padding4 = [0; 8];
 
+        // Initialize withdrawal rate limiting state
+        self.exit_bucket = crate::state::withdrawal_limits::UserExitBucket::default();
+        self.queued_withdrawal = 0;
+        self.queued_withdrawal_ready_ts = 0;
+        self.queued_withdrawal_source = 0;
+        self._padding5 = [0; 7];
+
+        // Initialize collateral balances
+        self.collateral_balances = [0; MAX_COLLATERALS];
+        self.collateral_value = 0;
+        self.unrealized_pnl = 0;
+
         // Zero out the exposures array using ptr::write_bytes (efficient and stack-safe)
         unsafe {
             core::ptr::write_bytes(
@@ -111,6 +153,16 @@ impl Portfolio {
                 0,
                 MAX_SLABS * MAX_INSTRUMENTS,
             );
+            core::ptr::write_bytes(
+                self.exposure_entry_px.as_mut_ptr(),
+                0,
+                MAX_SLABS * MAX_INSTRUMENTS,
+            );
+            core::ptr::write_bytes(
+                self.exposure_funding.as_mut_ptr(),
+                0,
+                MAX_SLABS * MAX_INSTRUMENTS,
+            );
         }
 
         // Initialize LP buckets
@@ -146,14 +198,24 @@ impl Portfolio {
             health: 0,
             last_liquidation_ts: 0,
             cooldown_seconds: 60,
-            _padding2: [0; 8],
+            booked_bad_debt: 0,
             principal: 0,
             pnl: 0,
             vested_pnl: 0,
             last_slot: 0,
             pnl_index_checkpoint: crate::state::pnl_vesting::FP_ONE,
             _padding4: [0; 8],
+            exit_bucket: crate::state::withdrawal_limits::UserExitBucket::default(),
+            queued_withdrawal: 0,
+            queued_withdrawal_ready_ts: 0,
+            queued_withdrawal_source: 0,
+            _padding5: [0; 7],
+            collateral_balances: [0; MAX_COLLATERALS],
+            collateral_value: 0,
+            unrealized_pnl: 0,
             exposures: [(0, 0, 0); MAX_SLABS * MAX_INSTRUMENTS],
+            exposure_entry_px: [0; MAX_SLABS * MAX_INSTRUMENTS],
+            exposure_funding: [0; MAX_SLABS * MAX_INSTRUMENTS],
             lp_buckets: [zero_bucket; MAX_LP_BUCKETS],
             lp_bucket_count: 0,
             _padding3: [0; 6],
@@ -178,6 +240,8 @@ impl Portfolio {
         if qty != 0 && (self.exposure_count as usize) < self.exposures.len() {
             let idx = self.exposure_count as usize;
             self.exposures[idx] = (slab_idx, instrument_idx, qty);
+            self.exposure_entry_px[idx] = 0;
+            self.exposure_funding[idx] = FUNDING_UNSET;
             self.exposure_count += 1;
         }
     }
@@ -189,8 +253,12 @@ impl Portfolio {
             let last_idx = (self.exposure_count - 1) as usize;
             if idx != last_idx {
                 self.exposures[idx] = self.exposures[last_idx];
+                self.exposure_entry_px[idx] = self.exposure_entry_px[last_idx];
+                self.exposure_funding[idx] = self.exposure_funding[last_idx];
             }
             self.exposures[last_idx] = (0, 0, 0);
+            self.exposure_entry_px[last_idx] = 0;
+            self.exposure_funding[last_idx] = 0;
             self.exposure_count -= 1;
         }
     }
@@ -205,6 +273,115 @@ impl Portfolio {
         0
     }
 
+    /// Get entry VWAP for (slab, instrument), 0 if there is no open exposure
+    pub fn get_entry_price(&self, slab_idx: u16, instrument_idx: u16) -> i64 {
+        for i in 0..self.exposure_count as usize {
+            if self.exposures[i].0 == slab_idx && self.exposures[i].1 == instrument_idx {
+                return self.exposure_entry_px[i];
+            }
+        }
+        0
+    }
+
+    /// Apply a fill to the exposure for (slab, instrument) and return realized PnL
+    ///
+    /// `fill_qty` is signed (positive = buy, negative = sell) and `fill_px` is
+    /// the fill VWAP (1e6 scale).
+    /// - Increasing a position folds the fill into the entry VWAP
+    /// - Reducing realizes `closed_qty * (fill_px - entry_px)` on the closed part;
+    ///   the entry price of the remainder is unchanged
+    /// - Flipping realizes the whole old position and opens the remainder at `fill_px`
+    ///
+    /// The caller books the returned PnL via `book_fill` together with the fee.
+    pub fn apply_fill(
+        &mut self,
+        slab_idx: u16,
+        instrument_idx: u16,
+        fill_qty: i64,
+        fill_px: i64,
+    ) -> i128 {
+        use percolator_common::math::{calculate_pnl, calculate_vwap, update_vwap};
+
+        if fill_qty == 0 || fill_px <= 0 {
+            return 0;
+        }
+
+        let current_qty = self.get_exposure(slab_idx, instrument_idx);
+        let entry_px = self.get_entry_price(slab_idx, instrument_idx);
+        let new_qty = current_qty + fill_qty;
+
+        let (realized, new_entry_px) = if current_qty == 0 || (current_qty > 0) == (fill_qty > 0) {
+            // Opening or increasing: blend the fill into the entry VWAP
+            let current_abs = current_qty.unsigned_abs();
+            let (total_qty, total_notional) = update_vwap(
+                current_abs,
+                current_abs as u128 * entry_px as u128,
+                fill_qty.unsigned_abs(),
+                fill_px as u64,
+            );
+            (0, calculate_vwap(total_notional, total_qty) as i64)
+        } else {
+            // Reducing or flipping: realize PnL on the closed quantity
+            let closed = current_qty.unsigned_abs().min(fill_qty.unsigned_abs()) as i64;
+            let closed_signed = if current_qty > 0 { closed } else { -closed };
+            let realized = calculate_pnl(closed_signed, entry_px as u64, fill_px as u64) / 1_000_000;
+
+            let new_entry_px = if new_qty == 0 {
+                0
+            } else if (new_qty > 0) == (current_qty > 0) {
+                entry_px
+            } else {
+                fill_px
+            };
+            (realized, new_entry_px)
+        };
+
+        self.update_exposure(slab_idx, instrument_idx, new_qty);
+        if new_qty != 0 {
+            for i in 0..self.exposure_count as usize {
+                if self.exposures[i].0 == slab_idx && self.exposures[i].1 == instrument_idx {
+                    self.exposure_entry_px[i] = new_entry_px;
+                    break;
+                }
+            }
+        }
+
+        realized
+    }
+
+    /// Settle funding on every exposure and snapshot the current indices
+    ///
+    /// Each exposure owes `qty * (cum_funding - snapshot) / 1e6`; exposures
+    /// opened since the last settlement only take the snapshot. Returns the
+    /// total owed (positive = paid by this portfolio), which the caller books
+    /// via `book_fill`.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic from model_safety::math to prevent
+    /// overflow bugs in funding settlement.
+    pub fn settle_funding(&mut self, cum_funding: &[i64; MAX_INSTRUMENTS]) -> i128 {
+        use model_safety::math::{add_i128, div_i128, mul_i128, sub_i128};
+
+        let mut owed: i128 = 0;
+        for i in 0..self.exposure_count as usize {
+            let (_, instrument_idx, qty) = self.exposures[i];
+            let cum = match cum_funding.get(instrument_idx as usize) {
+                Some(&cum) => cum,
+                None => continue,
+            };
+
+            let snapshot = self.exposure_funding[i];
+            if snapshot != FUNDING_UNSET {
+                let payment = div_i128(mul_i128(qty as i128, sub_i128(cum as i128, snapshot as i128)), 1_000_000);
+                owed = add_i128(owed, payment);
+            }
+            self.exposure_funding[i] = cum;
+        }
+
+        owed
+    }
+
     /// Update margin requirements (using verified math)
     ///
     /// # Safety
@@ -232,6 +409,142 @@ impl Portfolio {
         self.free_collateral = sub_i128(equity, u128_to_i128(self.im));
     }
 
+    /// Credit deposited collateral to principal and equity (using verified math)
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow.
+    pub fn credit_principal(&mut self, amount: u128) {
+        use model_safety::math::{u128_to_i128, add_i128};
+
+        let amount = u128_to_i128(amount);
+        self.principal = add_i128(self.principal, amount);
+        self.update_equity(add_i128(self.equity, amount));
+    }
+
+    /// Debit withdrawn collateral from principal and equity (using verified math)
+    ///
+    /// Fails if the amount exceeds the current principal.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn debit_principal(&mut self, amount: u128) -> Result<(), ()> {
+        use model_safety::math::{u128_to_i128, sub_i128};
+
+        let amount = u128_to_i128(amount);
+        if self.principal < amount {
+            return Err(());
+        }
+        self.principal = sub_i128(self.principal, amount);
+        self.update_equity(sub_i128(self.equity, amount));
+        Ok(())
+    }
+
+    /// Debit withdrawn vested PnL from pnl, vested_pnl and equity (using verified math)
+    ///
+    /// Fails if the amount exceeds the vested PnL. Unvested PnL can never
+    /// be withdrawn.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn debit_vested_pnl(&mut self, amount: u128) -> Result<(), ()> {
+        use model_safety::math::{u128_to_i128, sub_i128};
+
+        let amount = u128_to_i128(amount);
+        if self.vested_pnl < amount {
+            return Err(());
+        }
+        self.pnl = sub_i128(self.pnl, amount);
+        self.vested_pnl = sub_i128(self.vested_pnl, amount);
+        self.update_equity(sub_i128(self.equity, amount));
+        Ok(())
+    }
+
+    /// Credit a deposit of non-quote collateral (using verified math)
+    ///
+    /// Equity picks up the weighted value on the next margin refresh.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow.
+    pub fn credit_collateral(&mut self, idx: usize, amount: u128) {
+        use model_safety::math::add_u128;
+        self.collateral_balances[idx] = add_u128(self.collateral_balances[idx], amount);
+    }
+
+    /// Debit a withdrawal of non-quote collateral (using verified math)
+    ///
+    /// Fails if the amount exceeds the balance held in that mint.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent underflow.
+    pub fn debit_collateral(&mut self, idx: usize, amount: u128) -> Result<(), ()> {
+        use model_safety::math::sub_u128;
+
+        if self.collateral_balances[idx] < amount {
+            return Err(());
+        }
+        self.collateral_balances[idx] = sub_u128(self.collateral_balances[idx], amount);
+        Ok(())
+    }
+
+    /// Replace the weighted collateral value counted in equity (using verified math)
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow/underflow.
+    pub fn set_collateral_value(&mut self, value: i128) {
+        use model_safety::math::{add_i128, sub_i128};
+
+        let delta = sub_i128(value, self.collateral_value);
+        self.collateral_value = value;
+        self.update_equity(add_i128(self.equity, delta));
+    }
+
+    /// Replace the unrealized PnL counted in equity (using verified math)
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow/underflow.
+    pub fn set_unrealized_pnl(&mut self, value: i128) {
+        use model_safety::math::{add_i128, sub_i128};
+
+        let delta = sub_i128(value, self.unrealized_pnl);
+        self.unrealized_pnl = value;
+        self.update_equity(add_i128(self.equity, delta));
+    }
+
+    /// Book a fill's realized PnL and fee into pnl and equity (using verified math)
+    ///
+    /// The fee is always a debit; realized PnL may be either sign.
+    ///
+    /// # Safety
+    ///
+    /// Uses formally verified arithmetic to prevent overflow/underflow.
+    pub fn book_fill(&mut self, pnl_delta: i128, fee: i128) {
+        use model_safety::math::{add_i128, sub_i128};
+
+        let net = sub_i128(pnl_delta, fee);
+        self.pnl = add_i128(self.pnl, net);
+        self.update_equity(add_i128(self.equity, net));
+    }
+
+    /// Check that removing `amount` of equity keeps the portfolio margin-sufficient
+    ///
+    /// Post-withdrawal equity must cover the venue-aware total IM and leave
+    /// free collateral non-negative.
+    pub fn withdrawal_keeps_margin(&self, amount: u128) -> bool {
+        use model_safety::math::{u128_to_i128, sub_i128};
+
+        let equity_after = sub_i128(self.equity, u128_to_i128(amount));
+        let free_collateral_after = sub_i128(equity_after, u128_to_i128(self.im));
+
+        free_collateral_after >= 0 && equity_after >= u128_to_i128(self.calculate_total_im())
+    }
+
     /// Check if sufficient margin
     pub fn has_sufficient_margin(&self) -> bool {
         self.equity >= self.im as i128
@@ -380,6 +693,33 @@ mod tests {
         assert_eq!(portfolio.exposure_count, 1);
     }
 
+    #[test]
+    fn test_entry_vwap_and_realized_pnl() {
+        const S: i64 = 1_000_000;
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+
+        // Open long 1 @ 100, add 1 @ 110 -> entry 105
+        assert_eq!(portfolio.apply_fill(0, 0, S, 100 * S), 0);
+        assert_eq!(portfolio.apply_fill(0, 0, S, 110 * S), 0);
+        assert_eq!(portfolio.get_entry_price(0, 0), 105 * S);
+
+        // Reduce 1 @ 120 -> realize +15, entry unchanged
+        assert_eq!(portfolio.apply_fill(0, 0, -S, 120 * S), 15 * S as i128);
+        assert_eq!(portfolio.get_exposure(0, 0), S);
+        assert_eq!(portfolio.get_entry_price(0, 0), 105 * S);
+
+        // Flip to short 2 @ 95 -> realize -10 on the long, short opens at 95
+        assert_eq!(portfolio.apply_fill(0, 0, -3 * S, 95 * S), -10 * S as i128);
+        assert_eq!(portfolio.get_exposure(0, 0), -2 * S);
+        assert_eq!(portfolio.get_entry_price(0, 0), 95 * S);
+
+        // Entry price follows its exposure when another one is removed
+        portfolio.apply_fill(1, 0, S, 200 * S);
+        portfolio.apply_fill(0, 0, 2 * S, 90 * S);
+        assert_eq!(portfolio.exposure_count, 1);
+        assert_eq!(portfolio.get_entry_price(1, 0), 200 * S);
+    }
+
     #[test]
     fn test_portfolio_margin() {
         let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
@@ -399,6 +739,102 @@ mod tests {
         assert!(!portfolio.is_above_maintenance());
     }
 
+    #[test]
+    fn test_principal_credit_and_debit() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.update_margin(1_000, 500);
+
+        portfolio.credit_principal(10_000);
+        assert_eq!(portfolio.principal, 10_000);
+        assert_eq!(portfolio.equity, 10_000);
+        assert_eq!(portfolio.free_collateral, 9_000);
+
+        assert!(portfolio.debit_principal(4_000).is_ok());
+        assert_eq!(portfolio.principal, 6_000);
+        assert_eq!(portfolio.equity, 6_000);
+        assert_eq!(portfolio.free_collateral, 5_000);
+
+        // Cannot debit more than principal
+        assert!(portfolio.debit_principal(6_001).is_err());
+        assert_eq!(portfolio.principal, 6_000);
+        assert_eq!(portfolio.equity, 6_000);
+    }
+
+    #[test]
+    fn test_vested_pnl_debit() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.principal = 10_000;
+        portfolio.pnl = 3_000;
+        portfolio.vested_pnl = 1_000;
+        portfolio.update_equity(13_000);
+
+        // Unvested PnL cannot be withdrawn
+        assert!(portfolio.debit_vested_pnl(1_001).is_err());
+        assert_eq!(portfolio.pnl, 3_000);
+
+        assert!(portfolio.debit_vested_pnl(1_000).is_ok());
+        assert_eq!(portfolio.pnl, 2_000);
+        assert_eq!(portfolio.vested_pnl, 0);
+        assert_eq!(portfolio.principal, 10_000);
+        assert_eq!(portfolio.equity, 12_000);
+    }
+
+    #[test]
+    fn test_collateral_balance_and_value() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(10_000);
+
+        // Balances do not move equity until revalued
+        portfolio.credit_collateral(1, 500);
+        assert_eq!(portfolio.collateral_balances[1], 500);
+        assert_eq!(portfolio.equity, 10_000);
+
+        portfolio.set_collateral_value(4_000);
+        assert_eq!(portfolio.equity, 14_000);
+        portfolio.set_collateral_value(3_000);
+        assert_eq!(portfolio.equity, 13_000);
+        assert_eq!(portfolio.principal, 10_000);
+
+        assert!(portfolio.debit_collateral(1, 501).is_err());
+        assert!(portfolio.debit_collateral(1, 500).is_ok());
+        assert_eq!(portfolio.collateral_balances[1], 0);
+    }
+
+    #[test]
+    fn test_book_fill() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(10_000);
+
+        // Opening fill: fee only
+        portfolio.book_fill(0, 25);
+        assert_eq!(portfolio.pnl, -25);
+        assert_eq!(portfolio.equity, 9_975);
+        assert_eq!(portfolio.principal, 10_000);
+
+        // Closing fill: realized gain net of fee
+        portfolio.book_fill(500, 25);
+        assert_eq!(portfolio.pnl, 450);
+        assert_eq!(portfolio.equity, 10_450);
+    }
+
+    #[test]
+    fn test_withdrawal_keeps_margin() {
+        let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
+        portfolio.credit_principal(10_000);
+        portfolio.update_margin(4_000, 2_000);
+
+        assert!(portfolio.withdrawal_keeps_margin(6_000));
+        assert!(!portfolio.withdrawal_keeps_margin(6_001));
+
+        // LP bucket IM counts against withdrawable equity
+        let mut bucket = LpBucket::new_slab(VenueId::new_slab(Pubkey::from([1; 32])));
+        bucket.im = 1_000;
+        portfolio.add_lp_bucket(bucket).unwrap();
+
+        assert!(portfolio.withdrawal_keeps_margin(5_000));
+        assert!(!portfolio.withdrawal_keeps_margin(5_001));
+    }
+
     #[test]
     fn test_lp_bucket_management() {
         let mut portfolio = Portfolio::new(Pubkey::default(), Pubkey::default(), 0);
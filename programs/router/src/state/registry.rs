@@ -1,13 +1,14 @@
 //! Slab registry for governance and validation
 
 use pinocchio::pubkey::Pubkey;
-use percolator_common::MAX_SLABS;
+use percolator_common::{MAX_INSTRUMENTS, MAX_SLABS};
+use crate::state::collateral::{CollateralEntry, MAX_COLLATERALS};
 
 /// Slab registration entry
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct SlabEntry {
-    /// Slab program ID
+    /// Slab state account pubkey
     pub slab_id: Pubkey,
     /// Version hash (for upgrade validation)
     pub version_hash: [u8; 32],
@@ -27,10 +28,12 @@ pub struct SlabEntry {
     pub max_exposure: u128,
     /// Registered timestamp
     pub registered_ts: u64,
-    /// Active flag
+    /// Active flag (false once deactivated; permanent)
     pub active: bool,
+    /// Paused flag (no new trades; liquidation and funding continue)
+    pub paused: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 6],
 }
 
 /// Slab registry account
@@ -41,6 +44,8 @@ pub struct SlabRegistry {
     pub router_id: Pubkey,
     /// Governance authority (can update registry)
     pub governance: Pubkey,
+    /// Proposed governance authority (must accept to take over)
+    pub pending_governance: Pubkey,
     /// Number of registered slabs
     pub slab_count: u16,
     /// Bump seed
@@ -65,6 +70,18 @@ pub struct SlabRegistry {
     pub min_equity_to_quote: i128,
     /// Oracle price tolerance (basis points, e.g., 50 = 0.5%)
     pub oracle_tolerance_bps: u64,
+    /// Liquidation penalty (basis points of liquidated notional, e.g., 50 = 0.5%)
+    pub liquidation_fee_bps: u64,
+    /// Keeper share of the liquidation penalty (basis points, remainder to insurance)
+    pub liquidation_keeper_share_bps: u64,
+    /// Maximum oracle price age (seconds) accepted for liquidation
+    pub oracle_max_age_secs: u64,
+    /// Maximum oracle confidence interval (basis points of price)
+    pub oracle_max_conf_bps: u64,
+    /// Minimum time between funding accruals per instrument (seconds)
+    pub funding_interval_secs: u64,
+    /// Cap on the funding premium charged per interval (basis points of index)
+    pub funding_cap_bps: u64,
     /// Padding for alignment
     pub _padding2: [u8; 8],
 
@@ -80,6 +97,45 @@ pub struct SlabRegistry {
     /// Global haircut state (runtime tracking)
     pub global_haircut: crate::state::pnl_vesting::GlobalHaircut,
 
+    // Withdrawal rate limiting parameters and global state
+    /// Exit bucket parameters (configurable by governance)
+    pub exit_bucket_params: crate::state::withdrawal_limits::ExitBucketParams,
+    /// Withdrawal threshold exceptions (configurable by governance)
+    pub withdrawal_thresholds: crate::state::withdrawal_limits::WithdrawalThresholds,
+    /// Emergency mode (tightened exit caps)
+    pub emergency_mode: crate::state::withdrawal_limits::EmergencyMode,
+    /// Global exit bucket (runtime tracking)
+    pub global_exit_bucket: crate::state::withdrawal_limits::GlobalExitBucket,
+
+    // Instrument table
+    /// Number of known instruments
+    pub instrument_count: u16,
+    /// Padding for alignment
+    pub _padding3: [u8; 6],
+    /// Known instruments (SlabHeader.instrument); array index is the
+    /// instrument index used in portfolio exposure keys
+    pub instruments: [Pubkey; MAX_INSTRUMENTS],
+    /// Last known mark price per instrument (1e6 scale, 0 = unknown), read
+    /// from the instrument's registry-bound oracle
+    pub instrument_marks: [i64; MAX_INSTRUMENTS],
+    /// Oracle timestamp of each cached mark (unix seconds)
+    pub instrument_mark_ts: [u64; MAX_INSTRUMENTS],
+    /// Cumulative funding index per instrument (quote per unit, 1e6 scale);
+    /// longs pay and shorts receive as it rises
+    pub instrument_cum_funding: [i64; MAX_INSTRUMENTS],
+    /// Last funding accrual per instrument (unix seconds, 0 = never)
+    pub instrument_funding_ts: [u64; MAX_INSTRUMENTS],
+
+    // Collateral table
+    /// Quote mint, credited to principal 1:1 (set at initialization)
+    pub quote_mint: Pubkey,
+    /// Number of registered non-quote collateral mints
+    pub collateral_count: u16,
+    /// Padding for alignment
+    pub _padding4: [u8; 6],
+    /// Weighted collateral mints; array index is the portfolio balance slot
+    pub collaterals: [CollateralEntry; MAX_COLLATERALS],
+
     /// Registered slabs
     pub slabs: [SlabEntry; MAX_SLABS],
 }
@@ -94,6 +150,7 @@ impl SlabRegistry {
     pub fn initialize_in_place(&mut self, router_id: Pubkey, governance: Pubkey, bump: u8) {
         self.router_id = router_id;
         self.governance = governance;
+        self.pending_governance = Pubkey::default();
         self.slab_count = 0;
         self.bump = bump;
         self._padding = [0; 5];
@@ -107,6 +164,12 @@ impl SlabRegistry {
         self.router_cap_per_slab = 1_000_000_000;  // 1000 units max per slab
         self.min_equity_to_quote = 100_000_000;  // $100 minimum equity
         self.oracle_tolerance_bps = 50;  // 0.5% oracle tolerance
+        self.liquidation_fee_bps = 50;  // 0.5% of liquidated notional
+        self.liquidation_keeper_share_bps = 5_000;  // 50% to keeper, 50% to insurance
+        self.oracle_max_age_secs = 60;  // 1 minute
+        self.oracle_max_conf_bps = 100;  // 1% confidence interval
+        self.funding_interval_secs = 3_600;  // hourly funding
+        self.funding_cap_bps = 50;  // 0.5% of index per interval
         self._padding2 = [0; 8];
 
         // Initialize insurance with defaults
@@ -117,6 +180,33 @@ impl SlabRegistry {
         self.pnl_vesting_params = crate::state::pnl_vesting::PnlVestingParams::default();
         self.global_haircut = crate::state::pnl_vesting::GlobalHaircut::default();
 
+        // Initialize withdrawal rate limiting with defaults
+        self.exit_bucket_params = crate::state::withdrawal_limits::ExitBucketParams::default();
+        self.withdrawal_thresholds = crate::state::withdrawal_limits::WithdrawalThresholds::default();
+        self.emergency_mode = crate::state::withdrawal_limits::EmergencyMode::default();
+        self.global_exit_bucket = crate::state::withdrawal_limits::GlobalExitBucket::default();
+
+        // Initialize empty instrument table
+        self.instrument_count = 0;
+        self._padding3 = [0; 6];
+        unsafe {
+            core::ptr::write_bytes(
+                self.instruments.as_mut_ptr(),
+                0,
+                MAX_INSTRUMENTS,
+            );
+        }
+        self.instrument_marks = [0; MAX_INSTRUMENTS];
+        self.instrument_mark_ts = [0; MAX_INSTRUMENTS];
+        self.instrument_cum_funding = [0; MAX_INSTRUMENTS];
+        self.instrument_funding_ts = [0; MAX_INSTRUMENTS];
+
+        // Initialize empty collateral table
+        self.quote_mint = Pubkey::default();
+        self.collateral_count = 0;
+        self._padding4 = [0; 6];
+        self.collaterals = [CollateralEntry::default(); MAX_COLLATERALS];
+
         // Zero out the slabs array using ptr::write_bytes (efficient and stack-safe)
         unsafe {
             core::ptr::write_bytes(
@@ -134,6 +224,7 @@ impl SlabRegistry {
         Self {
             router_id,
             governance,
+            pending_governance: Pubkey::default(),
             slab_count: 0,
             bump,
             _padding: [0; 5],
@@ -145,11 +236,32 @@ impl SlabRegistry {
             router_cap_per_slab: 1_000_000_000,
             min_equity_to_quote: 100_000_000,
             oracle_tolerance_bps: 50,
+            liquidation_fee_bps: 50,
+            liquidation_keeper_share_bps: 5_000,
+            oracle_max_age_secs: 60,
+            oracle_max_conf_bps: 100,
+            funding_interval_secs: 3_600,
+            funding_cap_bps: 50,
             _padding2: [0; 8],
             insurance_params: crate::state::insurance::InsuranceParams::default(),
             insurance_state: crate::state::insurance::InsuranceState::default(),
             pnl_vesting_params: crate::state::pnl_vesting::PnlVestingParams::default(),
             global_haircut: crate::state::pnl_vesting::GlobalHaircut::default(),
+            exit_bucket_params: crate::state::withdrawal_limits::ExitBucketParams::default(),
+            withdrawal_thresholds: crate::state::withdrawal_limits::WithdrawalThresholds::default(),
+            emergency_mode: crate::state::withdrawal_limits::EmergencyMode::default(),
+            global_exit_bucket: crate::state::withdrawal_limits::GlobalExitBucket::default(),
+            instrument_count: 0,
+            _padding3: [0; 6],
+            instruments: [Pubkey::default(); MAX_INSTRUMENTS],
+            instrument_marks: [0; MAX_INSTRUMENTS],
+            instrument_mark_ts: [0; MAX_INSTRUMENTS],
+            instrument_cum_funding: [0; MAX_INSTRUMENTS],
+            instrument_funding_ts: [0; MAX_INSTRUMENTS],
+            quote_mint: Pubkey::default(),
+            collateral_count: 0,
+            _padding4: [0; 6],
+            collaterals: [CollateralEntry::default(); MAX_COLLATERALS],
             slabs: [SlabEntry {
                 slab_id: Pubkey::default(),
                 version_hash: [0; 32],
@@ -162,7 +274,8 @@ impl SlabRegistry {
                 max_exposure: 0,
                 registered_ts: 0,
                 active: false,
-                _padding: [0; 7],
+                paused: false,
+                _padding: [0; 6],
             }; MAX_SLABS],
         }
     }
@@ -198,7 +311,8 @@ impl SlabRegistry {
             max_exposure,
             registered_ts: current_ts,
             active: true,
-            _padding: [0; 7],
+            paused: false,
+            _padding: [0; 6],
         };
         self.slab_count += 1;
 
@@ -215,6 +329,97 @@ impl SlabRegistry {
         None
     }
 
+    /// Find instrument index by instrument ID
+    pub fn find_instrument(&self, instrument: &Pubkey) -> Option<u16> {
+        for i in 0..self.instrument_count as usize {
+            if &self.instruments[i] == instrument {
+                return Some(i as u16);
+            }
+        }
+        None
+    }
+
+    /// Find instrument index, adding the instrument if it is not yet known
+    pub fn find_or_add_instrument(&mut self, instrument: &Pubkey) -> Result<u16, ()> {
+        if let Some(idx) = self.find_instrument(instrument) {
+            return Ok(idx);
+        }
+
+        if (self.instrument_count as usize) >= MAX_INSTRUMENTS {
+            return Err(());
+        }
+
+        let idx = self.instrument_count;
+        self.instruments[idx as usize] = *instrument;
+        self.instrument_count += 1;
+
+        Ok(idx)
+    }
+
+    /// Record the latest mark price for an instrument and the oracle
+    /// timestamp it was published at (ignores non-positive prices)
+    pub fn set_instrument_mark(&mut self, instrument_idx: u16, mark_px: i64, mark_ts: u64) {
+        if mark_px > 0 && (instrument_idx as usize) < MAX_INSTRUMENTS {
+            self.instrument_marks[instrument_idx as usize] = mark_px;
+            self.instrument_mark_ts[instrument_idx as usize] = mark_ts;
+        }
+    }
+
+    /// Cached mark for an instrument if it is within `oracle_max_age_secs`
+    /// of `now_ts`
+    pub fn fresh_mark(&self, instrument_idx: u16, now_ts: u64) -> Option<i64> {
+        let idx = instrument_idx as usize;
+        let mark_px = *self.instrument_marks.get(idx)?;
+        if mark_px <= 0 || now_ts.saturating_sub(self.instrument_mark_ts[idx]) > self.oracle_max_age_secs {
+            return None;
+        }
+        Some(mark_px)
+    }
+
+    /// Find collateral slot by mint
+    pub fn find_collateral(&self, mint: &Pubkey) -> Option<usize> {
+        self.collaterals[..self.collateral_count as usize]
+            .iter()
+            .position(|entry| &entry.mint == mint)
+    }
+
+    /// Register a collateral mint or update its oracle, weight and decimals
+    ///
+    /// The cached price is cleared when the oracle changes.
+    pub fn set_collateral(
+        &mut self,
+        mint: Pubkey,
+        oracle: Pubkey,
+        weight_bps: u64,
+        decimals: u8,
+    ) -> Result<usize, ()> {
+        let idx = match self.find_collateral(&mint) {
+            Some(idx) => idx,
+            None => {
+                if (self.collateral_count as usize) >= MAX_COLLATERALS {
+                    return Err(());
+                }
+                self.collateral_count += 1;
+                self.collaterals[self.collateral_count as usize - 1] = CollateralEntry {
+                    mint,
+                    ..CollateralEntry::default()
+                };
+                self.collateral_count as usize - 1
+            }
+        };
+
+        let entry = &mut self.collaterals[idx];
+        if entry.oracle != oracle {
+            entry.oracle = oracle;
+            entry.price = 0;
+            entry.price_ts = 0;
+        }
+        entry.weight_bps = weight_bps;
+        entry.decimals = decimals;
+
+        Ok(idx)
+    }
+
     /// Validate slab version hash
     pub fn validate_version(&self, slab_id: &Pubkey, version_hash: &[u8; 32]) -> bool {
         if let Some((_, entry)) = self.find_slab(slab_id) {
@@ -234,6 +439,23 @@ impl SlabRegistry {
         }
     }
 
+    /// Pause or resume trading on a slab
+    pub fn set_slab_paused(&mut self, slab_id: &Pubkey, paused: bool) -> Result<(), ()> {
+        if let Some((idx, _)) = self.find_slab(slab_id) {
+            self.slabs[idx as usize].paused = paused;
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Check whether a slab was ever registered (including deactivated slabs)
+    pub fn is_registered(&self, slab_id: &Pubkey) -> bool {
+        self.slabs[..self.slab_count as usize]
+            .iter()
+            .any(|entry| &entry.slab_id == slab_id)
+    }
+
     /// Update slab risk params
     pub fn update_risk_params(&mut self, slab_id: &Pubkey, imr: u64, mmr: u64) -> Result<(), ()> {
         if let Some((idx, _)) = self.find_slab(slab_id) {
@@ -264,6 +486,40 @@ impl SlabRegistry {
         self.router_cap_per_slab = router_cap_per_slab;
         self.oracle_tolerance_bps = oracle_tolerance_bps;
     }
+
+    /// Update liquidation penalty parameters (governance only)
+    ///
+    /// Fails if the keeper share exceeds 100%.
+    pub fn update_liquidation_fee(
+        &mut self,
+        liquidation_fee_bps: u64,
+        liquidation_keeper_share_bps: u64,
+    ) -> Result<(), ()> {
+        if liquidation_keeper_share_bps > 10_000 {
+            return Err(());
+        }
+        self.liquidation_fee_bps = liquidation_fee_bps;
+        self.liquidation_keeper_share_bps = liquidation_keeper_share_bps;
+        Ok(())
+    }
+
+    /// Update oracle freshness and confidence limits (governance only)
+    pub fn update_oracle_guards(&mut self, oracle_max_age_secs: u64, oracle_max_conf_bps: u64) {
+        self.oracle_max_age_secs = oracle_max_age_secs;
+        self.oracle_max_conf_bps = oracle_max_conf_bps;
+    }
+
+    /// Update funding interval and premium cap (governance only)
+    ///
+    /// Fails if the interval is zero.
+    pub fn update_funding_params(&mut self, funding_interval_secs: u64, funding_cap_bps: u64) -> Result<(), ()> {
+        if funding_interval_secs == 0 {
+            return Err(());
+        }
+        self.funding_interval_secs = funding_interval_secs;
+        self.funding_cap_bps = funding_cap_bps;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -304,5 +560,23 @@ mod tests {
 
         registry.deactivate_slab(&slab_id).unwrap();
         assert!(registry.find_slab(&slab_id).is_none());
+        assert!(registry.is_registered(&slab_id));
+    }
+
+    #[test]
+    fn test_instrument_table() {
+        let mut registry = SlabRegistry::new(Pubkey::default(), Pubkey::default(), 0);
+
+        let btc = Pubkey::from([1; 32]);
+        let eth = Pubkey::from([2; 32]);
+
+        assert!(registry.find_instrument(&btc).is_none());
+        assert_eq!(registry.find_or_add_instrument(&btc), Ok(0));
+        assert_eq!(registry.find_or_add_instrument(&eth), Ok(1));
+
+        // Same instrument always maps to the same index
+        assert_eq!(registry.find_or_add_instrument(&btc), Ok(0));
+        assert_eq!(registry.find_instrument(&eth), Some(1));
+        assert_eq!(registry.instrument_count, 2);
     }
 }
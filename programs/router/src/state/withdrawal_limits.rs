@@ -0,0 +1,391 @@
+//! Withdrawal rate limiting via exit buckets
+//!
+//! This module implements bank-run throttling for withdrawals:
+//! - Per-user exit buckets (rolling-window rate limit on equity)
+//! - Global exit bucket (aggregate rolling-window limit on TVL)
+//! - Threshold exceptions (principal fast lane, free PnL allowance)
+//! - Emergency mode (tightened caps)
+//! - Queuing of any amount that exceeds the current allowance
+//!
+//! Amounts are in the vault's base units (1e6 scale for $1.00).
+
+/// Money scale (1e6 for $1.00)
+const SCALE: i128 = 1_000_000;
+
+/// Seconds per day (daily threshold reset)
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Exit bucket parameters (governance configurable)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ExitBucketParams {
+    /// Per-user percentage of equity per hour (basis points)
+    /// e.g., 2000 = 20% of equity per hour
+    pub user_pct_per_hour_bps: u64,
+
+    /// Per-user hard maximum per hour (in SCALE units)
+    /// e.g., 250_000 * SCALE = $250k per hour
+    pub user_hard_max_per_hour: i128,
+
+    /// Rolling window duration in seconds (typically 3600 = 1 hour)
+    pub rolling_window_secs: u64,
+
+    /// Global percentage of TVL per hour (basis points)
+    /// e.g., 500 = 5% of TVL per hour
+    pub tvl_pct_per_hour_bps: u64,
+
+    /// Global hard maximum per hour (as percentage of TVL, basis points)
+    /// e.g., 300 = 3% of TVL hard cap
+    pub global_hard_max_bps: u64,
+}
+
+impl Default for ExitBucketParams {
+    fn default() -> Self {
+        Self {
+            user_pct_per_hour_bps: 2000,  // 20%/h
+            user_hard_max_per_hour: 500_000 * SCALE,  // $500k/h (allows 20% up to $500k)
+            rolling_window_secs: 3600,  // 1 hour
+            tvl_pct_per_hour_bps: 500,  // 5%/h
+            global_hard_max_bps: 10000,  // 100% of TVL (effectively no hard max unless explicitly set)
+        }
+    }
+}
+
+/// Withdrawal threshold exceptions
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WithdrawalThresholds {
+    /// Free PnL threshold per day (no bucket charge)
+    /// e.g., 500 * SCALE = $500/day free
+    pub free_pnl_threshold_per_day: i128,
+
+    /// Principal fast lane per day (no bucket charge)
+    /// min(pct_of_principal, hard_max)
+    pub principal_fast_lane_hard_max: i128,  // e.g., $10k
+    pub principal_fast_lane_pct_bps: u64,  // e.g., 500 = 5%
+}
+
+impl Default for WithdrawalThresholds {
+    fn default() -> Self {
+        Self {
+            free_pnl_threshold_per_day: 500 * SCALE,
+            principal_fast_lane_hard_max: 10_000 * SCALE,
+            principal_fast_lane_pct_bps: 500,  // 5%
+        }
+    }
+}
+
+/// Per-user exit bucket state (time-windowed rate limiter)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserExitBucket {
+    /// Amount used in current window
+    pub amount_used: i128,
+
+    /// Window start time (seconds since epoch)
+    pub window_start_secs: u64,
+
+    /// Free PnL used today
+    pub free_pnl_used_today: i128,
+
+    /// Principal fast lane used today
+    pub principal_fast_lane_used_today: i128,
+
+    /// Last reset day (for daily threshold resets)
+    pub last_reset_day: u64,
+}
+
+/// Global exit bucket state (aggregate throttling)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalExitBucket {
+    /// Total amount used in current window across all users
+    pub amount_used: i128,
+
+    /// Window start time
+    pub window_start_secs: u64,
+}
+
+/// Emergency mode parameters
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EmergencyMode {
+    /// Is emergency mode active?
+    pub active: bool,
+
+    /// Exit multiplier during emergency (basis points)
+    /// e.g., 5000 = 50% of normal caps
+    pub exit_multiplier_bps: u64,
+
+    /// When emergency mode auto-expires (seconds, 0 = no expiry)
+    pub expires_at_secs: u64,
+}
+
+impl Default for EmergencyMode {
+    fn default() -> Self {
+        Self {
+            active: false,
+            exit_multiplier_bps: 10000,  // 100% (no reduction)
+            expires_at_secs: 0,
+        }
+    }
+}
+
+impl EmergencyMode {
+    /// Check if emergency mode is in effect at `now_secs`
+    pub fn is_active(&self, now_secs: u64) -> bool {
+        self.active && (self.expires_at_secs == 0 || now_secs < self.expires_at_secs)
+    }
+}
+
+/// Withdrawal plan result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithdrawalPlan {
+    /// Amount that can be withdrawn immediately
+    pub immediate: i128,
+
+    /// Amount that must be queued
+    pub queued: i128,
+
+    /// Estimated wait time in seconds (0 if immediate)
+    pub eta_secs: u64,
+}
+
+/// Reset bucket if window has expired
+fn maybe_reset_bucket(
+    bucket: &mut UserExitBucket,
+    now_secs: u64,
+    window_secs: u64,
+) {
+    if now_secs >= bucket.window_start_secs.saturating_add(window_secs) {
+        bucket.amount_used = 0;
+        bucket.window_start_secs = now_secs;
+    }
+}
+
+/// Reset daily thresholds if day boundary crossed
+fn maybe_reset_daily_thresholds(
+    bucket: &mut UserExitBucket,
+    now_secs: u64,
+) {
+    let current_day = now_secs / SECONDS_PER_DAY;
+    if current_day > bucket.last_reset_day {
+        bucket.free_pnl_used_today = 0;
+        bucket.principal_fast_lane_used_today = 0;
+        bucket.last_reset_day = current_day;
+    }
+}
+
+/// Apply `bps` to `value` without overflowing
+fn apply_bps(value: i128, bps: u64) -> i128 {
+    if value.abs() > i128::MAX / 10000 {
+        // For very large values, do division first
+        (value / 10000) * bps as i128
+    } else {
+        (value * bps as i128) / 10000
+    }
+}
+
+/// Compute per-user exit allowance
+pub fn compute_user_allowance(
+    equity: i128,
+    bucket: &UserExitBucket,
+    params: &ExitBucketParams,
+    emergency: &EmergencyMode,
+) -> i128 {
+    // Compute base cap: min(pct_of_equity, hard_max)
+    let pct_cap = apply_bps(equity, params.user_pct_per_hour_bps);
+    let mut cap = pct_cap.min(params.user_hard_max_per_hour);
+
+    // Apply emergency multiplier
+    if emergency.active {
+        cap = apply_bps(cap, emergency.exit_multiplier_bps);
+    }
+
+    // Return remaining allowance
+    cap.saturating_sub(bucket.amount_used).max(0)
+}
+
+/// Compute global exit allowance
+pub fn compute_global_allowance(
+    tvl: i128,
+    global_bucket: &GlobalExitBucket,
+    params: &ExitBucketParams,
+    emergency: &EmergencyMode,
+    now_secs: u64,
+) -> i128 {
+    // Reset global bucket if window expired
+    let window_expired = now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs);
+    let bucket_used = if window_expired { 0 } else { global_bucket.amount_used };
+
+    // Compute base cap: min(pct_of_tvl, hard_max)
+    let pct_cap = apply_bps(tvl, params.tvl_pct_per_hour_bps);
+    let hard_max = apply_bps(tvl, params.global_hard_max_bps);
+    let mut cap = pct_cap.min(hard_max);
+
+    // Apply emergency multiplier
+    if emergency.active {
+        cap = apply_bps(cap, emergency.exit_multiplier_bps);
+    }
+
+    cap.saturating_sub(bucket_used).max(0)
+}
+
+/// Plan a withdrawal: compute immediate vs queued amounts
+///
+/// Threshold exceptions are consumed first (free PnL, then principal fast
+/// lane) and are not charged to the exit buckets. Whatever remains passes
+/// through min(user allowance, global allowance); the rest is queued until
+/// the rolling window refills. Buckets are updated in place.
+///
+/// # Arguments
+/// * `amount` - Requested withdrawal amount
+/// * `withdrawable` - Maximum the user may withdraw right now
+/// * `free_pnl_eligible` - Portion of the request source that is vested PnL
+/// * `fast_lane_principal` - Principal the fast lane is sized against
+/// * `equity` - User equity (sizes the per-user cap)
+/// * `user_bucket` - User's exit bucket (mutable)
+/// * `tvl` - Total value locked (sizes the global cap)
+/// * `global_bucket` - Global exit bucket (mutable)
+/// * `params` - Exit bucket parameters
+/// * `thresholds` - Threshold exceptions
+/// * `emergency` - Emergency mode state
+/// * `now_secs` - Current unix timestamp
+pub fn plan_withdrawal(
+    amount: i128,
+    withdrawable: i128,
+    free_pnl_eligible: i128,
+    fast_lane_principal: i128,
+    equity: i128,
+    user_bucket: &mut UserExitBucket,
+    tvl: i128,
+    global_bucket: &mut GlobalExitBucket,
+    params: &ExitBucketParams,
+    thresholds: &WithdrawalThresholds,
+    emergency: &EmergencyMode,
+    now_secs: u64,
+) -> WithdrawalPlan {
+    let requested = amount.min(withdrawable);  // Can't withdraw more than available
+
+    if requested <= 0 {
+        return WithdrawalPlan { immediate: 0, queued: 0, eta_secs: 0 };
+    }
+
+    // Reset buckets if windows expired
+    maybe_reset_bucket(user_bucket, now_secs, params.rolling_window_secs);
+    maybe_reset_daily_thresholds(user_bucket, now_secs);
+
+    // Initialize or reset global bucket
+    if global_bucket.window_start_secs == 0 {
+        // First use - initialize window
+        global_bucket.window_start_secs = now_secs;
+        global_bucket.amount_used = 0;
+    } else {
+        let window_expired = now_secs >= global_bucket.window_start_secs.saturating_add(params.rolling_window_secs);
+        if window_expired {
+            global_bucket.amount_used = 0;
+            global_bucket.window_start_secs = now_secs;
+        }
+    }
+
+    // Check threshold exceptions
+    // Priority: free PnL first (for vested_pnl withdrawals), then principal fast lane
+    let mut bypass_amount = 0i128;
+    let mut remaining_request = requested;
+
+    // Free PnL threshold (for vested_pnl withdrawals)
+    let free_pnl_remaining = thresholds.free_pnl_threshold_per_day.saturating_sub(user_bucket.free_pnl_used_today);
+    if free_pnl_remaining > 0 && remaining_request > 0 && free_pnl_eligible > 0 {
+        let from_free_pnl = free_pnl_remaining.min(remaining_request).min(free_pnl_eligible);
+        if from_free_pnl > 0 {
+            bypass_amount += from_free_pnl;
+            user_bucket.free_pnl_used_today += from_free_pnl;
+            remaining_request -= from_free_pnl;
+        }
+    }
+
+    // Principal fast lane (for principal withdrawals)
+    let principal_fast_lane_cap = apply_bps(fast_lane_principal, thresholds.principal_fast_lane_pct_bps)
+        .min(thresholds.principal_fast_lane_hard_max);
+    let principal_fast_lane_remaining = principal_fast_lane_cap.saturating_sub(user_bucket.principal_fast_lane_used_today);
+    if principal_fast_lane_remaining > 0 && remaining_request > 0 {
+        let from_fast_lane = principal_fast_lane_remaining.min(remaining_request);
+        bypass_amount += from_fast_lane;
+        user_bucket.principal_fast_lane_used_today += from_fast_lane;
+        remaining_request -= from_fast_lane;
+    }
+
+    // Apply exit buckets to remaining amount
+    if remaining_request == 0 {
+        // All bypassed!
+        return WithdrawalPlan { immediate: requested, queued: 0, eta_secs: 0 };
+    }
+
+    // Min of user and global allowances
+    let user_allowance = compute_user_allowance(equity, user_bucket, params, emergency);
+    let global_allowance = compute_global_allowance(tvl, global_bucket, params, emergency, now_secs);
+    let bucket_allowance = user_allowance.min(global_allowance);
+
+    // How much can pass through buckets?
+    let through_buckets = remaining_request.min(bucket_allowance);
+
+    // Update buckets
+    user_bucket.amount_used += through_buckets;
+    global_bucket.amount_used += through_buckets;
+
+    // Total immediate
+    let immediate = bypass_amount + through_buckets;
+    let queued = requested - immediate;
+
+    // Estimate ETA (simple: assume cap refills after one window)
+    let eta_secs = if queued > 0 {
+        params.rolling_window_secs
+    } else {
+        0
+    };
+
+    WithdrawalPlan { immediate, queued, eta_secs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emergency_mode_expiry() {
+        let mut emergency = EmergencyMode::default();
+        assert!(!emergency.is_active(100));
+
+        emergency.active = true;
+        assert!(emergency.is_active(100));
+
+        emergency.expires_at_secs = 200;
+        assert!(emergency.is_active(199));
+        assert!(!emergency.is_active(200));
+    }
+
+    #[test]
+    fn test_pnl_withdrawal_skips_principal_fast_lane() {
+        let params = ExitBucketParams::default();
+        let thresholds = WithdrawalThresholds::default();
+        let emergency = EmergencyMode::default();
+        let mut global_bucket = GlobalExitBucket::default();
+        let mut bucket = UserExitBucket::default();
+        let now = 10 * SECONDS_PER_DAY;
+
+        // Vested PnL withdrawal: only the free PnL allowance bypasses the buckets
+        let equity = 1_000 * SCALE;
+        let vested_pnl = 1_000 * SCALE;
+        let plan = plan_withdrawal(
+            vested_pnl, vested_pnl, vested_pnl, 0, equity, &mut bucket,
+            1_000_000 * SCALE, &mut global_bucket, &params, &thresholds, &emergency, now,
+        );
+
+        // $500 free PnL + 20% of $1000 equity through the bucket
+        assert_eq!(plan.immediate, 700 * SCALE);
+        assert_eq!(plan.queued, 300 * SCALE);
+        assert_eq!(plan.eta_secs, params.rolling_window_secs);
+        assert_eq!(bucket.principal_fast_lane_used_today, 0);
+        assert_eq!(bucket.free_pnl_used_today, 500 * SCALE);
+    }
+}
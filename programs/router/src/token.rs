@@ -0,0 +1,143 @@
+//! SPL Token CPI helpers for Router program
+//!
+//! The router moves collateral between user token accounts and vault token
+//! accounts with the SPL Token `Transfer` instruction. Instructions are built
+//! by hand (same approach as the slab commit_fill CPI) to avoid pulling in
+//! the full spl-token crate.
+
+use percolator_common::PercolatorError;
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction, Signer},
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+};
+
+/// SPL Token program ID
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    pinocchio_pubkey::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// SPL Token `Transfer` instruction discriminator
+const TRANSFER_DISCRIMINATOR: u8 = 3;
+
+/// Validate that an account is the SPL Token program
+#[inline]
+pub fn validate_token_program(token_program: &AccountInfo) -> Result<(), PercolatorError> {
+    if token_program.key() != &TOKEN_PROGRAM_ID {
+        return Err(PercolatorError::InvalidAccount);
+    }
+    Ok(())
+}
+
+/// Byte length of an SPL Token account
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Read the mint and owner of an SPL Token account
+///
+/// # Returns
+/// * `(mint, owner)` from the account's first 64 bytes
+pub fn read_token_account(token_account: &AccountInfo) -> Result<(Pubkey, Pubkey), PercolatorError> {
+    if !token_account.is_owned_by(&TOKEN_PROGRAM_ID) {
+        return Err(PercolatorError::InvalidAccountOwner);
+    }
+    let data = token_account
+        .try_borrow_data()
+        .map_err(|_| PercolatorError::InvalidAccount)?;
+    if data.len() != TOKEN_ACCOUNT_LEN {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let mut mint = Pubkey::default();
+    let mut owner = Pubkey::default();
+    mint.copy_from_slice(&data[0..32]);
+    owner.copy_from_slice(&data[32..64]);
+    Ok((mint, owner))
+}
+
+/// Build SPL Token `Transfer` instruction data
+///
+/// Layout: discriminator (1) + amount (8)
+#[inline]
+fn transfer_data(amount: u64) -> [u8; 9] {
+    let mut data = [0u8; 9];
+    data[0] = TRANSFER_DISCRIMINATOR;
+    data[1..9].copy_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Transfer tokens where the authority is a transaction signer
+///
+/// # Arguments
+/// * `from` - Source token account (writable)
+/// * `to` - Destination token account (writable)
+/// * `authority` - Owner of the source token account (signer)
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount in base units
+pub fn transfer(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    authority: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u64,
+) -> Result<(), PercolatorError> {
+    let data = transfer_data(amount);
+    let account_metas = [
+        AccountMeta::writable(from.key()),
+        AccountMeta::writable(to.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    invoke(&instruction, &[from, to, authority]).map_err(|_| PercolatorError::CpiFailed)
+}
+
+/// Transfer tokens where the authority is a router PDA
+///
+/// # Arguments
+/// * `from` - Source token account (writable, owned by the PDA)
+/// * `to` - Destination token account (writable)
+/// * `authority` - PDA that owns the source token account
+/// * `token_program` - SPL Token program
+/// * `amount` - Amount in base units
+/// * `signer` - PDA signer seeds for `authority`
+pub fn transfer_signed(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    authority: &AccountInfo,
+    token_program: &AccountInfo,
+    amount: u64,
+    signer: Signer,
+) -> Result<(), PercolatorError> {
+    let data = transfer_data(amount);
+    let account_metas = [
+        AccountMeta::writable(from.key()),
+        AccountMeta::writable(to.key()),
+        AccountMeta::readonly_signer(authority.key()),
+    ];
+
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: &account_metas,
+        data: &data,
+    };
+
+    invoke_signed(&instruction, &[from, to, authority], &[signer])
+        .map_err(|_| PercolatorError::CpiFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_data_layout() {
+        let data = transfer_data(1_000_000);
+        assert_eq!(data[0], TRANSFER_DISCRIMINATOR);
+        assert_eq!(u64::from_le_bytes(data[1..9].try_into().unwrap()), 1_000_000);
+    }
+}
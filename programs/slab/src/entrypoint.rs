@@ -8,9 +8,14 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::instructions::{SlabInstruction, process_initialize_slab, process_commit_fill, Side};
+use crate::instructions::{
+    SlabInstruction, process_initialize_slab, process_commit_fill, process_place_order,
+    process_cancel_order, process_replace_order, process_mass_cancel, process_reserve,
+    process_commit, process_cancel, process_batch_open, process_update_mark,
+    process_configure_batch, Side,
+};
 use crate::state::SlabState;
-use percolator_common::{PercolatorError, validate_owner, validate_writable, borrow_account_data_mut, InstructionReader};
+use percolator_common::{PercolatorError, MakerClass, PriceOracle, TimeInForce, ORACLE_PROGRAM_ID, validate_owner, validate_signer, validate_writable, borrow_account_data, borrow_account_data_mut, InstructionReader};
 
 entrypoint!(process_instruction);
 
@@ -30,6 +35,16 @@ pub fn process_instruction(
     let instruction = match discriminator {
         0 => SlabInstruction::Initialize,
         1 => SlabInstruction::CommitFill,
+        2 => SlabInstruction::PlaceOrder,
+        3 => SlabInstruction::CancelOrder,
+        4 => SlabInstruction::ReplaceOrder,
+        5 => SlabInstruction::MassCancel,
+        6 => SlabInstruction::Reserve,
+        7 => SlabInstruction::Commit,
+        8 => SlabInstruction::Cancel,
+        9 => SlabInstruction::BatchOpen,
+        10 => SlabInstruction::UpdateMark,
+        11 => SlabInstruction::ConfigureBatch,
         _ => {
             msg!("Error: Unknown instruction");
             return Err(PercolatorError::InvalidInstruction.into());
@@ -46,6 +61,46 @@ pub fn process_instruction(
             msg!("Instruction: CommitFill");
             process_commit_fill_inner(program_id, accounts, &instruction_data[1..])
         }
+        SlabInstruction::PlaceOrder => {
+            msg!("Instruction: PlaceOrder");
+            process_place_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::CancelOrder => {
+            msg!("Instruction: CancelOrder");
+            process_cancel_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::ReplaceOrder => {
+            msg!("Instruction: ReplaceOrder");
+            process_replace_order_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::MassCancel => {
+            msg!("Instruction: MassCancel");
+            process_mass_cancel_inner(program_id, accounts)
+        }
+        SlabInstruction::Reserve => {
+            msg!("Instruction: Reserve");
+            process_reserve_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::Commit => {
+            msg!("Instruction: Commit");
+            process_commit_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::Cancel => {
+            msg!("Instruction: Cancel");
+            process_cancel_inner(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::BatchOpen => {
+            msg!("Instruction: BatchOpen");
+            process_batch_open_inner(program_id, accounts)
+        }
+        SlabInstruction::UpdateMark => {
+            msg!("Instruction: UpdateMark");
+            process_update_mark_inner(program_id, accounts)
+        }
+        SlabInstruction::ConfigureBatch => {
+            msg!("Instruction: ConfigureBatch");
+            process_configure_batch_inner(program_id, accounts, &instruction_data[1..])
+        }
     }
 }
 
@@ -63,6 +118,7 @@ pub fn process_instruction(
 /// - instrument: Pubkey (32 bytes)
 /// - mark_px: i64 (8 bytes)
 /// - taker_fee_bps: i64 (8 bytes)
+/// - maker_fee_bps: i64 (8 bytes, negative = rebate)
 /// - contract_size: i64 (8 bytes)
 /// - bump: u8 (1 byte)
 fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
@@ -82,6 +138,7 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
     let instrument_bytes = reader.read_bytes::<32>()?;
     let mark_px = reader.read_i64()?;
     let taker_fee_bps = reader.read_i64()?;
+    let maker_fee_bps = reader.read_i64()?;
     let contract_size = reader.read_i64()?;
     let bump = reader.read_u8()?;
 
@@ -98,6 +155,7 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
         instrument,
         mark_px,
         taker_fee_bps,
+        maker_fee_bps,
         contract_size,
         bump,
     )?;
@@ -113,11 +171,13 @@ fn process_initialize_inner(program_id: &Pubkey, accounts: &[AccountInfo], data:
 /// 1. `[writable]` Fill receipt account
 /// 2. `[signer]` Router signer
 ///
-/// Expected data layout (21 bytes):
+/// Expected data layout (54 bytes):
 /// - expected_seqno: u32 (4 bytes) - expected slab seqno (TOCTOU protection)
 /// - side: u8 (1 byte) - 0 = Buy, 1 = Sell
 /// - qty: i64 (8 bytes) - quantity to fill (1e6 scale)
 /// - limit_px: i64 (8 bytes) - limit price (1e6 scale)
+/// - taker: Pubkey (32 bytes) - aggressor account (roundtrip guard)
+/// - liquidation: u8 (1 byte) - 1 = router liquidation (exempt from the guard)
 fn process_commit_fill_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     if accounts.len() < 3 {
         msg!("Error: CommitFill instruction requires at least 3 accounts");
@@ -132,6 +192,7 @@ fn process_commit_fill_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     validate_owner(slab_account, program_id)?;
     validate_writable(slab_account)?;
     validate_writable(receipt_account)?;
+    validate_signer(router_signer)?;
 
     // Borrow slab state mutably
     let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
@@ -142,6 +203,8 @@ fn process_commit_fill_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     let side_byte = reader.read_u8()?;
     let qty = reader.read_i64()?;
     let limit_px = reader.read_i64()?;
+    let taker = Pubkey::from(reader.read_bytes::<32>()?);
+    let liquidation = reader.read_u8()? != 0;
 
     // Convert side byte to Side enum
     let side = match side_byte {
@@ -156,9 +219,12 @@ fn process_commit_fill_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     // Call the commit_fill logic
     process_commit_fill(
         slab,
+        slab_account.key(),
         receipt_account,
         router_signer.key(),
         expected_seqno,
+        &taker,
+        liquidation,
         side,
         qty,
         limit_px,
@@ -167,3 +233,376 @@ fn process_commit_fill_inner(program_id: &Pubkey, accounts: &[AccountInfo], data
     msg!("CommitFill processed successfully");
     Ok(())
 }
+
+/// Validate LP order accounts
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+fn validate_lp_order_accounts(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<(), PercolatorError> {
+    if accounts.len() < 2 {
+        msg!("Error: LP order instructions require at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    validate_owner(&accounts[0], program_id)?;
+    validate_writable(&accounts[0])?;
+    validate_signer(&accounts[1])?;
+    Ok(())
+}
+
+/// Current time in milliseconds for order time priority, hold expiry and
+/// batch windows
+fn current_time_ms() -> u64 {
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    Clock::get()
+        .map(|clock| (clock.unix_timestamp as u64).saturating_mul(1_000))
+        .unwrap_or(0)
+}
+
+/// Parse a side byte
+fn parse_side(side_byte: u8) -> Result<Side, PercolatorError> {
+    match side_byte {
+        0 => Ok(Side::Buy),
+        1 => Ok(Side::Sell),
+        _ => {
+            msg!("Error: Invalid side");
+            Err(PercolatorError::InvalidSide)
+        }
+    }
+}
+
+/// Process place_order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (19 bytes):
+/// - maker_class: u8 (1 byte) - 0 = REG (pending until next epoch, needs a configured oracle), 1 = DLP
+/// - side: u8 (1 byte) - 0 = Buy, 1 = Sell
+/// - tif: u8 (1 byte) - 0 = GTC, 3 = PostOnly
+/// - price: i64 (8 bytes) - limit price (1e6 scale)
+/// - qty: i64 (8 bytes) - quantity (1e6 scale)
+fn process_place_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    validate_lp_order_accounts(program_id, accounts)?;
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let lp_signer = accounts[1].key();
+
+    let mut reader = InstructionReader::new(data);
+    let maker_class = match reader.read_u8()? {
+        0 => MakerClass::REG,
+        1 => MakerClass::DLP,
+        _ => {
+            msg!("Error: Invalid maker class");
+            return Err(PercolatorError::InvalidInstruction.into());
+        }
+    };
+    let side = parse_side(reader.read_u8()?)?;
+    let tif = match reader.read_u8()? {
+        0 => TimeInForce::GTC,
+        3 => TimeInForce::PostOnly,
+        _ => {
+            msg!("Error: Invalid time in force");
+            return Err(PercolatorError::InvalidTimeInForce.into());
+        }
+    };
+    let price = reader.read_i64()?;
+    let qty = reader.read_i64()?;
+
+    process_place_order(slab, lp_signer, maker_class, side, tif, price, qty, current_time_ms())?;
+    Ok(())
+}
+
+/// Process cancel_order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (8 bytes):
+/// - order_id: u64 (8 bytes)
+fn process_cancel_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    validate_lp_order_accounts(program_id, accounts)?;
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let lp_signer = accounts[1].key();
+
+    let mut reader = InstructionReader::new(data);
+    let order_id = reader.read_u64()?;
+
+    process_cancel_order(slab, lp_signer, order_id)?;
+    Ok(())
+}
+
+/// Process replace_order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (24 bytes):
+/// - order_id: u64 (8 bytes) - order to replace
+/// - new_price: i64 (8 bytes) - new limit price (1e6 scale)
+/// - new_qty: i64 (8 bytes) - new quantity (1e6 scale)
+fn process_replace_order_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    validate_lp_order_accounts(program_id, accounts)?;
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let lp_signer = accounts[1].key();
+
+    let mut reader = InstructionReader::new(data);
+    let order_id = reader.read_u64()?;
+    let new_price = reader.read_i64()?;
+    let new_qty = reader.read_i64()?;
+
+    process_replace_order(slab, lp_signer, order_id, new_price, new_qty, current_time_ms())?;
+    Ok(())
+}
+
+/// Process mass_cancel instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout: none
+fn process_mass_cancel_inner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    validate_lp_order_accounts(program_id, accounts)?;
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let lp_signer = accounts[1].key();
+
+    process_mass_cancel(slab, lp_signer)?;
+    Ok(())
+}
+
+/// Process reserve instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[writable]` Reserve receipt account
+/// 2. `[signer]` Router signer
+///
+/// Expected data layout (69 bytes):
+/// - expected_seqno: u32 (4 bytes) - expected slab seqno (TOCTOU protection)
+/// - side: u8 (1 byte) - 0 = Buy, 1 = Sell
+/// - qty: i64 (8 bytes) - quantity to reserve (1e6 scale)
+/// - limit_px: i64 (8 bytes) - limit price (1e6 scale)
+/// - ttl_ms: u64 (8 bytes) - hold lifetime (clamped to MAX_CAP_TTL_MS)
+/// - route_id: u64 (8 bytes)
+/// - commitment: [u8; 32] (32 bytes) - required to commit or cancel
+fn process_reserve_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: Reserve instruction requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    let receipt_account = &accounts[1];
+    let router_signer = &accounts[2];
+
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+    validate_writable(receipt_account)?;
+    validate_signer(router_signer)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let expected_seqno = reader.read_u32()?;
+    let side = parse_side(reader.read_u8()?)?;
+    let qty = reader.read_i64()?;
+    let limit_px = reader.read_i64()?;
+    let ttl_ms = reader.read_u64()?;
+    let route_id = reader.read_u64()?;
+    let commitment = reader.read_bytes::<32>()?;
+
+    process_reserve(
+        slab,
+        receipt_account,
+        router_signer.key(),
+        expected_seqno,
+        side,
+        qty,
+        limit_px,
+        ttl_ms,
+        route_id,
+        commitment,
+        current_time_ms(),
+    )?;
+
+    msg!("Reserve processed successfully");
+    Ok(())
+}
+
+/// Process commit instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[writable]` Fill receipt account
+/// 2. `[signer]` Router signer
+///
+/// Expected data layout (41 bytes):
+/// - hold_id: u64 (8 bytes)
+/// - side: u8 (1 byte) - 0 = Buy, 1 = Sell (must match the hold)
+/// - commitment: [u8; 32] (32 bytes)
+fn process_commit_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 3 {
+        msg!("Error: Commit instruction requires at least 3 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    let receipt_account = &accounts[1];
+    let router_signer = &accounts[2];
+
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+    validate_writable(receipt_account)?;
+    validate_signer(router_signer)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let hold_id = reader.read_u64()?;
+    let side = parse_side(reader.read_u8()?)?;
+    let commitment = reader.read_bytes::<32>()?;
+
+    process_commit(
+        slab,
+        slab_account.key(),
+        receipt_account,
+        router_signer.key(),
+        hold_id,
+        side,
+        &commitment,
+        current_time_ms(),
+    )?;
+
+    msg!("Commit processed successfully");
+    Ok(())
+}
+
+/// Process cancel instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Router signer
+///
+/// Expected data layout (40 bytes):
+/// - hold_id: u64 (8 bytes)
+/// - commitment: [u8; 32] (32 bytes)
+fn process_cancel_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: Cancel instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab_account = &accounts[0];
+    let router_signer = &accounts[1];
+
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+    validate_signer(router_signer)?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+
+    let mut reader = InstructionReader::new(data);
+    let hold_id = reader.read_u64()?;
+    let commitment = reader.read_bytes::<32>()?;
+
+    process_cancel(slab, router_signer.key(), hold_id, &commitment)?;
+    Ok(())
+}
+
+/// Borrow the slab and oracle accounts for a mark crank
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[]` Oracle price account (the slab's configured oracle, owned by
+///    the oracle program)
+fn borrow_mark_accounts<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo],
+) -> Result<(&'a mut SlabState, &'a PriceOracle), PercolatorError> {
+    if accounts.len() < 2 {
+        msg!("Error: Mark cranks require at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    validate_owner(&accounts[0], program_id)?;
+    validate_writable(&accounts[0])?;
+    validate_owner(&accounts[1], &ORACLE_PROGRAM_ID).inspect_err(|_| {
+        msg!("Error: Oracle account not owned by the oracle program");
+    })?;
+
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let oracle = unsafe { borrow_account_data::<PriceOracle>(&accounts[1])? };
+    Ok((slab, oracle))
+}
+
+/// Process batch_open instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[]` Oracle price account (the slab's configured oracle, owned by the oracle program)
+///
+/// Expected data layout: none
+fn process_batch_open_inner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let (slab, oracle) = borrow_mark_accounts(program_id, accounts)?;
+
+    process_batch_open(slab, accounts[1].key(), oracle, current_time_ms())?;
+    Ok(())
+}
+
+/// Process update_mark instruction (permissionless crank)
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[]` Oracle price account (the slab's configured oracle, owned by the oracle program)
+///
+/// Expected data layout: none
+fn process_update_mark_inner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let (slab, oracle) = borrow_mark_accounts(program_id, accounts)?;
+
+    process_update_mark(slab, accounts[1].key(), oracle)?;
+    Ok(())
+}
+
+/// Process configure_batch instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Expected data layout (57 bytes):
+/// - oracle: Pubkey (32 bytes)
+/// - batch_ms: u64 (8 bytes) - minimum batch length
+/// - kill_band_bps: u64 (8 bytes) - 0 = disabled
+/// - roundtrip_max_qty: u64 (8 bytes) - maximum same-batch roundtrip per taker
+/// - jit_penalty_on: u8 (1 byte) - 0 = off, 1 = on
+fn process_configure_batch_inner(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    validate_lp_order_accounts(program_id, accounts)?;
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0])? };
+    let lp_signer = accounts[1].key();
+
+    let mut reader = InstructionReader::new(data);
+    let oracle = Pubkey::from(reader.read_bytes::<32>()?);
+    let batch_ms = reader.read_u64()?;
+    let kill_band_bps = reader.read_u64()?;
+    let roundtrip_max_qty = reader.read_u64()?;
+    let jit_penalty_on = reader.read_u8()? != 0;
+
+    process_configure_batch(
+        slab,
+        lp_signer,
+        oracle,
+        batch_ms,
+        kill_band_bps,
+        roundtrip_max_qty,
+        jit_penalty_on,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "entrypoint_test.rs"]
+mod entrypoint_test;
@@ -0,0 +1,172 @@
+//! Entrypoint account validation tests
+//!
+//! Accounts are built in the loader's serialized input format and parsed
+//! with pinocchio's `deserialize`, so the `process_*_inner` account checks
+//! run exactly as on chain.
+
+#[cfg(test)]
+mod entrypoint_tests {
+    use crate::entrypoint::process_instruction;
+    use crate::instructions::process_place_order;
+    use crate::state::{FillReceipt, SlabHeader, SlabState};
+    use core::mem::MaybeUninit;
+    use percolator_common::{
+        borrow_account_data_mut, MakerClass, PercolatorError, PriceOracle, Side, TimeInForce, ORACLE_PROGRAM_ID,
+        PRICE_ORACLE_SIZE,
+    };
+    use pinocchio::account_info::{AccountInfo, MAX_PERMITTED_DATA_INCREASE};
+    use pinocchio::entrypoint::{deserialize, NON_DUP_MARKER};
+    use pinocchio::pubkey::Pubkey;
+
+    const S: i64 = 1_000_000;
+    const PROGRAM_ID: Pubkey = [1; 32];
+    const ROUTER: Pubkey = [2; 32];
+    const LP: Pubkey = [5; 32];
+    const TAKER: Pubkey = [9; 32];
+
+    /// Test account in loader input form
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        is_signer: bool,
+        is_writable: bool,
+        data_len: usize,
+    }
+
+    /// Serialize accounts and instruction data as the loader does
+    ///
+    /// The buffer is `u128`-backed so account data keeps the loader's
+    /// 16-byte alignment.
+    fn serialize(accounts: &[TestAccount], data: &[u8]) -> Vec<u128> {
+        let mut input = Vec::new();
+        input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for account in accounts {
+            input.extend_from_slice(&[NON_DUP_MARKER, account.is_signer as u8, account.is_writable as u8, 0]);
+            input.extend_from_slice(&0i32.to_le_bytes());
+            input.extend_from_slice(&account.key);
+            input.extend_from_slice(&account.owner);
+            input.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+            input.extend_from_slice(&(account.data_len as u64).to_le_bytes());
+            input.resize(input.len() + account.data_len + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.extend_from_slice(&0u64.to_le_bytes());
+            input.resize(input.len().next_multiple_of(8), 0);
+        }
+        input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&PROGRAM_ID);
+
+        let mut buffer = vec![0u128; input.len().div_ceil(16)];
+        // SAFETY: the buffer holds at least `input.len()` bytes
+        unsafe {
+            core::ptr::copy_nonoverlapping(input.as_ptr(), buffer.as_mut_ptr() as *mut u8, input.len());
+        }
+        buffer
+    }
+
+    /// Slab, receipt and router accounts for commit_fill
+    fn commit_fill_accounts(router_signed: bool) -> [TestAccount; 3] {
+        [
+            TestAccount { key: [3; 32], owner: PROGRAM_ID, is_signer: false, is_writable: true, data_len: SlabState::LEN },
+            TestAccount { key: [4; 32], owner: PROGRAM_ID, is_signer: false, is_writable: true, data_len: FillReceipt::LEN },
+            TestAccount { key: ROUTER, owner: [0; 32], is_signer: router_signed, is_writable: false, data_len: 0 },
+        ]
+    }
+
+    /// Slab, receipt and router accounts parsed from a loader input buffer
+    struct CommitFillHarness {
+        /// Backing memory of the accounts
+        _buffer: Vec<u128>,
+        accounts: [AccountInfo; 3],
+    }
+
+    impl CommitFillHarness {
+        /// Deserialize the accounts and initialize the slab
+        fn new(router_signed: bool) -> Self {
+            let mut buffer = serialize(&commit_fill_accounts(router_signed), &[]);
+            let mut accounts = [MaybeUninit::<AccountInfo>::uninit(); 3];
+
+            // SAFETY: `buffer` is in loader format and outlives the accounts
+            let (_, count, _) = unsafe { deserialize::<3>(buffer.as_mut_ptr() as *mut u8, &mut accounts) };
+            assert_eq!(count, 3);
+            let mut harness = Self {
+                _buffer: buffer,
+                accounts: accounts.map(|account| unsafe { account.assume_init() }),
+            };
+
+            let header = SlabHeader::new(PROGRAM_ID, LP, ROUTER, [6; 32], 100 * S, 10, S, 255);
+            harness.slab().init(header);
+            harness
+        }
+
+        fn slab(&mut self) -> &mut SlabState {
+            unsafe { borrow_account_data_mut::<SlabState>(&self.accounts[0]).unwrap() }
+        }
+
+        /// Run commit_fill at the slab's current seqno
+        fn commit_fill(&mut self, side: u8, qty: i64, limit_px: i64, liquidation: bool) -> pinocchio::ProgramResult {
+            let mut data = vec![1u8];
+            data.extend_from_slice(&self.slab().header.seqno.to_le_bytes());
+            data.push(side);
+            data.extend_from_slice(&qty.to_le_bytes());
+            data.extend_from_slice(&limit_px.to_le_bytes());
+            data.extend_from_slice(&TAKER);
+            data.push(liquidation as u8);
+            process_instruction(&PROGRAM_ID, &self.accounts, &data)
+        }
+    }
+
+    #[test]
+    fn test_commit_fill_requires_router_signature() {
+        let mut harness = CommitFillHarness::new(false);
+        assert_eq!(harness.commit_fill(0, S, 100 * S, false), Err(PercolatorError::InvalidAccount.into()));
+
+        let mut harness = CommitFillHarness::new(true);
+        assert_eq!(harness.commit_fill(0, S, 100 * S, false), Ok(()));
+    }
+
+    #[test]
+    fn test_liquidation_flag_bypasses_roundtrip_guard() {
+        let mut harness = CommitFillHarness::new(true);
+        let slab = harness.slab();
+        process_place_order(slab, &LP, MakerClass::DLP, Side::Sell, TimeInForce::GTC, 101 * S, 2 * S, 0).unwrap();
+        process_place_order(slab, &LP, MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S, 2 * S, 0).unwrap();
+
+        assert_eq!(harness.commit_fill(0, S, 101 * S, false), Ok(()));
+        assert_eq!(
+            harness.commit_fill(1, S, 99 * S, false),
+            Err(PercolatorError::RoundtripDetected.into())
+        );
+        assert_eq!(harness.commit_fill(1, S, 99 * S, true), Ok(()));
+        assert_eq!(harness.slab().quote_cache.best_bids[0].avail_qty, S);
+    }
+
+    #[test]
+    fn test_update_mark_requires_oracle_program_owner() {
+        let oracle_key = [7; 32];
+        for (owner, expected) in [
+            ([8; 32], Err(PercolatorError::InvalidAccountOwner.into())),
+            (ORACLE_PROGRAM_ID, Ok(())),
+        ] {
+            let mut buffer = serialize(
+                &[
+                    TestAccount { key: [3; 32], owner: PROGRAM_ID, is_signer: false, is_writable: true, data_len: SlabState::LEN },
+                    TestAccount { key: oracle_key, owner, is_signer: false, is_writable: false, data_len: PRICE_ORACLE_SIZE },
+                ],
+                &[],
+            );
+            let mut accounts = [MaybeUninit::<AccountInfo>::uninit(); 2];
+            // SAFETY: `buffer` is in loader format and outlives the accounts
+            let (_, count, _) = unsafe { deserialize::<2>(buffer.as_mut_ptr() as *mut u8, &mut accounts) };
+            assert_eq!(count, 2);
+            let accounts = accounts.map(|account| unsafe { account.assume_init() });
+
+            let slab = unsafe { borrow_account_data_mut::<SlabState>(&accounts[0]).unwrap() };
+            slab.init(SlabHeader::new(PROGRAM_ID, LP, ROUTER, [6; 32], 100 * S, 10, S, 255));
+            slab.batch.oracle = oracle_key;
+            let oracle = unsafe { borrow_account_data_mut::<PriceOracle>(&accounts[1]).unwrap() };
+            *oracle = PriceOracle::new([0; 32], [6; 32], 100 * S, 255);
+
+            assert_eq!(process_instruction(&PROGRAM_ID, &accounts, &[10]), expected);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Batch instructions - epoch advance, mark refresh and anti-toxicity config
+//!
+//! `batch_open` and `update_mark` are permissionless cranks that read the
+//! slab's configured oracle. Opening a batch promotes regular orders that
+//! were posted in the previous epoch; refreshing the mark mid-batch is
+//! what arms the kill band against oracle spikes.
+
+use crate::instructions::validate_lp_owner;
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Maximum kill band (basis points)
+const MAX_KILL_BAND_BPS: u64 = 10_000;
+
+/// Copy the oracle price into the slab mark
+fn refresh_mark(
+    slab: &mut SlabState,
+    oracle_key: &Pubkey,
+    oracle: &PriceOracle,
+) -> Result<i64, PercolatorError> {
+    if slab.batch.oracle == Pubkey::default() || &slab.batch.oracle != oracle_key {
+        msg!("Error: Oracle is not the slab's configured oracle");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if !oracle.validate() || oracle.instrument != slab.header.instrument {
+        msg!("Error: Invalid oracle account");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if oracle.price <= 0 {
+        msg!("Error: Oracle price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    slab.header.mark_px = oracle.price;
+    Ok(oracle.price)
+}
+
+/// Process configure_batch instruction
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `lp_signer` - LP owner (must match slab.header.lp_owner)
+/// * `oracle` - Oracle the mark is read from (cannot be unset once configured,
+///   so pending regular orders can always be promoted)
+/// * `batch_ms` - Minimum batch length (milliseconds)
+/// * `kill_band_bps` - Maximum mark move within a batch (0 = disabled)
+/// * `roundtrip_max_qty` - Maximum same-batch roundtrip per taker
+/// * `jit_penalty_on` - Whether JIT DLP liquidity forfeits its rebate
+pub fn process_configure_batch(
+    slab: &mut SlabState,
+    lp_signer: &Pubkey,
+    oracle: Pubkey,
+    batch_ms: u64,
+    kill_band_bps: u64,
+    roundtrip_max_qty: u64,
+    jit_penalty_on: bool,
+) -> Result<(), PercolatorError> {
+    validate_lp_owner(slab, lp_signer)?;
+
+    if oracle == Pubkey::default() {
+        msg!("Error: Batch oracle must be set");
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if kill_band_bps > MAX_KILL_BAND_BPS {
+        msg!("Error: Kill band exceeds 100%");
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+
+    slab.batch.oracle = oracle;
+    slab.batch.batch_ms = batch_ms;
+    slab.batch.kill_band_bps = kill_band_bps;
+    slab.batch.roundtrip_max_qty = roundtrip_max_qty;
+    slab.batch.jit_penalty_on = jit_penalty_on;
+
+    msg!("ConfigureBatch executed successfully");
+    Ok(())
+}
+
+/// Process batch_open instruction
+///
+/// Advances the epoch once the current batch has run for `batch_ms`,
+/// snapshots the oracle mark for the kill band, clears the aggressor
+/// ledger and promotes pending orders eligible in the new epoch.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `oracle_key` - Oracle account pubkey
+/// * `oracle` - Oracle price account
+/// * `now_ms` - Current time (milliseconds)
+///
+/// # Returns
+/// * The new epoch
+pub fn process_batch_open(
+    slab: &mut SlabState,
+    oracle_key: &Pubkey,
+    oracle: &PriceOracle,
+    now_ms: u64,
+) -> Result<u16, PercolatorError> {
+    if !slab.batch.can_open(now_ms) {
+        msg!("Error: Current batch has not run for batch_ms");
+        return Err(PercolatorError::BatchNotOpen);
+    }
+
+    let mark_px = refresh_mark(slab, oracle_key, oracle)?;
+    let epoch = slab.batch.open(now_ms, mark_px);
+    slab.aggressors.clear();
+
+    // Promoted orders become matchable: republish the top of book
+    if slab.book.promote(epoch) > 0 {
+        slab.publish_book_change();
+    }
+
+    msg!("BatchOpen executed successfully");
+    Ok(epoch)
+}
+
+/// Process update_mark instruction
+///
+/// Refreshes the mark from the oracle within a batch. Fills are rejected
+/// while the new mark is outside the kill band.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `oracle_key` - Oracle account pubkey
+/// * `oracle` - Oracle price account
+pub fn process_update_mark(
+    slab: &mut SlabState,
+    oracle_key: &Pubkey,
+    oracle: &PriceOracle,
+) -> Result<(), PercolatorError> {
+    refresh_mark(slab, oracle_key, oracle)?;
+
+    msg!("UpdateMark executed successfully");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{check_kill_band, process_place_order, roundtrip_allowance};
+    use crate::state::SlabHeader;
+
+    const S: i64 = 1_000_000;
+    const LP: Pubkey = [7; 32];
+    const ORACLE: Pubkey = [5; 32];
+    const INSTRUMENT: Pubkey = [3; 32];
+
+    fn slab() -> SlabState {
+        let header = SlabHeader::new([0; 32], LP, [9; 32], INSTRUMENT, 100 * S, 10, S, 255);
+        let mut slab = SlabState::new(header);
+        process_configure_batch(&mut slab, &LP, ORACLE, 100, 500, 0, true).unwrap();
+        slab
+    }
+
+    #[test]
+    fn test_configure_requires_lp_and_sane_band() {
+        let mut slab = slab();
+        assert_eq!(
+            process_configure_batch(&mut slab, &ORACLE, ORACLE, 100, 500, 0, true),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(
+            process_configure_batch(&mut slab, &LP, ORACLE, 100, 10_001, 0, true),
+            Err(PercolatorError::InvalidRiskParams)
+        );
+        assert_eq!(
+            process_configure_batch(&mut slab, &LP, Pubkey::default(), 100, 500, 0, true),
+            Err(PercolatorError::InvalidAccount)
+        );
+    }
+
+    #[test]
+    fn test_regular_orders_wait_one_epoch() {
+        let mut slab = slab();
+        let oracle = PriceOracle::new([0; 32], INSTRUMENT, 100 * S, 0);
+
+        process_place_order(&mut slab, &LP, MakerClass::REG, Side::Sell, TimeInForce::GTC, 101 * S, S, 0).unwrap();
+        assert_eq!(slab.quote_cache.best_asks[0].avail_qty, 0);
+
+        assert_eq!(process_batch_open(&mut slab, &ORACLE, &oracle, 1_000), Ok(1));
+        assert_eq!(slab.quote_cache.best_asks[0].px, 101 * S);
+        assert_eq!(slab.quote_cache.best_asks[0].avail_qty, S);
+
+        // Posted in epoch 1: waits for epoch 2, batches are at least 100ms
+        process_place_order(&mut slab, &LP, MakerClass::REG, Side::Sell, TimeInForce::GTC, 102 * S, S, 1_050).unwrap();
+        assert_eq!(
+            process_batch_open(&mut slab, &ORACLE, &oracle, 1_099),
+            Err(PercolatorError::BatchNotOpen)
+        );
+        assert_eq!(slab.quote_cache.best_asks[1].avail_qty, 0);
+        assert_eq!(process_batch_open(&mut slab, &ORACLE, &oracle, 1_100), Ok(2));
+        assert_eq!(slab.quote_cache.best_asks[1].avail_qty, S);
+    }
+
+    #[test]
+    fn test_regular_orders_require_oracle() {
+        let header = SlabHeader::new([0; 32], LP, [9; 32], INSTRUMENT, 100 * S, 10, S, 255);
+        let mut slab = SlabState::new(header);
+
+        assert_eq!(
+            process_place_order(&mut slab, &LP, MakerClass::REG, Side::Sell, TimeInForce::GTC, 101 * S, S, 0),
+            Err(PercolatorError::InvalidMakerClass)
+        );
+        assert!(process_place_order(&mut slab, &LP, MakerClass::DLP, Side::Sell, TimeInForce::GTC, 101 * S, S, 0).is_ok());
+
+        process_configure_batch(&mut slab, &LP, ORACLE, 100, 500, 0, true).unwrap();
+        assert!(process_place_order(&mut slab, &LP, MakerClass::REG, Side::Sell, TimeInForce::GTC, 102 * S, S, 0).is_ok());
+    }
+
+    #[test]
+    fn test_mark_outside_kill_band_blocks_fills() {
+        let mut slab = slab();
+        let mut oracle = PriceOracle::new([0; 32], INSTRUMENT, 100 * S, 0);
+        process_batch_open(&mut slab, &ORACLE, &oracle, 1_000).unwrap();
+
+        assert_eq!(process_update_mark(&mut slab, &[6; 32], &oracle), Err(PercolatorError::InvalidAccount));
+
+        oracle.price = 106 * S;
+        process_update_mark(&mut slab, &ORACLE, &oracle).unwrap();
+        assert_eq!(slab.header.mark_px, 106 * S);
+        assert_eq!(check_kill_band(&slab), Err(PercolatorError::KillBandExceeded));
+
+        // The next batch re-anchors the band at the new mark
+        process_batch_open(&mut slab, &ORACLE, &oracle, 1_100).unwrap();
+        assert_eq!(check_kill_band(&slab), Ok(()));
+    }
+
+    #[test]
+    fn test_roundtrip_guard_resets_each_batch() {
+        let mut slab = slab();
+        let oracle = PriceOracle::new([0; 32], INSTRUMENT, 100 * S, 0);
+        let taker: Pubkey = [4; 32];
+
+        assert_eq!(roundtrip_allowance(&slab, &taker, Side::Buy), Ok(u64::MAX));
+        slab.aggressors.record(&taker, slab.batch.epoch, Side::Buy, S as u64, 0);
+        assert_eq!(
+            roundtrip_allowance(&slab, &taker, Side::Sell),
+            Err(PercolatorError::RoundtripDetected)
+        );
+
+        process_batch_open(&mut slab, &ORACLE, &oracle, 1_000).unwrap();
+        assert_eq!(roundtrip_allowance(&slab, &taker, Side::Sell), Ok(u64::MAX));
+        assert_eq!(slab.aggressors.find(&taker), None);
+    }
+}
@@ -1,49 +1,31 @@
 //! Commit fill instruction - v0 single-instruction orderbook interaction
 
-use crate::state::{SlabState, FillReceipt, QuoteCache, QuoteLevel};
+use crate::state::{SlabState, FillReceipt, MatchResult};
 use percolator_common::*;
 use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 
-/// Side of the order
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Side {
-    Buy = 0,
-    Sell = 1,
-}
-
-/// Update quote cache after a fill (v0 stub)
-/// In v1, this will reflect actual book state after matching
-fn update_quote_cache_after_fill(
-    cache: &mut QuoteCache,
-    seqno: u32,
-    side: Side,
-    px: i64,
-    qty: i64,
-) {
-    // For v0, simulate liquidity by adding fill as a quote level
-    // This proves the cache update mechanism works
-    let level = QuoteLevel { px, avail_qty: qty };
-    match side {
-        Side::Buy => {
-            // Buy removes ask liquidity, add to bids
-            cache.update(seqno, &[level], &[]);
-        }
-        Side::Sell => {
-            // Sell removes bid liquidity, add to asks
-            cache.update(seqno, &[], &[level]);
-        }
-    }
-}
+/// Side of the order (shared with the book)
+pub use percolator_common::Side;
 
 /// Process commit_fill instruction (v0 - atomic fill)
 ///
 /// This is the single CPI endpoint for v0. Router calls this to fill orders.
+/// The taker is matched against resting orders in price-time priority up
+/// to `limit_px`; the receipt reports the actual filled qty and VWAP,
+/// which may be less than requested (zero if nothing crosses). Fills are
+/// rejected while the mark is outside the batch kill band, and the taker
+/// quantity is clipped to what the roundtrip guard allows this batch.
+/// Router liquidation fills bypass the guard; the flag is trusted because
+/// the router authority must sign.
 ///
 /// # Arguments
 /// * `slab` - The slab state account
+/// * `slab_id` - Slab account pubkey (for the fill event)
 /// * `receipt_account` - Account to write fill receipt
 /// * `router_signer` - Router authority (must match slab.header.router_id)
+/// * `expected_seqno` - Slab seqno the router priced off (TOCTOU check)
+/// * `taker` - Aggressor account (roundtrip ledger key)
+/// * `liquidation` - Router liquidation fill (exempt from the roundtrip guard)
 /// * `side` - Buy or Sell
 /// * `qty` - Desired quantity (1e6 scale, positive)
 /// * `limit_px` - Worst acceptable price (1e6 scale)
@@ -53,9 +35,12 @@ fn update_quote_cache_after_fill(
 /// * Updates slab state (book, seqno, quote_cache)
 pub fn process_commit_fill(
     slab: &mut SlabState,
+    slab_id: &Pubkey,
     receipt_account: &AccountInfo,
     router_signer: &Pubkey,
     expected_seqno: u32,
+    taker: &Pubkey,
+    liquidation: bool,
     side: Side,
     qty: i64,
     limit_px: i64,
@@ -82,32 +67,107 @@ pub fn process_commit_fill(
         return Err(PercolatorError::InvalidPrice);
     }
 
+    check_kill_band(slab)?;
+    // Liquidations are never clipped or recorded
+    let max_qty = if liquidation {
+        u64::MAX
+    } else {
+        roundtrip_allowance(slab, taker, side)?
+    };
+
     // Capture seqno at start
     let seqno_start = slab.header.seqno;
 
-    // v0 Matching: Simulate instant fill at limit price
-    // In v1, this will match against real book liquidity
-    let filled_qty = qty;
-    let vwap_px = limit_px;
+    // Match against resting orders up to the limit price
+    let jit_cutoff_ms = slab.batch.jit_cutoff_ms();
+    let qty = (qty as u64).min(max_qty);
+    let result = slab.book.match_taker(side, qty, limit_px as u64, jit_cutoff_ms);
+    if !liquidation {
+        record_aggressor(slab, taker, side, &result);
+    }
+    write_fill(slab, slab_id, receipt_account, seqno_start, side, &result)?;
+
+    msg!("CommitFill executed successfully");
+    Ok(())
+}
 
-    // Calculate notional: qty * contract_size * price / 1e6
-    // For v0, simplified: qty * price / 1e6 (assuming contract_size normalized)
-    let notional = (filled_qty as i128 * limit_px as i128 / 1_000_000) as i64;
+/// Reject fills while the mark has moved beyond the kill band since the
+/// batch opened
+pub(crate) fn check_kill_band(slab: &SlabState) -> Result<(), PercolatorError> {
+    if !slab.batch.within_kill_band(slab.header.mark_px) {
+        msg!("Error: Mark moved beyond the kill band this batch");
+        return Err(PercolatorError::KillBandExceeded);
+    }
+    Ok(())
+}
+
+/// Most the taker may take on `side` this batch without roundtripping
+/// past the slab's limit
+pub(crate) fn roundtrip_allowance(slab: &SlabState, taker: &Pubkey, side: Side) -> Result<u64, PercolatorError> {
+    let max_qty = slab.aggressors.max_qty(taker, side, slab.batch.roundtrip_max_qty);
+    if max_qty == 0 {
+        msg!("Error: Same-batch roundtrip limit reached");
+        return Err(PercolatorError::RoundtripDetected);
+    }
+    Ok(max_qty)
+}
+
+/// Record a taker fill in the aggressor ledger (empty fills are skipped)
+pub(crate) fn record_aggressor(slab: &mut SlabState, taker: &Pubkey, side: Side, result: &MatchResult) {
+    let epoch = slab.batch.epoch;
+    slab.aggressors.record(taker, epoch, side, result.filled_qty, result.notional);
+}
+
+/// Write the fill receipt for a matched taker and publish the book change
+///
+/// Shared by commit_fill and reservation commits: computes VWAP, notional
+/// and fees from the match, writes the receipt and, if anything filled,
+/// bumps seqno, republishes the quote cache and emits the fill event.
+/// Just-in-time DLP liquidity forfeits its maker rebate.
+pub(crate) fn write_fill(
+    slab: &mut SlabState,
+    slab_id: &Pubkey,
+    receipt_account: &AccountInfo,
+    seqno_start: u32,
+    side: Side,
+    result: &MatchResult,
+) -> Result<(), PercolatorError> {
+    let filled_qty = result.filled_qty as i64;
+    let vwap_px = calculate_vwap(result.notional, result.filled_qty) as i64;
+
+    // Calculate notional: sum(qty * price) / 1e6
+    // For v0, simplified: contract_size assumed normalized
+    let notional = (result.notional / 1_000_000) as i64;
 
     // Calculate fee: notional * taker_fee_bps / 10000
     let fee = (notional as i128 * slab.header.taker_fee_bps as i128 / 10_000) as i64;
-
-    // Update quote cache to reflect this fill
-    // For v0, add this as liquidity at the fill price
-    update_quote_cache_after_fill(&mut slab.quote_cache, slab.header.seqno + 1, side, limit_px, filled_qty);
+    // Maker fee (negative = rebate) on the same notional, minus any
+    // rebate on JIT fills
+    let maker_fee_notional = if slab.header.maker_fee_bps < 0 {
+        notional - (result.jit_notional / 1_000_000) as i64
+    } else {
+        notional
+    };
+    let maker_fee = (maker_fee_notional as i128 * slab.header.maker_fee_bps as i128 / 10_000) as i64;
 
     // Write receipt
     let receipt = unsafe { percolator_common::borrow_account_data_mut::<FillReceipt>(receipt_account)? };
-    receipt.write(seqno_start, filled_qty, vwap_px, notional, fee);
+    receipt.write(seqno_start, filled_qty, vwap_px, notional, fee, maker_fee);
 
-    // Increment seqno (book changed)
-    slab.header.increment_seqno();
+    // Book changed: bump seqno and republish the top of book
+    if filled_qty > 0 {
+        slab.publish_book_change();
+
+        emit(&Event::Fill {
+            venue: *slab_id,
+            account: slab.header.lp_owner,
+            side: side as u8,
+            qty: filled_qty,
+            price: vwap_px,
+            fee,
+            seqno: seqno_start,
+        });
+    }
 
-    msg!("CommitFill executed successfully");
     Ok(())
 }
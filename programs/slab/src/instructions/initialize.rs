@@ -6,7 +6,7 @@ use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 
 /// Process initialize instruction for slab (v0 minimal)
 ///
-/// Initializes the ~4KB slab state account with header, quote cache, and book.
+/// Initializes the ~11.5KB slab state account with header, quote cache, and book.
 /// This is called once during slab deployment for each market.
 ///
 /// # Arguments
@@ -17,6 +17,7 @@ use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
 /// * `instrument` - Shared instrument ID (agreed with router)
 /// * `mark_px` - Initial mark price from oracle (1e6 scale)
 /// * `taker_fee_bps` - Taker fee (basis points)
+/// * `maker_fee_bps` - Maker fee (basis points, negative = rebate)
 /// * `contract_size` - Contract size (1e6 scale)
 /// * `bump` - PDA bump seed
 pub fn process_initialize_slab(
@@ -27,13 +28,14 @@ pub fn process_initialize_slab(
     instrument: Pubkey,
     mark_px: i64,
     taker_fee_bps: i64,
+    maker_fee_bps: i64,
     contract_size: i64,
     bump: u8,
 ) -> Result<(), PercolatorError> {
     // For v0, we skip PDA derivation and just verify ownership
     // In production, we would verify the account is a valid PDA
 
-    // Verify account size (~4KB for v0)
+    // Verify account size (~11.5KB for v0)
     let data = slab_account.try_borrow_data()
         .map_err(|_| PercolatorError::InvalidAccount)?;
 
@@ -54,7 +56,7 @@ pub fn process_initialize_slab(
     let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
 
     // Initialize header with v0 parameters
-    let header = SlabHeader::new(
+    let mut header = SlabHeader::new(
         *program_id,
         lp_owner,
         router_id,
@@ -64,9 +66,10 @@ pub fn process_initialize_slab(
         contract_size,
         bump,
     );
+    header.maker_fee_bps = maker_fee_bps;
 
-    // Create new slab state (initializes quote_cache and book automatically)
-    *slab = SlabState::new(header);
+    // Initialize every area in place (the slab does not fit on the stack)
+    slab.init(header);
 
     msg!("Slab initialized successfully");
     Ok(())
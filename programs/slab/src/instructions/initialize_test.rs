@@ -44,6 +44,7 @@ mod initialize_v0_tests {
         assert_eq!(header.instrument, instrument);
         assert_eq!(header.mark_px, mark_px);
         assert_eq!(header.taker_fee_bps, taker_fee_bps);
+        assert_eq!(header.maker_fee_bps, 0);
         assert_eq!(header.contract_size, contract_size);
         assert_eq!(header.bump, bump);
 
@@ -53,13 +54,14 @@ mod initialize_v0_tests {
 
     #[test]
     fn test_slab_state_size_v0() {
-        // v0: SlabState should be ~4KB (not 7MB like complex design)
+        // v0: SlabState should be ~11.5KB (not 7MB like complex design)
         use core::mem::size_of;
         let actual_size = size_of::<SlabState>();
 
-        // Should be around 4KB for v0
+        // ~11.5KB with batch state and the aggressor ledger; initialized in
+        // place, so not limited by the 4KB SBF stack
         assert!(actual_size > 3_000, "SlabState too small: {} bytes", actual_size);
-        assert!(actual_size < 5_000, "SlabState too large: {} bytes", actual_size);
+        assert!(actual_size < 12_288, "SlabState too large: {} bytes", actual_size);
     }
 
     #[test]
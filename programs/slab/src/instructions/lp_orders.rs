@@ -0,0 +1,326 @@
+//! LP order management - place, cancel, replace and mass-cancel resting orders
+//!
+//! All instructions are signed by the slab's LP owner. Every book change
+//! bumps `seqno` and rebuilds the quote cache so routers pricing off a
+//! stale snapshot are rejected at commit time.
+//!
+//! DLP orders are live immediately. Regular orders rest pending and only
+//! become matchable at the next batch epoch, so they cannot be used to
+//! sandwich flow inside the batch they were posted in. Promotion needs the
+//! `batch_open` crank, so regular orders are refused until the slab has an
+//! oracle configured.
+
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::{msg, pubkey::Pubkey};
+
+/// Verify the signer is the slab's LP owner
+pub(crate) fn validate_lp_owner(slab: &SlabState, lp_signer: &Pubkey) -> Result<(), PercolatorError> {
+    if &slab.header.lp_owner != lp_signer {
+        msg!("Error: Signer is not the LP owner");
+        return Err(PercolatorError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Validate price/qty against the slab's tick and lot sizes
+fn validate_order_params(slab: &SlabState, price: i64, qty: i64) -> Result<(), PercolatorError> {
+    if price <= 0 {
+        msg!("Error: Price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+    if qty <= 0 {
+        msg!("Error: Quantity must be positive");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if slab.header.tick > 0 && !is_tick_aligned(price as u64, slab.header.tick as u64) {
+        msg!("Error: Price not aligned to tick");
+        return Err(PercolatorError::PriceNotAligned);
+    }
+    if slab.header.lot > 0 && !is_lot_aligned(qty as u64, slab.header.lot as u64) {
+        msg!("Error: Quantity not aligned to lot");
+        return Err(PercolatorError::QuantityNotAligned);
+    }
+    Ok(())
+}
+
+/// Insert an order into the book without publishing the change
+///
+/// Post-only orders that would cross are rejected. A GTC order that
+/// crosses the LP's own resting orders cancels them (self-trade
+/// prevention, newest quote wins) and then rests. Reserved quantity is
+/// left to its holds. Regular orders require a configured oracle, without
+/// which no batch can open to promote them.
+fn insert_order(
+    slab: &mut SlabState,
+    maker_class: MakerClass,
+    side: Side,
+    tif: TimeInForce,
+    price: i64,
+    qty: i64,
+    now_ms: u64,
+) -> Result<u64, PercolatorError> {
+    validate_order_params(slab, price, qty)?;
+    if maker_class == MakerClass::REG && slab.batch.oracle == Pubkey::default() {
+        msg!("Error: Regular orders require a configured oracle");
+        return Err(PercolatorError::InvalidMakerClass);
+    }
+
+    match tif {
+        TimeInForce::PostOnly => {
+            if slab.book.would_cross(side, price as u64) {
+                msg!("Error: Post-only order would cross the book");
+                return Err(PercolatorError::PostOnlyWouldCross);
+            }
+        }
+        TimeInForce::GTC => {
+            while let Some(idx) = slab.book.first_crossing(side, price as u64) {
+                slab.book.cancel(idx).map_err(|_| PercolatorError::BookCorrupted)?;
+            }
+        }
+        _ => {
+            msg!("Error: Resting orders must be GTC or post-only");
+            return Err(PercolatorError::InvalidTimeInForce);
+        }
+    }
+
+    let eligible_epoch = match maker_class {
+        MakerClass::DLP => 0,
+        MakerClass::REG => slab.batch.epoch.wrapping_add(1),
+    };
+    let (order_id, _) = slab
+        .book
+        .insert_class(maker_class, eligible_epoch, side, tif, price as u64, qty as u64, now_ms)
+        .map_err(|_| {
+            msg!("Error: Book is full");
+            PercolatorError::PoolFull
+        })?;
+
+    Ok(order_id)
+}
+
+/// Process place_order instruction
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `lp_signer` - LP owner (must match slab.header.lp_owner)
+/// * `maker_class` - DLP (live now) or REG (pending until the next epoch)
+/// * `side` - Buy or Sell
+/// * `tif` - GTC or PostOnly
+/// * `price` - Limit price (1e6 scale, tick-aligned)
+/// * `qty` - Quantity (1e6 scale, lot-aligned)
+/// * `now_ms` - Current time for time priority
+///
+/// # Returns
+/// * The new order ID
+pub fn process_place_order(
+    slab: &mut SlabState,
+    lp_signer: &Pubkey,
+    maker_class: MakerClass,
+    side: Side,
+    tif: TimeInForce,
+    price: i64,
+    qty: i64,
+    now_ms: u64,
+) -> Result<u64, PercolatorError> {
+    validate_lp_owner(slab, lp_signer)?;
+
+    let order_id = insert_order(slab, maker_class, side, tif, price, qty, now_ms)?;
+    slab.publish_book_change();
+
+    msg!("PlaceOrder executed successfully");
+    Ok(order_id)
+}
+
+/// Process cancel_order instruction
+///
+/// Quantity locked by a reservation stays in the book until the hold is
+/// committed, cancelled or expires.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `lp_signer` - LP owner (must match slab.header.lp_owner)
+/// * `order_id` - Order to cancel
+pub fn process_cancel_order(
+    slab: &mut SlabState,
+    lp_signer: &Pubkey,
+    order_id: u64,
+) -> Result<(), PercolatorError> {
+    validate_lp_owner(slab, lp_signer)?;
+
+    let idx = slab.book.find_order(order_id).ok_or_else(|| {
+        msg!("Error: Order not found");
+        PercolatorError::OrderNotFound
+    })?;
+    slab.book.cancel(idx).map_err(|_| PercolatorError::BookCorrupted)?;
+    slab.publish_book_change();
+
+    msg!("CancelOrder executed successfully");
+    Ok(())
+}
+
+/// Process replace_order instruction
+///
+/// Cancels the order and places a new one on the same side with the same
+/// time in force and maker class. The replacement loses time priority and
+/// gets a new ID; a regular replacement waits for the next epoch again.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `lp_signer` - LP owner (must match slab.header.lp_owner)
+/// * `order_id` - Order to replace
+/// * `new_price` - New limit price (1e6 scale, tick-aligned)
+/// * `new_qty` - New quantity (1e6 scale, lot-aligned)
+/// * `now_ms` - Current time for time priority
+///
+/// # Returns
+/// * The replacement order ID
+pub fn process_replace_order(
+    slab: &mut SlabState,
+    lp_signer: &Pubkey,
+    order_id: u64,
+    new_price: i64,
+    new_qty: i64,
+    now_ms: u64,
+) -> Result<u64, PercolatorError> {
+    validate_lp_owner(slab, lp_signer)?;
+
+    let idx = slab.book.find_order(order_id).ok_or_else(|| {
+        msg!("Error: Order not found");
+        PercolatorError::OrderNotFound
+    })?;
+    let Order { side, tif, maker_class, .. } = slab.book.orders[idx as usize];
+
+    // Validate before touching the book so a bad replace leaves the order intact
+    validate_order_params(slab, new_price, new_qty)?;
+    slab.book.cancel(idx).map_err(|_| PercolatorError::BookCorrupted)?;
+
+    let new_order_id = insert_order(slab, maker_class, side, tif, new_price, new_qty, now_ms)?;
+    slab.publish_book_change();
+
+    msg!("ReplaceOrder executed successfully");
+    Ok(new_order_id)
+}
+
+/// Process mass_cancel instruction
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `lp_signer` - LP owner (must match slab.header.lp_owner)
+///
+/// # Returns
+/// * Number of orders cancelled
+pub fn process_mass_cancel(
+    slab: &mut SlabState,
+    lp_signer: &Pubkey,
+) -> Result<u32, PercolatorError> {
+    validate_lp_owner(slab, lp_signer)?;
+
+    let cancelled = slab.book.clear();
+    if cancelled > 0 {
+        slab.publish_book_change();
+    }
+
+    msg!("MassCancel executed successfully");
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SlabHeader;
+
+    const S: i64 = 1_000_000;
+
+    fn lp() -> Pubkey {
+        Pubkey::from([7; 32])
+    }
+
+    fn slab() -> SlabState {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            lp(),
+            Pubkey::default(),
+            Pubkey::default(),
+            100 * S,
+            20,
+            S,
+            255,
+        );
+        SlabState::new(header)
+    }
+
+    #[test]
+    fn test_place_updates_seqno_and_cache() {
+        let mut slab = slab();
+        process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S, 2 * S, 0).unwrap();
+        process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Sell, TimeInForce::PostOnly, 101 * S, S, 0).unwrap();
+
+        assert_eq!(slab.header.seqno, 2);
+        assert_eq!(slab.quote_cache.seqno_snapshot, 2);
+        assert_eq!(slab.quote_cache.best_bids[0].px, 99 * S);
+        assert_eq!(slab.quote_cache.best_bids[0].avail_qty, 2 * S);
+        assert_eq!(slab.quote_cache.best_asks[0].px, 101 * S);
+    }
+
+    #[test]
+    fn test_place_rejects_non_owner_and_misaligned() {
+        let mut slab = slab();
+        assert_eq!(
+            process_place_order(&mut slab, &Pubkey::default(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S, S, 0),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(
+            process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S + 1, S, 0),
+            Err(PercolatorError::PriceNotAligned)
+        );
+        assert_eq!(
+            process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S, S / 2, 0),
+            Err(PercolatorError::QuantityNotAligned)
+        );
+        assert_eq!(
+            process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::IOC, 99 * S, S, 0),
+            Err(PercolatorError::InvalidTimeInForce)
+        );
+        assert_eq!(slab.header.seqno, 0);
+    }
+
+    #[test]
+    fn test_post_only_rejects_cross_gtc_cancels_crossed() {
+        let mut slab = slab();
+        process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Sell, TimeInForce::GTC, 100 * S, S, 0).unwrap();
+
+        assert_eq!(
+            process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::PostOnly, 100 * S, S, 0),
+            Err(PercolatorError::PostOnlyWouldCross)
+        );
+
+        process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 100 * S, S, 0).unwrap();
+        assert_eq!(slab.book.order_count, 1);
+        assert_eq!(slab.quote_cache.best_asks[0].avail_qty, 0);
+        assert_eq!(slab.quote_cache.best_bids[0].px, 100 * S);
+    }
+
+    #[test]
+    fn test_cancel_replace_and_mass_cancel() {
+        let mut slab = slab();
+        let id = process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 99 * S, S, 0).unwrap();
+        let other = process_place_order(&mut slab, &lp(), MakerClass::DLP, Side::Buy, TimeInForce::GTC, 98 * S, S, 0).unwrap();
+
+        let new_id = process_replace_order(&mut slab, &lp(), id, 97 * S, 3 * S, 1).unwrap();
+        assert_ne!(new_id, id);
+        assert_eq!(slab.book.find_order(id), None);
+        assert_eq!(slab.quote_cache.best_bids[0].px, 98 * S);
+        assert_eq!(slab.quote_cache.best_bids[1].avail_qty, 3 * S);
+
+        process_cancel_order(&mut slab, &lp(), other).unwrap();
+        assert_eq!(
+            process_cancel_order(&mut slab, &lp(), other),
+            Err(PercolatorError::OrderNotFound)
+        );
+
+        assert_eq!(process_mass_cancel(&mut slab, &lp()).unwrap(), 1);
+        assert_eq!(slab.book.order_count, 0);
+        assert_eq!(slab.quote_cache.best_bids[0].avail_qty, 0);
+    }
+}
@@ -1,8 +1,14 @@
 pub mod initialize;
 pub mod commit_fill;
+pub mod lp_orders;
+pub mod reserve;
+pub mod batch;
 
 pub use initialize::*;
 pub use commit_fill::*;
+pub use lp_orders::*;
+pub use reserve::*;
+pub use batch::*;
 
 /// Instruction discriminator
 #[repr(u8)]
@@ -12,4 +18,24 @@ pub enum SlabInstruction {
     Initialize = 0,
     /// Commit fill (v0 - single instruction for fills)
     CommitFill = 1,
+    /// Place a resting LP order
+    PlaceOrder = 2,
+    /// Cancel a resting LP order
+    CancelOrder = 3,
+    /// Replace a resting LP order (cancel + place)
+    ReplaceOrder = 4,
+    /// Cancel all resting LP orders
+    MassCancel = 5,
+    /// Reserve liquidity for a later commit (two-phase)
+    Reserve = 6,
+    /// Commit a reservation at its captured maker prices
+    Commit = 7,
+    /// Cancel a reservation and release its liquidity
+    Cancel = 8,
+    /// Open the next batch epoch and promote pending orders
+    BatchOpen = 9,
+    /// Refresh the mark from the oracle
+    UpdateMark = 10,
+    /// Set the oracle and anti-toxicity knobs
+    ConfigureBatch = 11,
 }
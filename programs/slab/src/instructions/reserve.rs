@@ -0,0 +1,272 @@
+//! Reserve, commit and cancel - two-phase execution for the router
+//!
+//! Reserve locks liquidity for a taker and reports its worst price, VWAP
+//! and maximum charge, so the router can price a large order across slabs
+//! before committing anywhere. Commit fills the hold at the maker prices
+//! captured at reserve; cancel (or expiry) releases it. Every hold is
+//! bound to the 32-byte commitment the router supplied at reserve, and
+//! only the same commitment can commit or cancel it. The router commits
+//! holds to the taker's pubkey, so the commitment also keys the taker in
+//! the aggressor ledger.
+
+use crate::instructions::{check_kill_band, record_aggressor, roundtrip_allowance, write_fill};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::{account_info::AccountInfo, msg, pubkey::Pubkey};
+
+/// Verify the router signer
+fn validate_router(slab: &SlabState, router_signer: &Pubkey) -> Result<(), PercolatorError> {
+    if &slab.header.router_id != router_signer {
+        msg!("Error: Invalid router signer");
+        return Err(PercolatorError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Find an open hold and check the caller's commitment
+fn find_hold(slab: &SlabState, hold_id: u64, commitment: &[u8; 32]) -> Result<u32, PercolatorError> {
+    let idx = slab.reservations.find(hold_id).ok_or_else(|| {
+        msg!("Error: Reservation not found");
+        PercolatorError::ReservationNotFound
+    })?;
+    if &slab.reservations.reservations[idx as usize].commitment_hash != commitment {
+        msg!("Error: Commitment does not match reservation");
+        return Err(PercolatorError::InvalidCommitment);
+    }
+    Ok(idx)
+}
+
+/// Process reserve instruction
+///
+/// Expired holds are released first. The hold may cover less than `qty`
+/// if crossing liquidity runs out; its lifetime is clamped to
+/// `MAX_CAP_TTL_MS`, since a hold cannot outlive the cap that pays for it.
+/// A commitment may hold at most `MAX_HOLDS_PER_COMMITMENT` open
+/// reservations, so one user cannot take every hold slot.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `receipt_account` - Account to write the reserve receipt
+/// * `router_signer` - Router authority (must match slab.header.router_id)
+/// * `expected_seqno` - Slab seqno the router priced off (TOCTOU check)
+/// * `side` - Taker side
+/// * `qty` - Quantity to reserve (1e6 scale, positive)
+/// * `limit_px` - Worst acceptable price (1e6 scale)
+/// * `ttl_ms` - Hold lifetime (milliseconds)
+/// * `route_id` - Router route ID
+/// * `commitment` - Tag that must be presented to commit or cancel
+/// * `now_ms` - Current time (milliseconds)
+///
+/// # Returns
+/// * The hold ID; writes ReserveReceipt to receipt_account
+pub fn process_reserve(
+    slab: &mut SlabState,
+    receipt_account: &AccountInfo,
+    router_signer: &Pubkey,
+    expected_seqno: u32,
+    side: Side,
+    qty: i64,
+    limit_px: i64,
+    ttl_ms: u64,
+    route_id: u64,
+    commitment: [u8; 32],
+    now_ms: u64,
+) -> Result<u64, PercolatorError> {
+    validate_router(slab, router_signer)?;
+
+    if slab.header.seqno != expected_seqno {
+        msg!("Error: Seqno mismatch - book changed since read");
+        return Err(PercolatorError::SeqnoMismatch);
+    }
+    if qty <= 0 {
+        msg!("Error: Quantity must be positive");
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if limit_px <= 0 {
+        msg!("Error: Limit price must be positive");
+        return Err(PercolatorError::InvalidPrice);
+    }
+    if ttl_ms == 0 {
+        msg!("Error: Reservation TTL must be positive");
+        return Err(PercolatorError::InvalidReservation);
+    }
+
+    slab.reservations.expire(&mut slab.book, now_ms);
+
+    let seqno_start = slab.header.seqno;
+    let expiry_ms = now_ms.saturating_add(ttl_ms.min(MAX_CAP_TTL_MS));
+    let idx = slab.reservations.reserve(
+        &mut slab.book,
+        side,
+        qty as u64,
+        limit_px as u64,
+        slab.header.taker_fee_bps,
+        route_id,
+        commitment,
+        seqno_start as u64,
+        expiry_ms,
+    ).inspect_err(|_| msg!("Error: Reservation failed"))?;
+    let hold = slab.reservations.reservations[idx as usize];
+
+    let receipt = unsafe { borrow_account_data_mut::<ReserveReceipt>(receipt_account)? };
+    let fill = HoldFill {
+        qty: hold.qty as i64,
+        vwap_px: hold.vwap_px as i64,
+        worst_px: hold.worst_px as i64,
+        max_charge: hold.max_charge as i64,
+    };
+    receipt.write(seqno_start, hold.hold_id, fill, hold.expiry_ms);
+
+    // Reserved quantity is no longer available: republish the top of book
+    slab.publish_book_change();
+
+    msg!("Reserve executed successfully");
+    Ok(hold.hold_id)
+}
+
+/// Process commit instruction
+///
+/// Fills every slice of the hold at its maker price and writes a
+/// FillReceipt exactly like commit_fill. No seqno check is needed: the
+/// reserved quantity could not change since reserve. Like commit_fill, it
+/// is rejected while the mark is outside the batch kill band; a hold is
+/// never clipped, so it is rejected if it would exceed the roundtrip
+/// limit.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `slab_id` - Slab account pubkey (for the fill event)
+/// * `receipt_account` - Account to write fill receipt
+/// * `router_signer` - Router authority (must match slab.header.router_id)
+/// * `hold_id` - Hold to commit
+/// * `side` - Taker side (must match the hold)
+/// * `commitment` - Commitment the hold was reserved with
+/// * `now_ms` - Current time (milliseconds)
+pub fn process_commit(
+    slab: &mut SlabState,
+    slab_id: &Pubkey,
+    receipt_account: &AccountInfo,
+    router_signer: &Pubkey,
+    hold_id: u64,
+    side: Side,
+    commitment: &[u8; 32],
+    now_ms: u64,
+) -> Result<(), PercolatorError> {
+    validate_router(slab, router_signer)?;
+
+    let idx = find_hold(slab, hold_id, commitment)?;
+    let hold = slab.reservations.reservations[idx as usize];
+    if hold.side != side {
+        msg!("Error: Side does not match reservation");
+        return Err(PercolatorError::InvalidReservation);
+    }
+    if now_ms >= hold.expiry_ms {
+        msg!("Error: Reservation expired");
+        return Err(PercolatorError::ReservationExpired);
+    }
+
+    check_kill_band(slab)?;
+    if hold.qty > roundtrip_allowance(slab, commitment, side)? {
+        msg!("Error: Hold exceeds the same-batch roundtrip limit");
+        return Err(PercolatorError::RoundtripDetected);
+    }
+
+    let seqno_start = slab.header.seqno;
+    let jit_cutoff_ms = slab.batch.jit_cutoff_ms();
+    let result = slab.reservations.commit(&mut slab.book, idx, jit_cutoff_ms);
+    record_aggressor(slab, commitment, side, &result);
+    write_fill(slab, slab_id, receipt_account, seqno_start, side, &result)?;
+
+    msg!("Commit executed successfully");
+    Ok(())
+}
+
+/// Process cancel instruction
+///
+/// Idempotent: a hold that was already committed, cancelled or swept
+/// after expiry is reported as not released rather than failing.
+///
+/// # Arguments
+/// * `slab` - The slab state account
+/// * `router_signer` - Router authority (must match slab.header.router_id)
+/// * `hold_id` - Hold to cancel
+/// * `commitment` - Commitment the hold was reserved with
+///
+/// # Returns
+/// * Whether a hold was released
+pub fn process_cancel(
+    slab: &mut SlabState,
+    router_signer: &Pubkey,
+    hold_id: u64,
+    commitment: &[u8; 32],
+) -> Result<bool, PercolatorError> {
+    validate_router(slab, router_signer)?;
+
+    let idx = match find_hold(slab, hold_id, commitment) {
+        Ok(idx) => idx,
+        Err(PercolatorError::ReservationNotFound) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    slab.reservations.release(&mut slab.book, idx);
+    slab.publish_book_change();
+
+    msg!("Cancel executed successfully");
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::process_place_order;
+    use crate::state::SlabHeader;
+
+    const S: i64 = 1_000_000;
+    const ROUTER: Pubkey = [9; 32];
+    const LP: Pubkey = [7; 32];
+    const TAG: [u8; 32] = [1; 32];
+
+    fn slab_with_hold() -> (SlabState, u64) {
+        let header = SlabHeader::new([0; 32], LP, ROUTER, [0; 32], 100 * S, 10, S, 255);
+        let mut slab = SlabState::new(header);
+        process_place_order(&mut slab, &LP, MakerClass::DLP, Side::Sell, TimeInForce::GTC, 100 * S, 2 * S, 0).unwrap();
+
+        let idx = slab
+            .reservations
+            .reserve(&mut slab.book, Side::Buy, S as u64, 100 * S as u64, 10, 5, TAG, 1, 1_000)
+            .unwrap();
+        let hold_id = slab.reservations.reservations[idx as usize].hold_id;
+        (slab, hold_id)
+    }
+
+    #[test]
+    fn test_cancel_requires_router_and_commitment() {
+        let (mut slab, hold_id) = slab_with_hold();
+
+        assert_eq!(process_cancel(&mut slab, &LP, hold_id, &TAG), Err(PercolatorError::Unauthorized));
+        assert_eq!(
+            process_cancel(&mut slab, &ROUTER, hold_id, &[2; 32]),
+            Err(PercolatorError::InvalidCommitment)
+        );
+
+        let seqno = slab.header.seqno;
+        assert_eq!(process_cancel(&mut slab, &ROUTER, hold_id, &TAG), Ok(true));
+        assert_eq!(slab.header.seqno, seqno + 1);
+        assert_eq!(slab.quote_cache.best_asks[0].avail_qty, 2 * S);
+
+        // Idempotent once released
+        assert_eq!(process_cancel(&mut slab, &ROUTER, hold_id, &TAG), Ok(false));
+    }
+
+    #[test]
+    fn test_lp_cancel_keeps_reserved_part_until_release() {
+        let (mut slab, hold_id) = slab_with_hold();
+        let order_id = slab.book.orders[slab.book.asks_head as usize].order_id;
+
+        crate::instructions::process_cancel_order(&mut slab, &LP, order_id).unwrap();
+        assert_eq!(slab.book.order_count, 1);
+        assert_eq!(slab.quote_cache.best_asks[0].avail_qty, 0);
+
+        process_cancel(&mut slab, &ROUTER, hold_id, &TAG).unwrap();
+        assert_eq!(slab.book.order_count, 0);
+    }
+}
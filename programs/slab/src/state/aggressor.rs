@@ -0,0 +1,183 @@
+//! Aggressor ledger - per-batch taker volume for the roundtrip guard
+//!
+//! Every filled taker leg is recorded against its account for the current
+//! batch.
+//! An account that has both bought and sold in the same batch has
+//! roundtripped the overlap (`min(buy_qty, sell_qty)`); legs that would
+//! push the overlap past the slab's limit are clipped or rejected, which
+//! takes the profit out of sandwiching flow inside one batch. Maker fills
+//! and liquidations are never recorded. The ledger is cleared when a
+//! batch opens.
+
+use percolator_common::{AggressorEntry, Side};
+use pinocchio::pubkey::Pubkey;
+
+/// Maximum number of aggressors tracked per batch
+///
+/// When full, the smallest entry is evicted rather than rejecting the
+/// fill, so flushing a large taker out of the ledger takes this many
+/// bigger fills from distinct accounts within one batch.
+pub const AGGRESSOR_CAPACITY: usize = 64;
+
+/// Aggressor ledger stored in the slab account
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AggressorLedger {
+    /// Aggressor account of each entry
+    pub accounts: [Pubkey; AGGRESSOR_CAPACITY],
+    /// Per-batch volume of each entry
+    pub entries: [AggressorEntry; AGGRESSOR_CAPACITY],
+}
+
+impl AggressorLedger {
+    /// Create an empty ledger
+    pub fn new() -> Self {
+        Self {
+            accounts: [Pubkey::default(); AGGRESSOR_CAPACITY],
+            entries: [AggressorEntry::default(); AGGRESSOR_CAPACITY],
+        }
+    }
+
+    /// Forget every aggressor (on batch open and slab init)
+    pub fn clear(&mut self) {
+        self.accounts.fill(Pubkey::default());
+        self.entries.fill(AggressorEntry::default());
+    }
+
+    /// Find the entry for `account`
+    pub fn find(&self, account: &Pubkey) -> Option<u32> {
+        self.entries
+            .iter()
+            .zip(self.accounts.iter())
+            .position(|(e, a)| e.used && a == account)
+            .map(|idx| idx as u32)
+    }
+
+    /// Largest taker quantity on `side` that keeps the account's same-batch
+    /// roundtrip overlap within `max_overlap` (untracked accounts are
+    /// unlimited)
+    pub fn max_qty(&self, account: &Pubkey, side: Side, max_overlap: u64) -> u64 {
+        let Some(slot) = self.find(account) else {
+            return u64::MAX;
+        };
+        let entry = &self.entries[slot as usize];
+        let (same, opposite) = match side {
+            Side::Buy => (entry.buy_qty, entry.sell_qty),
+            Side::Sell => (entry.sell_qty, entry.buy_qty),
+        };
+
+        // Overlap is capped by the opposite leg: only growth beyond the
+        // limit on this leg has to be clipped
+        if opposite <= max_overlap {
+            return u64::MAX;
+        }
+        max_overlap.saturating_sub(same)
+    }
+
+    /// Record a taker fill in `epoch` (`notional` in 1e12 scale, as in
+    /// `MatchResult`)
+    ///
+    /// Empty fills are not recorded. A new account takes a free entry or,
+    /// when the ledger is full, evicts the entry with the least one-sided
+    /// volume, which is the least an evicted taker could roundtrip.
+    pub fn record(&mut self, account: &Pubkey, epoch: u16, side: Side, qty: u64, notional: u128) {
+        if qty == 0 {
+            return;
+        }
+        let slot = match self.find(account) {
+            Some(slot) => slot as usize,
+            None => self.allocate(account, epoch),
+        };
+
+        let entry = &mut self.entries[slot];
+        match side {
+            Side::Buy => {
+                entry.buy_qty = entry.buy_qty.saturating_add(qty);
+                entry.buy_notional = entry.buy_notional.saturating_add(notional);
+            }
+            Side::Sell => {
+                entry.sell_qty = entry.sell_qty.saturating_add(qty);
+                entry.sell_notional = entry.sell_notional.saturating_add(notional);
+            }
+        }
+    }
+
+    /// Take a free entry for `account`, evicting the smallest if full
+    fn allocate(&mut self, account: &Pubkey, epoch: u16) -> usize {
+        let idx = self.entries.iter().position(|e| !e.used).unwrap_or_else(|| {
+            self.entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.buy_qty.max(e.sell_qty))
+                .map_or(0, |(idx, _)| idx)
+        });
+
+        self.accounts[idx] = *account;
+        self.entries[idx] = AggressorEntry {
+            account_idx: idx as u32,
+            epoch,
+            used: true,
+            ..AggressorEntry::default()
+        };
+        idx
+    }
+}
+
+impl Default for AggressorLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: u64 = 1_000_000;
+
+    #[test]
+    fn test_overlap_is_clipped_to_limit() {
+        let mut ledger = AggressorLedger::new();
+        let taker: Pubkey = [1; 32];
+
+        // One-way flow is never clipped
+        assert_eq!(ledger.max_qty(&taker, Side::Buy, 0), u64::MAX);
+        ledger.record(&taker, 3, Side::Buy, 5 * S, 0);
+        assert_eq!(ledger.entries[ledger.find(&taker).unwrap() as usize].epoch, 3);
+        assert_eq!(ledger.max_qty(&taker, Side::Buy, 0), u64::MAX);
+
+        // Selling back is limited to the allowed overlap
+        assert_eq!(ledger.max_qty(&taker, Side::Sell, 2 * S), 2 * S);
+        ledger.record(&taker, 3, Side::Sell, 2 * S, 0);
+        assert_eq!(ledger.max_qty(&taker, Side::Sell, 2 * S), 0);
+        assert_eq!(ledger.max_qty(&taker, Side::Buy, 2 * S), u64::MAX);
+    }
+
+    #[test]
+    fn test_empty_fills_take_no_entry() {
+        let mut ledger = AggressorLedger::new();
+        ledger.record(&[1; 32], 0, Side::Buy, 0, 0);
+        assert_eq!(ledger.find(&[1; 32]), None);
+        assert!(ledger.entries.iter().all(|e| !e.used));
+    }
+
+    #[test]
+    fn test_full_ledger_evicts_smallest_and_clears() {
+        let mut ledger = AggressorLedger::new();
+        for i in 0..AGGRESSOR_CAPACITY {
+            ledger.record(&[i as u8; 32], 0, Side::Buy, (i as u64 + 10) * S, 0);
+        }
+
+        // A new taker is still recorded, evicting the smallest entry
+        ledger.record(&[0xff; 32], 0, Side::Sell, S, 0);
+        assert_eq!(ledger.find(&[0; 32]), None);
+        assert_eq!(ledger.find(&[0xff; 32]), Some(0));
+        assert_eq!(ledger.entries[0].sell_qty, S);
+        assert!(ledger.find(&[1; 32]).is_some());
+
+        ledger.clear();
+        assert_eq!(ledger.find(&[0xff; 32]), None);
+        ledger.record(&[0xff; 32], 1, Side::Buy, S, 0);
+        assert_eq!(ledger.find(&[0xff; 32]), Some(0));
+    }
+}
@@ -0,0 +1,124 @@
+//! Batch state - discrete matching epochs and anti-toxicity knobs
+//!
+//! A batch opens on a permissionless `batch_open` crank once `batch_ms`
+//! has passed. Opening a batch advances `epoch`, promotes pending orders
+//! and snapshots the oracle mark; fills are rejected while the mark sits
+//! outside `kill_band_bps` of that snapshot. Within a batch, no taker may
+//! roundtrip more than `roundtrip_max_qty`.
+
+use pinocchio::pubkey::Pubkey;
+
+/// Default minimum batch length (milliseconds)
+pub const DEFAULT_BATCH_MS: u64 = 100;
+
+/// Default kill band (basis points)
+pub const DEFAULT_KILL_BAND_BPS: u64 = 500;
+
+/// Basis point denominator
+const BPS: u128 = 10_000;
+
+/// Batch state stored in the slab account
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BatchState {
+    /// Current batch epoch (wraps)
+    pub epoch: u16,
+    /// DLP orders posted after batch open earn no rebate this batch
+    pub jit_penalty_on: bool,
+    /// Padding
+    pub _padding: [u8; 5],
+    /// Minimum batch length (milliseconds)
+    pub batch_ms: u64,
+    /// Time the current batch opened (milliseconds)
+    pub batch_open_ms: u64,
+    /// Maximum mark move within a batch (basis points, 0 = disabled)
+    pub kill_band_bps: u64,
+    /// Maximum quantity a taker may buy and sell back in one batch
+    pub roundtrip_max_qty: u64,
+    /// Mark price at batch open (1e6 scale)
+    pub mark_at_open: i64,
+    /// Oracle the mark is read from (unset until configured)
+    pub oracle: Pubkey,
+}
+
+impl BatchState {
+    /// Create batch state at epoch 0 with the default knobs
+    pub fn new(mark_px: i64) -> Self {
+        Self {
+            epoch: 0,
+            jit_penalty_on: true,
+            _padding: [0; 5],
+            batch_ms: DEFAULT_BATCH_MS,
+            batch_open_ms: 0,
+            kill_band_bps: DEFAULT_KILL_BAND_BPS,
+            roundtrip_max_qty: 0,
+            mark_at_open: mark_px,
+            oracle: Pubkey::default(),
+        }
+    }
+
+    /// True once the current batch has run for at least `batch_ms`
+    pub fn can_open(&self, now_ms: u64) -> bool {
+        now_ms >= self.batch_open_ms.saturating_add(self.batch_ms)
+    }
+
+    /// Advance to the next epoch and snapshot the mark
+    ///
+    /// # Returns
+    /// * The new epoch
+    pub fn open(&mut self, now_ms: u64, mark_px: i64) -> u16 {
+        self.epoch = self.epoch.wrapping_add(1);
+        self.batch_open_ms = now_ms;
+        self.mark_at_open = mark_px;
+        self.epoch
+    }
+
+    /// True if `mark_px` is within the kill band of the batch-open mark
+    pub fn within_kill_band(&self, mark_px: i64) -> bool {
+        if self.kill_band_bps == 0 || self.mark_at_open <= 0 {
+            return true;
+        }
+        let moved = (mark_px as i128 - self.mark_at_open as i128).unsigned_abs();
+        moved * BPS <= self.kill_band_bps as u128 * self.mark_at_open as u128
+    }
+
+    /// Time after which newly posted DLP liquidity forfeits its rebate
+    /// for the current batch (`u64::MAX` when the penalty is off)
+    pub fn jit_cutoff_ms(&self) -> u64 {
+        if self.jit_penalty_on {
+            self.batch_open_ms
+        } else {
+            u64::MAX
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_advances_epoch_and_gates_on_batch_ms() {
+        let mut batch = BatchState::new(100_000_000);
+        assert!(batch.can_open(DEFAULT_BATCH_MS));
+        assert_eq!(batch.open(1_000, 101_000_000), 1);
+        assert!(!batch.can_open(1_000 + DEFAULT_BATCH_MS - 1));
+        assert!(batch.can_open(1_000 + DEFAULT_BATCH_MS));
+        assert_eq!(batch.mark_at_open, 101_000_000);
+
+        batch.epoch = u16::MAX;
+        assert_eq!(batch.open(2_000, 101_000_000), 0);
+    }
+
+    #[test]
+    fn test_kill_band() {
+        let mut batch = BatchState::new(100_000_000);
+        assert!(batch.within_kill_band(105_000_000));
+        assert!(batch.within_kill_band(95_000_000));
+        assert!(!batch.within_kill_band(105_000_001));
+        assert!(!batch.within_kill_band(94_999_999));
+
+        batch.kill_band_bps = 0;
+        assert!(batch.within_kill_band(200_000_000));
+    }
+}
@@ -0,0 +1,593 @@
+//! Book area - fixed-capacity price-time priority order book
+//!
+//! Resting orders live in a fixed array of `Order` slots inside the slab
+//! account. Free slots are chained through `next_free`; each side is a
+//! doubly linked list (`next`/`prev`) sorted best price first, oldest
+//! first within a price level (FIFO).
+//!
+//! Quantity locked by reservations (`reserved_qty`) is never matched or
+//! cancelled from under a hold: cancelling a reserved order only removes
+//! its unreserved part and leaves it `CANCELLED` until its holds resolve.
+//!
+//! Regular (non-DLP) orders rest `PENDING` in their queue position and
+//! cannot match until the batch epoch they become eligible in.
+
+use percolator_common::{MakerClass, Order, OrderState, QuoteLevel, Side, TimeInForce};
+
+/// Size of the book area inside the slab account (bytes)
+pub const BOOK_AREA_SIZE: usize = 3072;
+
+/// Size of the book area header fields preceding the order slots (bytes)
+const BOOK_HEADER_SIZE: usize = 24;
+
+/// Maximum number of resting orders
+pub const BOOK_CAPACITY: usize = (BOOK_AREA_SIZE - BOOK_HEADER_SIZE) / core::mem::size_of::<Order>();
+
+/// Trailing padding so the book area keeps its fixed size
+const BOOK_PADDING: usize =
+    BOOK_AREA_SIZE - BOOK_HEADER_SIZE - BOOK_CAPACITY * core::mem::size_of::<Order>();
+
+/// Null order index (end of list)
+pub const NULL_IDX: u32 = u32::MAX;
+
+/// Number of price levels published to the quote cache per side
+pub const QUOTE_LEVELS: usize = 4;
+
+/// Result of matching a taker order against the book
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    /// Total filled quantity (1e6 scale)
+    pub filled_qty: u64,
+    /// Sum of fill qty * price (1e12 scale, divide by 1e6 for notional)
+    pub notional: u128,
+    /// Part of `notional` filled against DLP orders posted after the JIT
+    /// cutoff (1e12 scale)
+    pub jit_notional: u128,
+}
+
+/// Quantity of an order a taker or reservation can take now
+pub(crate) fn available_qty(order: &Order) -> u64 {
+    if order.state == OrderState::PENDING {
+        return 0;
+    }
+    order.qty.saturating_sub(order.reserved_qty)
+}
+
+/// True if a fill against `order` forfeits the maker rebate: DLP
+/// liquidity posted after the JIT cutoff (just-in-time)
+pub(crate) fn is_jit(order: &Order, jit_cutoff_ms: u64) -> bool {
+    order.maker_class == MakerClass::DLP && order.created_ms > jit_cutoff_ms
+}
+
+/// Book area - price-time order book stored in the slab account
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BookArea {
+    /// Best bid (highest price, oldest first)
+    pub bids_head: u32,
+    /// Best ask (lowest price, oldest first)
+    pub asks_head: u32,
+    /// Head of the free slot list
+    pub free_head: u32,
+    /// Number of resting orders
+    pub order_count: u32,
+    /// Next order ID to assign (monotonic)
+    pub next_order_id: u64,
+    /// Order slots
+    pub orders: [Order; BOOK_CAPACITY],
+    /// Padding to BOOK_AREA_SIZE
+    pub _padding: [u8; BOOK_PADDING],
+}
+
+const _: () = assert!(core::mem::size_of::<BookArea>() == BOOK_AREA_SIZE);
+
+impl BookArea {
+    /// Create an empty book with every slot on the freelist
+    pub fn new() -> Self {
+        let mut book = Self {
+            bids_head: NULL_IDX,
+            asks_head: NULL_IDX,
+            free_head: 0,
+            order_count: 0,
+            next_order_id: 1,
+            orders: [Order::default(); BOOK_CAPACITY],
+            _padding: [0; BOOK_PADDING],
+        };
+        book.init();
+        book
+    }
+
+    /// Reset to an empty book in place, with every slot on the freelist
+    pub fn init(&mut self) {
+        self.bids_head = NULL_IDX;
+        self.asks_head = NULL_IDX;
+        self.free_head = 0;
+        self.order_count = 0;
+        self.next_order_id = 1;
+        for (i, order) in self.orders.iter_mut().enumerate() {
+            *order = Order {
+                next: NULL_IDX,
+                prev: NULL_IDX,
+                next_free: if i + 1 < BOOK_CAPACITY { (i + 1) as u32 } else { NULL_IDX },
+                ..Order::default()
+            };
+        }
+        self._padding = [0; BOOK_PADDING];
+    }
+
+    /// Head of the list for a side
+    pub fn head(&self, side: Side) -> u32 {
+        match side {
+            Side::Buy => self.bids_head,
+            Side::Sell => self.asks_head,
+        }
+    }
+
+    fn set_head(&mut self, side: Side, idx: u32) {
+        match side {
+            Side::Buy => self.bids_head = idx,
+            Side::Sell => self.asks_head = idx,
+        }
+    }
+
+    /// True if `price` has strictly better priority than `other` on `side`
+    fn is_better(side: Side, price: u64, other: u64) -> bool {
+        match side {
+            Side::Buy => price > other,
+            Side::Sell => price < other,
+        }
+    }
+
+    /// True if a resting order at `price` on `side` crosses a taker `limit_px`
+    pub fn crosses(side: Side, price: u64, limit_px: u64) -> bool {
+        match side {
+            // Resting bid: taker sells at or below it
+            Side::Buy => price >= limit_px,
+            // Resting ask: taker buys at or above it
+            Side::Sell => price <= limit_px,
+        }
+    }
+
+    /// Take a slot off the freelist
+    fn alloc(&mut self) -> Option<u32> {
+        let idx = self.free_head;
+        if idx == NULL_IDX || idx as usize >= BOOK_CAPACITY || self.orders[idx as usize].used {
+            return None;
+        }
+        self.free_head = self.orders[idx as usize].next_free;
+        Some(idx)
+    }
+
+    /// Return a slot to the freelist
+    fn release(&mut self, idx: u32) {
+        let free_head = self.free_head;
+        let order = &mut self.orders[idx as usize];
+        *order = Order::default();
+        order.next = NULL_IDX;
+        order.prev = NULL_IDX;
+        order.next_free = free_head;
+        self.free_head = idx;
+    }
+
+    /// Insert a live DLP order behind all orders at the same or better price
+    ///
+    /// # Returns
+    /// * `(order_id, slot_idx)`, or `Err` if the book is full or inputs are zero
+    pub fn insert(
+        &mut self,
+        side: Side,
+        tif: TimeInForce,
+        price: u64,
+        qty: u64,
+        created_ms: u64,
+    ) -> Result<(u64, u32), ()> {
+        self.insert_class(MakerClass::DLP, 0, side, tif, price, qty, created_ms)
+    }
+
+    /// Insert a resting order of the given maker class
+    ///
+    /// DLP orders are live immediately; regular orders rest `PENDING`
+    /// until `eligible_epoch` is promoted.
+    ///
+    /// # Returns
+    /// * `(order_id, slot_idx)`, or `Err` if the book is full or inputs are zero
+    pub fn insert_class(
+        &mut self,
+        maker_class: MakerClass,
+        eligible_epoch: u16,
+        side: Side,
+        tif: TimeInForce,
+        price: u64,
+        qty: u64,
+        created_ms: u64,
+    ) -> Result<(u64, u32), ()> {
+        if price == 0 || qty == 0 {
+            return Err(());
+        }
+        let idx = self.alloc().ok_or(())?;
+
+        // Find the first order with strictly worse priority
+        let mut prev = NULL_IDX;
+        let mut cur = self.head(side);
+        while cur != NULL_IDX && !Self::is_better(side, price, self.orders[cur as usize].price) {
+            prev = cur;
+            cur = self.orders[cur as usize].next;
+        }
+
+        let order_id = self.next_order_id;
+        self.next_order_id = self.next_order_id.wrapping_add(1);
+
+        self.orders[idx as usize] = Order {
+            order_id,
+            account_idx: 0,
+            instrument_idx: 0,
+            side,
+            tif,
+            maker_class,
+            state: match maker_class {
+                MakerClass::DLP => OrderState::LIVE,
+                MakerClass::REG => OrderState::PENDING,
+            },
+            eligible_epoch,
+            created_ms,
+            price,
+            qty,
+            reserved_qty: 0,
+            qty_orig: qty,
+            next: cur,
+            prev,
+            next_free: NULL_IDX,
+            used: true,
+            _padding: [0; 3],
+        };
+
+        if prev == NULL_IDX {
+            self.set_head(side, idx);
+        } else {
+            self.orders[prev as usize].next = idx;
+        }
+        if cur != NULL_IDX {
+            self.orders[cur as usize].prev = idx;
+        }
+
+        self.order_count += 1;
+        Ok((order_id, idx))
+    }
+
+    /// Unlink an order from its side and free its slot
+    pub fn remove(&mut self, idx: u32) -> Result<(), ()> {
+        if idx as usize >= BOOK_CAPACITY || !self.orders[idx as usize].used {
+            return Err(());
+        }
+
+        let Order { side, next, prev, .. } = self.orders[idx as usize];
+        if prev == NULL_IDX {
+            self.set_head(side, next);
+        } else {
+            self.orders[prev as usize].next = next;
+        }
+        if next != NULL_IDX {
+            self.orders[next as usize].prev = prev;
+        }
+
+        self.release(idx);
+        self.order_count -= 1;
+        Ok(())
+    }
+
+    /// Cancel an order, keeping any quantity locked by reservations
+    ///
+    /// An unreserved order is removed. A reserved order is cut down to its
+    /// reserved quantity and marked `CANCELLED`; it is removed once its
+    /// holds are committed or released.
+    pub fn cancel(&mut self, idx: u32) -> Result<(), ()> {
+        if idx as usize >= BOOK_CAPACITY || !self.orders[idx as usize].used {
+            return Err(());
+        }
+
+        let order = &mut self.orders[idx as usize];
+        if order.reserved_qty == 0 {
+            return self.remove(idx);
+        }
+        order.qty = order.reserved_qty;
+        order.state = OrderState::CANCELLED;
+        Ok(())
+    }
+
+    /// Cancel every resting order (reserved quantity stays locked)
+    ///
+    /// # Returns
+    /// * Number of orders cancelled
+    pub fn clear(&mut self) -> u32 {
+        let mut cancelled = 0;
+        for idx in 0..BOOK_CAPACITY as u32 {
+            let order = &self.orders[idx as usize];
+            if order.used && order.state != OrderState::CANCELLED && self.cancel(idx).is_ok() {
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    /// Promote pending orders that become eligible in `epoch`
+    ///
+    /// Promoted orders keep their queue position.
+    ///
+    /// # Returns
+    /// * Number of orders promoted
+    pub fn promote(&mut self, epoch: u16) -> u32 {
+        let mut promoted = 0;
+        for order in self.orders.iter_mut() {
+            if order.used && order.state == OrderState::PENDING && order.eligible_epoch == epoch {
+                order.state = OrderState::LIVE;
+                promoted += 1;
+            }
+        }
+        promoted
+    }
+
+    /// First opposite-side order with unreserved quantity that an order
+    /// at `price` on `side` would cross (pending orders included, so the
+    /// book never ends up crossed on promotion)
+    pub fn first_crossing(&self, side: Side, price: u64) -> Option<u32> {
+        let book_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        let mut cur = self.head(book_side);
+        while cur != NULL_IDX {
+            let order = &self.orders[cur as usize];
+            if !Self::crosses(book_side, order.price, price) {
+                return None;
+            }
+            if order.qty > order.reserved_qty {
+                return Some(cur);
+            }
+            cur = order.next;
+        }
+        None
+    }
+
+    /// True if an order at `price` on `side` would cross unreserved liquidity
+    pub fn would_cross(&self, side: Side, price: u64) -> bool {
+        self.first_crossing(side, price).is_some()
+    }
+
+    /// Find the slot of a resting (not cancelled) order by ID
+    pub fn find_order(&self, order_id: u64) -> Option<u32> {
+        self.orders
+            .iter()
+            .position(|o| o.used && o.order_id == order_id && o.state != OrderState::CANCELLED)
+            .map(|i| i as u32)
+    }
+
+    /// Match a taker order against the opposite side up to `limit_px`
+    ///
+    /// Walks resting orders in price-time order, filling each until the
+    /// taker quantity is exhausted or the next order no longer crosses.
+    /// Pending orders are skipped. Fully filled orders are removed from
+    /// the book.
+    pub fn match_taker(
+        &mut self,
+        taker_side: Side,
+        qty: u64,
+        limit_px: u64,
+        jit_cutoff_ms: u64,
+    ) -> MatchResult {
+        let book_side = match taker_side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        let mut result = MatchResult::default();
+        let mut remaining = qty;
+        let mut cur = self.head(book_side);
+
+        while remaining > 0 && cur != NULL_IDX {
+            let order = &mut self.orders[cur as usize];
+            if !Self::crosses(book_side, order.price, limit_px) {
+                break;
+            }
+
+            let take = remaining.min(available_qty(order));
+            let next = order.next;
+
+            if take > 0 {
+                let notional = take as u128 * order.price as u128;
+                if is_jit(order, jit_cutoff_ms) {
+                    result.jit_notional += notional;
+                }
+                order.qty -= take;
+                remaining -= take;
+                result.filled_qty += take;
+                result.notional += notional;
+            }
+
+            if order.qty == 0 {
+                let _ = self.remove(cur);
+            }
+            cur = next;
+        }
+
+        result
+    }
+
+    /// Aggregate the best price levels on one side
+    ///
+    /// # Returns
+    /// * Number of levels written to `out`
+    pub fn top_levels(&self, side: Side, out: &mut [QuoteLevel; QUOTE_LEVELS]) -> usize {
+        let mut count = 0;
+        let mut cur = self.head(side);
+
+        while cur != NULL_IDX {
+            let order = &self.orders[cur as usize];
+            let px = order.price as i64;
+            let avail = available_qty(order) as i64;
+
+            if count > 0 && out[count - 1].px == px {
+                out[count - 1].avail_qty += avail;
+            } else if avail > 0 {
+                if count == QUOTE_LEVELS {
+                    break;
+                }
+                out[count] = QuoteLevel { px, avail_qty: avail };
+                count += 1;
+            }
+            cur = order.next;
+        }
+
+        count
+    }
+}
+
+impl Default for BookArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: u64 = 1_000_000;
+
+    #[test]
+    fn test_book_area_size() {
+        assert_eq!(core::mem::size_of::<BookArea>(), BOOK_AREA_SIZE);
+        assert_eq!(BookArea::new().free_head, 0);
+    }
+
+    #[test]
+    fn test_price_time_priority() {
+        let mut book = BookArea::new();
+        let (first, _) = book.insert(Side::Sell, TimeInForce::GTC, 101 * S, S, 0).unwrap();
+        let (best, _) = book.insert(Side::Sell, TimeInForce::GTC, 100 * S, S, 1).unwrap();
+        let (second, _) = book.insert(Side::Sell, TimeInForce::GTC, 101 * S, S, 2).unwrap();
+
+        let head = book.asks_head as usize;
+        assert_eq!(book.orders[head].order_id, best);
+        let next = book.orders[head].next as usize;
+        assert_eq!(book.orders[next].order_id, first);
+        let last = book.orders[next].next as usize;
+        assert_eq!(book.orders[last].order_id, second);
+        assert_eq!(book.orders[last].next, NULL_IDX);
+    }
+
+    #[test]
+    fn test_match_walks_levels_up_to_limit() {
+        let mut book = BookArea::new();
+        book.insert(Side::Sell, TimeInForce::GTC, 100 * S, 2 * S, 0).unwrap();
+        book.insert(Side::Sell, TimeInForce::GTC, 101 * S, 2 * S, 1).unwrap();
+        book.insert(Side::Sell, TimeInForce::GTC, 103 * S, 5 * S, 2).unwrap();
+
+        // Buy 5 with limit 102: takes 2 @ 100 and 2 @ 101, stops at 103
+        let result = book.match_taker(Side::Buy, 5 * S, 102 * S, u64::MAX);
+        assert_eq!(result.filled_qty, 4 * S);
+        assert_eq!(result.notional, (2 * 100 + 2 * 101) as u128 * S as u128 * S as u128);
+        assert_eq!(book.order_count, 1);
+        assert_eq!(book.orders[book.asks_head as usize].price, 103 * S);
+    }
+
+    #[test]
+    fn test_partial_fill_keeps_order_and_reuses_slots() {
+        let mut book = BookArea::new();
+        let (id, idx) = book.insert(Side::Buy, TimeInForce::GTC, 100 * S, 3 * S, 0).unwrap();
+
+        let result = book.match_taker(Side::Sell, S, 99 * S, u64::MAX);
+        assert_eq!(result.filled_qty, S);
+        assert_eq!(book.orders[idx as usize].qty, 2 * S);
+        assert_eq!(book.find_order(id), Some(idx));
+
+        book.remove(idx).unwrap();
+        assert_eq!(book.bids_head, NULL_IDX);
+        assert_eq!(book.find_order(id), None);
+
+        // Fill the book to capacity, then overflow
+        for i in 0..BOOK_CAPACITY {
+            book.insert(Side::Buy, TimeInForce::GTC, 100 * S, S, i as u64).unwrap();
+        }
+        assert!(book.insert(Side::Buy, TimeInForce::GTC, 100 * S, S, 0).is_err());
+    }
+
+    #[test]
+    fn test_top_levels_aggregates_same_price() {
+        let mut book = BookArea::new();
+        for px in [100, 100, 99, 98, 97, 96] {
+            book.insert(Side::Buy, TimeInForce::GTC, px * S, S, 0).unwrap();
+        }
+
+        let mut levels = [QuoteLevel::default(); QUOTE_LEVELS];
+        let count = book.top_levels(Side::Buy, &mut levels);
+        assert_eq!(count, QUOTE_LEVELS);
+        assert_eq!(levels[0].px, (100 * S) as i64);
+        assert_eq!(levels[0].avail_qty, (2 * S) as i64);
+        assert_eq!(levels[3].px, (97 * S) as i64);
+    }
+
+    #[test]
+    fn test_would_cross_and_clear() {
+        let mut book = BookArea::new();
+        book.insert(Side::Sell, TimeInForce::GTC, 100 * S, S, 0).unwrap();
+        book.insert(Side::Buy, TimeInForce::GTC, 98 * S, S, 0).unwrap();
+
+        assert!(book.would_cross(Side::Buy, 100 * S));
+        assert!(!book.would_cross(Side::Buy, 99 * S));
+        assert!(book.would_cross(Side::Sell, 98 * S));
+        assert!(!book.would_cross(Side::Sell, 99 * S));
+
+        let next_id = book.next_order_id;
+        assert_eq!(book.clear(), 2);
+        assert_eq!(book.order_count, 0);
+        assert_eq!(book.bids_head, NULL_IDX);
+        assert_eq!(book.next_order_id, next_id);
+    }
+
+    #[test]
+    fn test_cancel_keeps_reserved_qty() {
+        let mut book = BookArea::new();
+        let (id, idx) = book.insert(Side::Sell, TimeInForce::GTC, 100 * S, 3 * S, 0).unwrap();
+        book.orders[idx as usize].reserved_qty = S;
+
+        // Reserved quantity is neither matched nor crossed
+        assert!(book.would_cross(Side::Buy, 100 * S));
+        book.cancel(idx).unwrap();
+        assert_eq!(book.orders[idx as usize].qty, S);
+        assert_eq!(book.orders[idx as usize].state, OrderState::CANCELLED);
+        assert!(!book.would_cross(Side::Buy, 100 * S));
+        assert_eq!(book.match_taker(Side::Buy, S, 100 * S, u64::MAX).filled_qty, 0);
+        assert_eq!(book.find_order(id), None);
+        assert_eq!(book.clear(), 0);
+        assert_eq!(book.order_count, 1);
+    }
+
+    #[test]
+    fn test_pending_orders_wait_for_promotion() {
+        let mut book = BookArea::new();
+        let (_, pending) = book
+            .insert_class(MakerClass::REG, 1, Side::Sell, TimeInForce::GTC, 100 * S, S, 0)
+            .unwrap();
+        book.insert(Side::Sell, TimeInForce::GTC, 101 * S, S, 50).unwrap();
+
+        // Pending liquidity is neither quoted nor matched, but still counts
+        // for self-cross prevention
+        let mut levels = [QuoteLevel::default(); QUOTE_LEVELS];
+        assert_eq!(book.top_levels(Side::Sell, &mut levels), 1);
+        assert_eq!(levels[0].px, (101 * S) as i64);
+        assert_eq!(book.first_crossing(Side::Buy, 100 * S), Some(pending));
+
+        // Only the late DLP order is JIT
+        let result = book.match_taker(Side::Buy, S, 101 * S, 10);
+        assert_eq!(result.filled_qty, S);
+        assert_eq!(result.jit_notional, result.notional);
+
+        assert_eq!(book.promote(2), 0);
+        assert_eq!(book.promote(1), 1);
+        assert_eq!(book.promote(1), 0);
+        let result = book.match_taker(Side::Buy, S, 101 * S, 10);
+        assert_eq!(result.notional, 100 * S as u128 * S as u128);
+        assert_eq!(result.jit_notional, 0);
+    }
+}
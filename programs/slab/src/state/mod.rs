@@ -1,6 +1,14 @@
+pub mod book;
 pub mod slab;
+pub mod reservations;
+pub mod batch;
+pub mod aggressor;
 
+pub use book::*;
 pub use slab::*;
+pub use reservations::*;
+pub use batch::*;
+pub use aggressor::*;
 
 // Re-export from common
 pub use percolator_common::{SlabHeader, QuoteCache, QuoteLevel, FillReceipt};
@@ -0,0 +1,375 @@
+//! Reservation area - liquidity holds for two-phase reserve/commit
+//!
+//! A reservation walks the opposite side in price-time order and locks
+//! `reserved_qty` on each resting order it would match, recording one
+//! `Slice` per order. Commit fills the slices at the locked orders'
+//! prices; cancel or expiry releases them. Both pools are small fixed
+//! arrays, so free slots are found by a linear scan.
+
+use super::{available_qty, is_jit, BookArea, MatchResult, NULL_IDX};
+use percolator_common::{calculate_vwap, OrderState, PercolatorError, Reservation, Side, Slice};
+
+/// Maximum number of open reservations
+pub const RESERVATION_CAPACITY: usize = 4;
+
+/// Maximum number of reserved slices across all reservations
+pub const SLICE_CAPACITY: usize = 16;
+
+/// Maximum open reservations per commitment (one reserving user)
+pub const MAX_HOLDS_PER_COMMITMENT: usize = 1;
+
+/// Reservation area - hold table and slice pool stored in the slab account
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ReservationArea {
+    /// Reservation slots
+    pub reservations: [Reservation; RESERVATION_CAPACITY],
+    /// Slice slots
+    pub slices: [Slice; SLICE_CAPACITY],
+    /// Next hold ID to assign (monotonic)
+    pub next_hold_id: u64,
+    /// Padding
+    pub _padding: u64,
+}
+
+impl ReservationArea {
+    /// Create an empty reservation area
+    pub fn new() -> Self {
+        let mut area = Self {
+            reservations: [Reservation::default(); RESERVATION_CAPACITY],
+            slices: [Slice::default(); SLICE_CAPACITY],
+            next_hold_id: 1,
+            _padding: 0,
+        };
+        area.init();
+        area
+    }
+
+    /// Reset to an empty reservation area in place
+    pub fn init(&mut self) {
+        self.next_hold_id = 1;
+        self._padding = 0;
+        for idx in 0..RESERVATION_CAPACITY {
+            self.free_reservation(idx as u32);
+        }
+        for idx in 0..SLICE_CAPACITY {
+            self.free_slice(idx as u32);
+        }
+    }
+
+    /// Find the slot of an open reservation by hold ID
+    pub fn find(&self, hold_id: u64) -> Option<u32> {
+        self.reservations
+            .iter()
+            .position(|r| r.used && r.hold_id == hold_id)
+            .map(|i| i as u32)
+    }
+
+    fn free_reservation(&mut self, idx: u32) {
+        self.reservations[idx as usize] = Reservation {
+            index: idx,
+            slice_head: NULL_IDX,
+            ..Reservation::default()
+        };
+    }
+
+    fn free_slice(&mut self, idx: u32) {
+        self.slices[idx as usize] = Slice {
+            index: idx,
+            next: NULL_IDX,
+            ..Slice::default()
+        };
+    }
+
+    fn alloc_slice(&self) -> Option<u32> {
+        self.slices.iter().position(|s| !s.used).map(|i| i as u32)
+    }
+
+    /// Reserve up to `qty` against the opposite side, no worse than `limit_px`
+    ///
+    /// Stops early if the book runs out of crossing liquidity or the slice
+    /// pool is exhausted, so the hold may be smaller than requested.
+    /// `max_charge` is the reserved notional plus the taker fee.
+    ///
+    /// # Returns
+    /// * Slot of the new reservation, `HoldLimitReached` if the commitment
+    ///   already holds `MAX_HOLDS_PER_COMMITMENT` reservations, or
+    ///   `InsufficientLiquidity` if nothing could be reserved
+    pub fn reserve(
+        &mut self,
+        book: &mut BookArea,
+        side: Side,
+        qty: u64,
+        limit_px: u64,
+        taker_fee_bps: i64,
+        route_id: u64,
+        commitment_hash: [u8; 32],
+        book_seqno: u64,
+        expiry_ms: u64,
+    ) -> Result<u32, PercolatorError> {
+        let held = self
+            .reservations
+            .iter()
+            .filter(|r| r.used && r.commitment_hash == commitment_hash)
+            .count();
+        if held >= MAX_HOLDS_PER_COMMITMENT {
+            return Err(PercolatorError::HoldLimitReached);
+        }
+
+        let idx = self
+            .reservations
+            .iter()
+            .position(|r| !r.used)
+            .ok_or(PercolatorError::PoolFull)? as u32;
+
+        let book_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+
+        let mut remaining = qty;
+        let mut reserved = MatchResult::default();
+        let mut worst_px = 0;
+        let mut slice_head = NULL_IDX;
+        let mut cur = book.head(book_side);
+
+        while remaining > 0 && cur != NULL_IDX {
+            let order = &mut book.orders[cur as usize];
+            if !BookArea::crosses(book_side, order.price, limit_px) {
+                break;
+            }
+
+            let take = remaining.min(available_qty(order));
+            if take > 0 {
+                let Some(slice_idx) = self.alloc_slice() else {
+                    break;
+                };
+                self.slices[slice_idx as usize] = Slice {
+                    order_idx: cur,
+                    qty: take,
+                    next: slice_head,
+                    index: slice_idx,
+                    used: true,
+                    _padding: [0; 7],
+                };
+                slice_head = slice_idx;
+
+                order.reserved_qty += take;
+                remaining -= take;
+                reserved.filled_qty += take;
+                reserved.notional += take as u128 * order.price as u128;
+                worst_px = order.price;
+            }
+            cur = order.next;
+        }
+
+        if reserved.filled_qty == 0 {
+            return Err(PercolatorError::InsufficientLiquidity);
+        }
+
+        let notional = reserved.notional / 1_000_000;
+        let fee = (notional as i128 * taker_fee_bps as i128 / 10_000).max(0) as u128;
+
+        let hold_id = self.next_hold_id;
+        self.next_hold_id = self.next_hold_id.wrapping_add(1);
+
+        self.reservations[idx as usize] = Reservation {
+            hold_id,
+            route_id,
+            side,
+            qty: reserved.filled_qty,
+            vwap_px: calculate_vwap(reserved.notional, reserved.filled_qty),
+            worst_px,
+            max_charge: notional + fee,
+            commitment_hash,
+            book_seqno,
+            expiry_ms,
+            slice_head,
+            index: idx,
+            used: true,
+            ..Reservation::default()
+        };
+
+        Ok(idx)
+    }
+
+    /// Fill a reservation's slices at the reserved orders' prices and free it
+    ///
+    /// Filled orders are removed from the book once empty.
+    pub fn commit(&mut self, book: &mut BookArea, idx: u32, jit_cutoff_ms: u64) -> MatchResult {
+        let mut result = MatchResult::default();
+
+        let mut cur = self.reservations[idx as usize].slice_head;
+        while cur != NULL_IDX {
+            let Slice { order_idx, qty, next, .. } = self.slices[cur as usize];
+            let order = &mut book.orders[order_idx as usize];
+
+            let notional = qty as u128 * order.price as u128;
+            if is_jit(order, jit_cutoff_ms) {
+                result.jit_notional += notional;
+            }
+            order.qty -= qty;
+            order.reserved_qty -= qty;
+            result.filled_qty += qty;
+            result.notional += notional;
+            if order.qty == 0 {
+                let _ = book.remove(order_idx);
+            }
+
+            self.free_slice(cur);
+            cur = next;
+        }
+
+        self.free_reservation(idx);
+        result
+    }
+
+    /// Release a reservation's slices back to the book and free it
+    ///
+    /// Quantity held on a cancelled order leaves the book with the hold.
+    pub fn release(&mut self, book: &mut BookArea, idx: u32) {
+        let mut cur = self.reservations[idx as usize].slice_head;
+        while cur != NULL_IDX {
+            let Slice { order_idx, qty, next, .. } = self.slices[cur as usize];
+            let order = &mut book.orders[order_idx as usize];
+
+            order.reserved_qty -= qty;
+            if order.state == OrderState::CANCELLED {
+                order.qty -= qty;
+                if order.qty == 0 {
+                    let _ = book.remove(order_idx);
+                }
+            }
+
+            self.free_slice(cur);
+            cur = next;
+        }
+
+        self.free_reservation(idx);
+    }
+
+    /// Release every reservation that has expired by `now_ms`
+    ///
+    /// # Returns
+    /// * Number of reservations released
+    pub fn expire(&mut self, book: &mut BookArea, now_ms: u64) -> u32 {
+        let mut expired = 0;
+        for idx in 0..RESERVATION_CAPACITY as u32 {
+            let reservation = &self.reservations[idx as usize];
+            if reservation.used && now_ms >= reservation.expiry_ms {
+                self.release(book, idx);
+                expired += 1;
+            }
+        }
+        expired
+    }
+}
+
+impl Default for ReservationArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percolator_common::TimeInForce;
+
+    const S: u64 = 1_000_000;
+
+    fn book() -> BookArea {
+        let mut book = BookArea::new();
+        book.insert(Side::Sell, TimeInForce::GTC, 100 * S, 2 * S, 0).unwrap();
+        book.insert(Side::Sell, TimeInForce::GTC, 101 * S, 2 * S, 1).unwrap();
+        book.insert(Side::Sell, TimeInForce::GTC, 103 * S, 5 * S, 2).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_reserve_locks_slices_and_prices() {
+        let mut book = book();
+        let mut area = ReservationArea::new();
+
+        let idx = area
+            .reserve(&mut book, Side::Buy, 3 * S, 102 * S, 10, 9, [1; 32], 0, 1_000)
+            .unwrap();
+        let hold = area.reservations[idx as usize];
+        assert_eq!(hold.hold_id, 1);
+        assert_eq!(hold.qty, 3 * S);
+        assert_eq!(hold.vwap_px, (2 * 100 + 101) * S / 3);
+        assert_eq!(hold.worst_px, 101 * S);
+        assert_eq!(hold.max_charge, 301 * S as u128 + 301 * S as u128 / 1_000);
+
+        // Reserved quantity is no longer available to takers or other holds
+        assert_eq!(book.match_taker(Side::Buy, 2 * S, 101 * S, u64::MAX).filled_qty, S);
+        assert_eq!(
+            area.reserve(&mut book, Side::Buy, S, 101 * S, 0, 0, [2; 32], 0, 1_000),
+            Err(PercolatorError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn test_one_open_hold_per_commitment() {
+        let mut book = book();
+        let mut area = ReservationArea::new();
+        let idx = area
+            .reserve(&mut book, Side::Buy, S, 100 * S, 0, 0, [1; 32], 0, 1_000)
+            .unwrap();
+        assert_eq!(
+            area.reserve(&mut book, Side::Buy, S, 100 * S, 0, 0, [1; 32], 0, 1_000),
+            Err(PercolatorError::HoldLimitReached)
+        );
+        assert!(area.reserve(&mut book, Side::Buy, S, 100 * S, 0, 0, [2; 32], 0, 1_000).is_ok());
+
+        // Releasing the hold frees the commitment to reserve again
+        area.release(&mut book, idx);
+        assert!(area.reserve(&mut book, Side::Buy, S, 101 * S, 0, 0, [1; 32], 0, 1_000).is_ok());
+    }
+
+    #[test]
+    fn test_commit_fills_at_reserved_prices() {
+        let mut book = book();
+        let mut area = ReservationArea::new();
+        let idx = area
+            .reserve(&mut book, Side::Buy, 3 * S, 102 * S, 0, 0, [0; 32], 0, 1_000)
+            .unwrap();
+
+        // The LP cancels a reserved order: only the unreserved part goes
+        let first = book.asks_head;
+        book.cancel(book.orders[first as usize].next).unwrap();
+
+        let result = area.commit(&mut book, idx, 0);
+        assert_eq!(result.filled_qty, 3 * S);
+        assert_eq!(result.notional, (2 * 100 + 101) as u128 * S as u128 * S as u128);
+        assert_eq!(result.jit_notional, 101 * S as u128 * S as u128);
+        assert_eq!(book.order_count, 1);
+        assert_eq!(book.orders[book.asks_head as usize].price, 103 * S);
+        assert!(area.find(1).is_none());
+        assert!(area.slices.iter().all(|s| !s.used));
+    }
+
+    #[test]
+    fn test_release_and_expire() {
+        let mut book = book();
+        let mut area = ReservationArea::new();
+        let idx = area
+            .reserve(&mut book, Side::Buy, 3 * S, 102 * S, 0, 0, [0; 32], 0, 1_000)
+            .unwrap();
+        area.reserve(&mut book, Side::Buy, S, 103 * S, 0, 0, [2; 32], 0, 2_000).unwrap();
+
+        // Released quantity returns to the book unless its order was cancelled
+        let second = book.orders[book.asks_head as usize].next;
+        book.cancel(second).unwrap();
+        area.release(&mut book, idx);
+        assert_eq!(book.orders[book.asks_head as usize].reserved_qty, 0);
+        assert_eq!(book.orders[second as usize].qty, S);
+        assert_eq!(book.order_count, 3);
+
+        assert_eq!(area.expire(&mut book, 1_999), 0);
+        assert_eq!(area.expire(&mut book, 2_000), 1);
+        assert_eq!(book.order_count, 2);
+        assert!(area.reservations.iter().all(|r| !r.used));
+        assert!(book.orders.iter().all(|o| o.reserved_qty == 0));
+    }
+}
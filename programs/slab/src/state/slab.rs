@@ -1,26 +1,11 @@
 //! Slab state - v0 minimal single-account orderbook
 
-use super::{SlabHeader, QuoteCache};
+use super::{AggressorLedger, BatchState, BookArea, ReservationArea, SlabHeader, QuoteCache, QuoteLevel, QUOTE_LEVELS};
+use percolator_common::Side;
 
-/// Book area - simplified price-time orderbook
-/// In v0, this is a stub placeholder for future book implementation
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct BookArea {
-    /// Placeholder data for book (3KB)
-    pub data: [u8; 3072],
-}
-
-impl BookArea {
-    pub fn new() -> Self {
-        Self {
-            data: [0; 3072],
-        }
-    }
-}
-
-/// Main slab state - v0 minimal structure (~4KB)
-/// Layout: Header (256B) + QuoteCache (256B) + BookArea (3KB)
+/// Main slab state - v0 minimal structure (~11.5KB)
+/// Layout: Header (256B) + QuoteCache (256B) + BookArea (3KB) + ReservationArea (~1KB)
+/// + BatchState (80B) + AggressorLedger (7KB)
 #[repr(C)]
 pub struct SlabState {
     /// Header with metadata and offsets
@@ -29,6 +14,12 @@ pub struct SlabState {
     pub quote_cache: QuoteCache,
     /// Book area (price-time queues)
     pub book: BookArea,
+    /// Reservation area (two-phase holds)
+    pub reservations: ReservationArea,
+    /// Batch epoch and anti-toxicity state
+    pub batch: BatchState,
+    /// Per-batch taker volume (roundtrip guard)
+    pub aggressors: AggressorLedger,
 }
 
 impl SlabState {
@@ -36,13 +27,49 @@ impl SlabState {
     pub const LEN: usize = core::mem::size_of::<Self>();
 
     /// Create new slab state
+    ///
+    /// Builds the whole state by value; on chain use `init`.
     pub fn new(header: SlabHeader) -> Self {
         Self {
+            batch: BatchState::new(header.mark_px),
             header,
             quote_cache: QuoteCache::new(),
             book: BookArea::new(),
+            reservations: ReservationArea::new(),
+            aggressors: AggressorLedger::new(),
         }
     }
+
+    /// Initialize slab state in place
+    ///
+    /// The slab is larger than the 4KB SBF stack frame, so each area is
+    /// reset through `&mut self` rather than assigned from a `SlabState`
+    /// built on the stack.
+    pub fn init(&mut self, header: SlabHeader) {
+        self.batch = BatchState::new(header.mark_px);
+        self.header = header;
+        self.quote_cache = QuoteCache::new();
+        self.book.init();
+        self.reservations.init();
+        self.aggressors.clear();
+    }
+
+    /// Record a book change: bump seqno and rebuild the quote cache
+    /// from the top levels on each side
+    ///
+    /// # Returns
+    /// * The new seqno
+    pub fn publish_book_change(&mut self) -> u32 {
+        let seqno = self.header.increment_seqno();
+
+        let mut bids = [QuoteLevel::default(); QUOTE_LEVELS];
+        let mut asks = [QuoteLevel::default(); QUOTE_LEVELS];
+        let bid_count = self.book.top_levels(Side::Buy, &mut bids);
+        let ask_count = self.book.top_levels(Side::Sell, &mut asks);
+        self.quote_cache.update(seqno, &bids[..bid_count], &asks[..ask_count]);
+
+        seqno
+    }
 }
 
 #[cfg(test)]
@@ -55,8 +82,11 @@ mod tests {
         use core::mem::size_of;
         let actual_size = size_of::<SlabState>();
 
-        // Should be around 4KB for v0
-        assert!(actual_size < 5000, "SlabState is {} bytes, should be < 5KB", actual_size);
+        // ~11.5KB: batch state and the 64-entry aggressor ledger push the
+        // slab past the original 5KB v0 budget. The account is initialized
+        // in place (`SlabState::init`), so its size is bounded by rent
+        // rather than the SBF stack.
+        assert!(actual_size < 12_288, "SlabState is {} bytes, should be < 12KB", actual_size);
         assert!(actual_size > 3000, "SlabState is {} bytes, should be > 3KB", actual_size);
     }
 
@@ -77,4 +107,35 @@ mod tests {
         assert_eq!(slab.header.seqno, 0);
         assert_eq!(slab.quote_cache.seqno_snapshot, 0);
     }
+
+    #[test]
+    fn test_init_in_place_resets_every_area() {
+        let header = SlabHeader::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            50_000_000_000,
+            20,
+            1_000_000,
+            255,
+        );
+
+        // Start from a dirty account buffer
+        let mut data = vec![u128::MAX; SlabState::LEN.div_ceil(16)];
+        let slab = unsafe { &mut *(data.as_mut_ptr() as *mut SlabState) };
+        slab.init(header);
+
+        let fresh = SlabState::new(header);
+        assert!(slab.header.validate());
+        assert_eq!(slab.book.head(Side::Buy), fresh.book.head(Side::Buy));
+        assert_eq!(slab.book.free_head, fresh.book.free_head);
+        assert_eq!(slab.book.order_count, 0);
+        assert_eq!(slab.book.orders[1].next_free, fresh.book.orders[1].next_free);
+        assert_eq!(slab.reservations.find(u64::MAX), None);
+        assert_eq!(slab.reservations.next_hold_id, 1);
+        assert!(slab.aggressors.entries.iter().all(|e| !e.used));
+        assert_eq!(slab.batch.mark_at_open, 50_000_000_000);
+        assert_eq!(slab.quote_cache.seqno_snapshot, 0);
+    }
 }
@@ -32,6 +32,7 @@ pub async fn test_t01_layout_validity(ctx: &TestContext) -> Result<()> {
     // - instrument: Pubkey (32 bytes)
     // - mark_px: i64 (8 bytes)
     // - taker_fee_bps: i64 (8 bytes)
+    // - maker_fee_bps: i64 (8 bytes)
     // - contract_size: i64 (8 bytes)
     // - bump: u8 (1 byte)
 
@@ -40,8 +41,9 @@ pub async fn test_t01_layout_validity(ctx: &TestContext) -> Result<()> {
     // lp_owner - use payer as LP owner
     init_data.extend_from_slice(ctx.payer.pubkey().as_ref());
 
-    // router_id - use router program ID
-    init_data.extend_from_slice(ctx.router_program_id.as_ref());
+    // router_id - the router authority PDA, which signs the router's slab CPIs
+    let (router_authority, _) = Pubkey::find_program_address(&[b"authority"], &ctx.router_program_id);
+    init_data.extend_from_slice(router_authority.as_ref());
 
     // instrument - use a dummy pubkey for test
     let instrument = Pubkey::new_unique();
@@ -53,6 +55,9 @@ pub async fn test_t01_layout_validity(ctx: &TestContext) -> Result<()> {
     // taker_fee_bps - 5 basis points
     init_data.extend_from_slice(&i64_to_le_bytes(5));
 
+    // maker_fee_bps - 1 basis point rebate
+    init_data.extend_from_slice(&i64_to_le_bytes(-1));
+
     // contract_size - 1 contract = SCALE
     init_data.extend_from_slice(&i64_to_le_bytes(SCALE));
 
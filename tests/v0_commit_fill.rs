@@ -147,7 +147,7 @@ mod commit_fill_tests {
         println!("   Notional: ${}", notional / 1_000_000);
     }
 
-    /// Test SlabState size (should be ~4KB for v0)
+    /// Test SlabState size (should be ~11.5KB for v0)
     #[test]
     fn test_slab_size() {
         use percolator_slab::state::SlabState;
@@ -155,9 +155,10 @@ mod commit_fill_tests {
 
         let size = size_of::<SlabState>();
 
-        // Should be around 4KB (between 3KB and 5KB)
+        // Book (~4KB) plus batch state and the aggressor ledger; the slab is
+        // initialized in place, so it is not limited by the SBF stack
         assert!(size > 3_000, "SlabState should be > 3KB, got {}", size);
-        assert!(size < 5_000, "SlabState should be < 5KB, got {}", size);
+        assert!(size < 12_288, "SlabState should be < 12KB, got {}", size);
 
         println!("✅ SLAB SIZE: {} bytes (~{}KB)", size, size / 1024);
     }